@@ -1,2 +1,2 @@
 -- Add down migration script here
-DELETE FROM users WHERE name="worker" OR name="boss";
+DELETE FROM users WHERE name='worker' OR name='boss';
@@ -0,0 +1,8 @@
+-- Add down migration script here
+
+ALTER TABLE items
+    ALTER COLUMN value TYPE INT
+    USING (value).amount::INT;
+
+DROP TYPE IF EXISTS money_amount;
+DROP TYPE IF EXISTS Currency;
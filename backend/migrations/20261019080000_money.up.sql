@@ -0,0 +1,12 @@
+-- Add up migration script here
+
+CREATE TYPE Currency AS ENUM ('PLN');
+
+CREATE TYPE money_amount AS (
+    amount BIGINT,
+    currency Currency
+);
+
+ALTER TABLE items
+    ALTER COLUMN value TYPE money_amount
+    USING ROW(value::BIGINT, 'PLN')::money_amount;
@@ -0,0 +1,6 @@
+-- Add down migration script here
+
+ALTER TABLE items DROP COLUMN IF EXISTS quantity_parsed;
+
+DROP TYPE IF EXISTS quantity_amount;
+DROP TYPE IF EXISTS Unit;
@@ -0,0 +1,30 @@
+-- Add up migration script here
+
+CREATE TYPE Unit AS ENUM ('szt', 'kg', 'g', 'l', 'ml', 'm', 'm2');
+
+-- amount in thousandths of the unit
+CREATE TYPE quantity_amount AS (
+    amount BIGINT,
+    unit Unit
+);
+
+ALTER TABLE items ADD COLUMN quantity_parsed quantity_amount
+    CONSTRAINT items_quantity_nonzero CHECK ((quantity_parsed).amount > 0);
+
+-- best effort for existing items, the ones that don't parse stay NULL like zero quantities,
+-- which the parser rejects
+UPDATE items
+SET quantity_parsed = ROW(
+    (replace(parsed.m[1], ',', '.')::NUMERIC * 1000)::BIGINT,
+    (CASE
+        WHEN parsed.m[2] IN ('', 'szt') THEN 'szt'
+        WHEN parsed.m[2] = 'm²' THEN 'm2'
+        ELSE parsed.m[2]
+    END)::Unit
+)::quantity_amount
+FROM (
+    SELECT id, regexp_match(lower(trim(quantity)), '^(\d+(?:[.,]\d{1,3})?)\s*(szt|kg|g|l|ml|m|m2|m²|)\.?$') AS m
+    FROM items
+) parsed
+WHERE parsed.id = items.id AND parsed.m IS NOT NULL
+    AND replace(parsed.m[1], ',', '.')::NUMERIC > 0;
@@ -0,0 +1,5 @@
+-- Add down migration script here
+
+ALTER TABLE items DROP COLUMN IF EXISTS product_id;
+
+DROP TABLE IF EXISTS products;
@@ -0,0 +1,13 @@
+-- Add up migration script here
+
+CREATE TABLE products(
+    id SERIAL PRIMARY KEY NOT NULL,
+    name VARCHAR NOT NULL,
+    default_unit Unit NOT NULL,
+    unit_price money_amount NOT NULL,
+    category VARCHAR,
+    active BOOLEAN DEFAULT true NOT NULL
+);
+
+-- name and value stay on the item as they were when it was ordered
+ALTER TABLE items ADD COLUMN product_id INT REFERENCES products(id);
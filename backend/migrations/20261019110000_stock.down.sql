@@ -0,0 +1,6 @@
+-- Add down migration script here
+
+ALTER TABLE products DROP COLUMN IF EXISTS low_stock_threshold;
+
+DROP TABLE IF EXISTS stock_movements;
+DROP TYPE IF EXISTS StockMovementKind;
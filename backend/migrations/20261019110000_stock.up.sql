@@ -0,0 +1,21 @@
+-- Add up migration script here
+
+CREATE TYPE StockMovementKind AS ENUM ('Receipt', 'Sale', 'Return', 'Adjustment');
+
+-- stock level of a product is the sum of its movements
+CREATE TABLE stock_movements(
+    id SERIAL PRIMARY KEY NOT NULL,
+    product_id INT NOT NULL,
+    item_id INT,
+    kind StockMovementKind NOT NULL,
+    amount BIGINT NOT NULL, -- thousandths of the product default_unit, negative for goods out
+    reason VARCHAR,
+    creator_id INT NOT NULL,
+    time_created TIMESTAMP NOT NULL
+);
+
+CREATE INDEX stock_movements_product_id ON stock_movements(product_id);
+CREATE INDEX stock_movements_item_id ON stock_movements(item_id);
+
+-- thousandths of the product default_unit
+ALTER TABLE products ADD COLUMN low_stock_threshold BIGINT;
@@ -0,0 +1,12 @@
+-- Add down migration script here
+
+DROP TABLE IF EXISTS status_history;
+
+ALTER TABLE orders ADD COLUMN deleted BOOLEAN NOT NULL DEFAULT false;
+ALTER TABLE orders ADD COLUMN paid BOOLEAN NOT NULL DEFAULT false;
+UPDATE orders SET deleted = (status = 'Cancelled'), paid = (status = 'Paid');
+ALTER TABLE orders ALTER COLUMN deleted DROP DEFAULT;
+ALTER TABLE orders ALTER COLUMN paid DROP DEFAULT;
+
+ALTER TABLE orders DROP COLUMN status;
+DROP TYPE IF EXISTS OrderStatus;
@@ -0,0 +1,35 @@
+-- Add up migration script here
+
+CREATE TYPE OrderStatus AS ENUM ('Draft', 'Submitted', 'InPreparation', 'Ready', 'PickedUp', 'Paid', 'Cancelled');
+
+ALTER TABLE orders ADD COLUMN status OrderStatus NOT NULL DEFAULT 'Submitted';
+
+-- fully checked orders were shown as done, so they become ready
+UPDATE orders SET status = CASE
+    WHEN deleted THEN 'Cancelled'
+    WHEN paid THEN 'Paid'
+    WHEN EXISTS (SELECT 1 FROM items WHERE items.order_id = orders.id AND items.deleted = false)
+        AND NOT EXISTS (SELECT 1 FROM items WHERE items.order_id = orders.id AND items.deleted = false AND items.checked = false)
+        THEN 'Ready'
+    ELSE 'Submitted'
+END::OrderStatus;
+
+ALTER TABLE orders DROP COLUMN deleted;
+ALTER TABLE orders DROP COLUMN paid;
+
+-- every status change, previous_status is NULL for the status an order was created with
+CREATE TABLE status_history(
+    id SERIAL PRIMARY KEY NOT NULL,
+    order_id INT NOT NULL,
+    previous_status OrderStatus,
+    status OrderStatus NOT NULL,
+    user_id INT NOT NULL,
+    time_created TIMESTAMP NOT NULL
+);
+
+CREATE INDEX status_history_order_id ON status_history(order_id);
+
+-- orders from before the history get the row they would have been created with, in their
+-- current status
+INSERT INTO status_history (order_id, previous_status, status, user_id, time_created)
+SELECT id, NULL, status, creator_id, time_created FROM orders ORDER BY id;
@@ -0,0 +1,5 @@
+-- Add down migration script here
+
+ALTER TABLE orders DROP COLUMN picked_up;
+DROP TABLE IF EXISTS payments;
+DROP TYPE IF EXISTS PaymentMethod;
@@ -0,0 +1,52 @@
+-- Add up migration script here
+
+CREATE TYPE PaymentMethod AS ENUM ('Cash', 'Card', 'Transfer');
+
+-- refunds are negative payments
+CREATE TABLE payments(
+    id SERIAL PRIMARY KEY NOT NULL,
+    order_id INT NOT NULL,
+    amount money_amount NOT NULL,
+    method PaymentMethod NOT NULL,
+    note VARCHAR,
+    creator_id INT NOT NULL,
+    time_created TIMESTAMP NOT NULL
+);
+
+CREATE INDEX payments_order_id ON payments(order_id);
+
+-- orders marked as paid get a payment of their total so their status still derives to paid,
+-- unparsed quantities count their digits like the order view does. Times are the shop's wall
+-- clock until the timestamptz migration. Orders with nothing to pay get no payment, payments
+-- move some money. They stay paid by their status alone, a payment of 0 wouldn't cover them
+-- either, until an item leaves something to pay
+INSERT INTO payments (order_id, amount, method, note, creator_id, time_created)
+SELECT
+    id,
+    ROW(total, 'PLN')::money_amount,
+    'Cash',
+    'przeniesione z oznaczenia opłacone',
+    creator_id,
+    now() AT TIME ZONE 'Europe/Warsaw'
+FROM (
+    SELECT
+        orders.id,
+        orders.creator_id,
+        COALESCE(SUM(ROUND(
+            (items.value).amount::NUMERIC * COALESCE(
+                (items.quantity_parsed).amount,
+                NULLIF(regexp_replace(items.quantity, '\D', '', 'g'), '')::BIGINT * 1000,
+                0
+            ) / 1000
+        )), 0)::BIGINT AS total
+    FROM orders
+    LEFT JOIN items ON items.order_id = orders.id AND items.deleted = false
+    WHERE orders.status = 'Paid'
+    GROUP BY orders.id
+) AS paid
+WHERE total <> 0;
+
+-- goods leave the shop when the order is picked up, paying doesn't move them. Orders paid so far
+-- were handed over with the payment, so they count as picked up
+ALTER TABLE orders ADD COLUMN picked_up BOOLEAN NOT NULL DEFAULT false;
+UPDATE orders SET picked_up = true WHERE status IN ('PickedUp', 'Paid');
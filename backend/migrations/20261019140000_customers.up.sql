@@ -0,0 +1,27 @@
+-- Add up migration script here
+
+CREATE TABLE customers(
+    id SERIAL PRIMARY KEY NOT NULL,
+    name VARCHAR NOT NULL,
+    phone VARCHAR,
+    email VARCHAR,
+    nip VARCHAR,
+    notes VARCHAR,
+    merged_into INT, -- duplicates point to the customer they were merged into
+    time_created TIMESTAMP NOT NULL
+);
+
+ALTER TABLE orders ADD COLUMN customer_id INT REFERENCES customers(id);
+CREATE INDEX orders_customer_id ON orders(customer_id);
+
+-- one customer per receiver ignoring case and surrounding spaces, named like the latest order.
+-- Times are the shop's wall clock until the timestamptz migration
+INSERT INTO customers (name, time_created)
+SELECT DISTINCT ON (lower(trim(receiver))) trim(receiver), now() AT TIME ZONE 'Europe/Warsaw'
+FROM orders
+WHERE trim(receiver) <> ''
+ORDER BY lower(trim(receiver)), time_created DESC;
+
+UPDATE orders SET customer_id = customers.id
+FROM customers
+WHERE lower(trim(orders.receiver)) = lower(customers.name);
@@ -0,0 +1,5 @@
+-- Add down migration script here
+
+DROP INDEX orders_number;
+ALTER TABLE orders DROP COLUMN number;
+DROP TABLE order_number_counters;
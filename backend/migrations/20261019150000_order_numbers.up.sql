@@ -0,0 +1,29 @@
+-- Add up migration script here
+
+-- last number given out in each year, the row lock serializes concurrent order creation
+CREATE TABLE order_number_counters(
+    year INT PRIMARY KEY NOT NULL,
+    last_number INT NOT NULL
+);
+
+ALTER TABLE orders ADD COLUMN number VARCHAR;
+
+-- existing orders are numbered in the order they were created
+UPDATE orders SET number = numbered.number
+FROM (
+    SELECT
+        id,
+        'ZAM/' || EXTRACT(YEAR FROM time_created)::INT || '/'
+            || lpad((ROW_NUMBER() OVER (PARTITION BY EXTRACT(YEAR FROM time_created) ORDER BY id))::TEXT, 5, '0')
+            AS number
+    FROM orders
+) AS numbered
+WHERE orders.id = numbered.id;
+
+INSERT INTO order_number_counters (year, last_number)
+SELECT EXTRACT(YEAR FROM time_created)::INT, COUNT(*)
+FROM orders
+GROUP BY EXTRACT(YEAR FROM time_created);
+
+ALTER TABLE orders ALTER COLUMN number SET NOT NULL;
+CREATE UNIQUE INDEX orders_number ON orders(number);
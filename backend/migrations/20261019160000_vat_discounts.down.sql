@@ -0,0 +1,12 @@
+-- Add down migration script here
+
+ALTER TABLE orders
+    DROP COLUMN discount_percent,
+    DROP COLUMN discount_amount;
+
+ALTER TABLE items
+    DROP COLUMN vat_rate,
+    DROP COLUMN discount_percent,
+    DROP COLUMN discount_amount;
+
+DROP TYPE VatRate;
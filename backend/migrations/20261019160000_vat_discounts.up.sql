@@ -0,0 +1,17 @@
+-- Add up migration script here
+
+CREATE TYPE VatRate AS ENUM ('23', '8', '5', '0');
+
+-- item values stay gross prices, at most one of the discount columns is set
+ALTER TABLE items
+    ADD COLUMN vat_rate VatRate NOT NULL DEFAULT '23',
+    ADD COLUMN discount_percent BIGINT, -- hundredths of a percent
+    ADD COLUMN discount_amount money_amount,
+    ADD CONSTRAINT items_single_discount
+        CHECK (discount_percent IS NULL OR discount_amount IS NULL);
+
+ALTER TABLE orders
+    ADD COLUMN discount_percent BIGINT,
+    ADD COLUMN discount_amount money_amount,
+    ADD CONSTRAINT orders_single_discount
+        CHECK (discount_percent IS NULL OR discount_amount IS NULL);
@@ -0,0 +1,8 @@
+-- Add down migration script here
+
+DROP TABLE invoice_rates;
+DROP TABLE invoice_lines;
+DROP TABLE invoices;
+DROP FUNCTION invoices_immutable;
+DROP TABLE invoice_number_counters;
+DROP TYPE InvoiceKind;
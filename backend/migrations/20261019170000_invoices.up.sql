@@ -0,0 +1,76 @@
+-- Add up migration script here
+
+CREATE TYPE InvoiceKind AS ENUM ('Invoice', 'Correction');
+
+-- last number given out per series (FV, FK) and year
+CREATE TABLE invoice_number_counters(
+    series VARCHAR NOT NULL,
+    year INT NOT NULL,
+    last_number INT NOT NULL,
+    PRIMARY KEY (series, year)
+);
+
+-- seller and buyer data are copied, an issued invoice doesn't follow later changes
+CREATE TABLE invoices(
+    id SERIAL PRIMARY KEY NOT NULL,
+    number VARCHAR NOT NULL,
+    kind InvoiceKind NOT NULL,
+    order_id INT NOT NULL REFERENCES orders(id),
+    corrected_id INT, -- the document a correction corrects
+    correction_reason VARCHAR,
+    issue_date DATE NOT NULL,
+    sale_date DATE NOT NULL,
+    seller_name VARCHAR NOT NULL,
+    seller_address VARCHAR NOT NULL,
+    seller_nip VARCHAR NOT NULL,
+    buyer_name VARCHAR NOT NULL,
+    buyer_address VARCHAR,
+    buyer_nip VARCHAR,
+    net money_amount NOT NULL,
+    vat money_amount NOT NULL,
+    gross money_amount NOT NULL,
+    creator_id INT NOT NULL,
+    time_created TIMESTAMP NOT NULL
+);
+CREATE UNIQUE INDEX invoices_number ON invoices(number);
+-- an order is invoiced once, later changes are corrections
+CREATE UNIQUE INDEX invoices_order_id ON invoices(order_id) WHERE kind = 'Invoice';
+-- only the latest document of a chain can be corrected
+CREATE UNIQUE INDEX invoices_corrected_id ON invoices(corrected_id);
+
+CREATE TABLE invoice_lines(
+    id SERIAL PRIMARY KEY NOT NULL,
+    invoice_id INT NOT NULL,
+    position INT NOT NULL,
+    name VARCHAR NOT NULL,
+    quantity VARCHAR NOT NULL,
+    unit_price money_amount NOT NULL, -- gross
+    vat_rate VatRate NOT NULL,
+    discount money_amount NOT NULL,
+    net money_amount NOT NULL,
+    vat money_amount NOT NULL,
+    gross money_amount NOT NULL
+);
+CREATE INDEX invoice_lines_invoice_id ON invoice_lines(invoice_id);
+
+CREATE TABLE invoice_rates(
+    invoice_id INT NOT NULL,
+    vat_rate VatRate NOT NULL,
+    net money_amount NOT NULL,
+    vat money_amount NOT NULL,
+    gross money_amount NOT NULL,
+    PRIMARY KEY (invoice_id, vat_rate)
+);
+
+CREATE FUNCTION invoices_immutable() RETURNS trigger AS $$
+BEGIN
+    RAISE EXCEPTION 'issued invoices can not be changed, issue a correction instead';
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER invoices_immutable BEFORE UPDATE OR DELETE ON invoices
+    FOR EACH ROW EXECUTE FUNCTION invoices_immutable();
+CREATE TRIGGER invoice_lines_immutable BEFORE UPDATE OR DELETE ON invoice_lines
+    FOR EACH ROW EXECUTE FUNCTION invoices_immutable();
+CREATE TRIGGER invoice_rates_immutable BEFORE UPDATE OR DELETE ON invoice_rates
+    FOR EACH ROW EXECUTE FUNCTION invoices_immutable();
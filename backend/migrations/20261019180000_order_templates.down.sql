@@ -0,0 +1,4 @@
+-- Add down migration script here
+
+DROP TABLE order_template_items;
+DROP TABLE order_templates;
@@ -0,0 +1,26 @@
+-- Add up migration script here
+
+CREATE TABLE order_templates(
+    id SERIAL PRIMARY KEY NOT NULL,
+    name VARCHAR NOT NULL,
+    receiver VARCHAR NOT NULL,
+    additional_info VARCHAR,
+    customer_id INT,
+    creator_id INT NOT NULL,
+    time_created TIMESTAMP NOT NULL
+);
+
+-- copies of the items of the order the template was made from
+CREATE TABLE order_template_items(
+    id SERIAL PRIMARY KEY NOT NULL,
+    template_id INT NOT NULL,
+    quantity VARCHAR NOT NULL,
+    product_id INT,
+    name VARCHAR NOT NULL,
+    value money_amount NOT NULL,
+    additional_info VARCHAR,
+    vat_rate VatRate NOT NULL,
+    discount_percent BIGINT,
+    discount_amount money_amount
+);
+CREATE INDEX order_template_items_template_id ON order_template_items(template_id);
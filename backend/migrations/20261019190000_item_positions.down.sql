@@ -0,0 +1,4 @@
+-- Add down migration script here
+
+DROP INDEX items_order_id_position;
+ALTER TABLE items DROP COLUMN position;
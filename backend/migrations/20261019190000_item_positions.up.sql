@@ -0,0 +1,9 @@
+-- Add up migration script here
+
+-- items keep the order they were added in until they are reordered
+ALTER TABLE items ADD COLUMN position INT NOT NULL DEFAULT 0;
+UPDATE items SET position = numbered.position
+FROM (SELECT id, ROW_NUMBER() OVER (PARTITION BY order_id ORDER BY id) AS position FROM items) AS numbered
+WHERE items.id = numbered.id;
+ALTER TABLE items ALTER COLUMN position DROP DEFAULT;
+CREATE INDEX items_order_id_position ON items(order_id, position);
@@ -0,0 +1,7 @@
+-- Add down migration script here
+
+DROP TRIGGER items_version ON items;
+DROP TRIGGER orders_version ON orders;
+DROP FUNCTION bump_version;
+ALTER TABLE items DROP COLUMN version;
+ALTER TABLE orders DROP COLUMN version;
@@ -0,0 +1,26 @@
+-- Add up migration script here
+
+-- optimistic locking, clients send back the version they read with every change
+ALTER TABLE orders ADD COLUMN version INT NOT NULL DEFAULT 1;
+ALTER TABLE items ADD COLUMN version INT NOT NULL DEFAULT 1;
+
+-- the version guards what clients edit. Columns the backend keeps up to date itself are passed
+-- to the trigger, changing only those doesn't move the version, which clients holding an older
+-- copy would get a conflict for. updated_by and updated_at follow every change, so they never
+-- count as one
+CREATE FUNCTION bump_version() RETURNS trigger AS $$
+DECLARE
+    derived TEXT[] := COALESCE(TG_ARGV, '{}') || '{updated_by,updated_at}'::TEXT[];
+BEGIN
+    IF to_jsonb(NEW) - derived <> to_jsonb(OLD) - derived THEN
+        NEW.version := OLD.version + 1;
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER orders_version BEFORE UPDATE ON orders
+    FOR EACH ROW WHEN (OLD.* IS DISTINCT FROM NEW.*)
+    EXECUTE FUNCTION bump_version('status', 'picked_up');
+CREATE TRIGGER items_version BEFORE UPDATE ON items
+    FOR EACH ROW WHEN (OLD.* IS DISTINCT FROM NEW.*) EXECUTE FUNCTION bump_version();
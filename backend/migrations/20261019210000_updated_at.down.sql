@@ -0,0 +1,12 @@
+-- Add down migration script here
+
+DROP TRIGGER items_updated ON items;
+DROP TRIGGER orders_updated ON orders;
+DROP FUNCTION stamp_updated;
+DROP TRIGGER items_created ON items;
+DROP TRIGGER orders_created ON orders;
+DROP FUNCTION created_is_updated;
+DROP INDEX items_updated_at;
+DROP INDEX orders_updated_at;
+ALTER TABLE items DROP COLUMN updated_at, DROP COLUMN updated_by;
+ALTER TABLE orders DROP COLUMN updated_at, DROP COLUMN updated_by;
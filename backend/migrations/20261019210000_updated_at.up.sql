@@ -0,0 +1,44 @@
+-- Add up migration script here
+
+ALTER TABLE orders ADD COLUMN updated_at TIMESTAMP, ADD COLUMN updated_by INT;
+UPDATE orders SET updated_at = time_created, updated_by = creator_id;
+ALTER TABLE orders ALTER COLUMN updated_at SET NOT NULL, ALTER COLUMN updated_by SET NOT NULL;
+ALTER TABLE items ADD COLUMN updated_at TIMESTAMP, ADD COLUMN updated_by INT;
+UPDATE items SET updated_at = time_created, updated_by = creator_id;
+ALTER TABLE items ALTER COLUMN updated_at SET NOT NULL, ALTER COLUMN updated_by SET NOT NULL;
+CREATE INDEX orders_updated_at ON orders(updated_at);
+CREATE INDEX items_updated_at ON items(updated_at);
+
+-- new rows count as updated by their creator
+CREATE FUNCTION created_is_updated() RETURNS trigger AS $$
+BEGIN
+    NEW.updated_at := COALESCE(NEW.updated_at, NEW.time_created);
+    NEW.updated_by := COALESCE(NEW.updated_by, NEW.creator_id);
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER orders_created BEFORE INSERT ON orders
+    FOR EACH ROW EXECUTE FUNCTION created_is_updated();
+CREATE TRIGGER items_created BEFORE INSERT ON items
+    FOR EACH ROW EXECUTE FUNCTION created_is_updated();
+
+-- the backend names the user in updated_by, the time follows here. It is the time of the change,
+-- not the start of its transaction, so a long transaction doesn't stamp its changes earlier than
+-- the ones clients already synced past. Saving without changing anything keeps the previous
+-- author. Fires before the version triggers, which leave both columns out
+CREATE FUNCTION stamp_updated() RETURNS trigger AS $$
+BEGIN
+    IF to_jsonb(NEW) - 'updated_by' = to_jsonb(OLD) - 'updated_by' THEN
+        NEW.updated_by := OLD.updated_by;
+        RETURN NEW;
+    END IF;
+    NEW.updated_at := clock_timestamp();
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER orders_updated BEFORE UPDATE ON orders
+    FOR EACH ROW WHEN (OLD.* IS DISTINCT FROM NEW.*) EXECUTE FUNCTION stamp_updated();
+CREATE TRIGGER items_updated BEFORE UPDATE ON items
+    FOR EACH ROW WHEN (OLD.* IS DISTINCT FROM NEW.*) EXECUTE FUNCTION stamp_updated();
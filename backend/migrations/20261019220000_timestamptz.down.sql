@@ -0,0 +1,13 @@
+-- Add down migration script here
+
+SET LOCAL TimeZone = 'Europe/Warsaw';
+ALTER TABLE orders ALTER COLUMN time_created TYPE TIMESTAMP;
+ALTER TABLE orders ALTER COLUMN updated_at TYPE TIMESTAMP;
+ALTER TABLE items ALTER COLUMN time_created TYPE TIMESTAMP;
+ALTER TABLE items ALTER COLUMN updated_at TYPE TIMESTAMP;
+ALTER TABLE status_history ALTER COLUMN time_created TYPE TIMESTAMP;
+ALTER TABLE payments ALTER COLUMN time_created TYPE TIMESTAMP;
+ALTER TABLE customers ALTER COLUMN time_created TYPE TIMESTAMP;
+ALTER TABLE stock_movements ALTER COLUMN time_created TYPE TIMESTAMP;
+ALTER TABLE invoices ALTER COLUMN time_created TYPE TIMESTAMP;
+ALTER TABLE order_templates ALTER COLUMN time_created TYPE TIMESTAMP;
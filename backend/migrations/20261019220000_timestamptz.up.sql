@@ -0,0 +1,14 @@
+-- Add up migration script here
+
+-- times were written in the local time of the server, which ran in the shop's timezone
+SET LOCAL TimeZone = 'Europe/Warsaw';
+ALTER TABLE orders ALTER COLUMN time_created TYPE TIMESTAMPTZ;
+ALTER TABLE orders ALTER COLUMN updated_at TYPE TIMESTAMPTZ;
+ALTER TABLE items ALTER COLUMN time_created TYPE TIMESTAMPTZ;
+ALTER TABLE items ALTER COLUMN updated_at TYPE TIMESTAMPTZ;
+ALTER TABLE status_history ALTER COLUMN time_created TYPE TIMESTAMPTZ;
+ALTER TABLE payments ALTER COLUMN time_created TYPE TIMESTAMPTZ;
+ALTER TABLE customers ALTER COLUMN time_created TYPE TIMESTAMPTZ;
+ALTER TABLE stock_movements ALTER COLUMN time_created TYPE TIMESTAMPTZ;
+ALTER TABLE invoices ALTER COLUMN time_created TYPE TIMESTAMPTZ;
+ALTER TABLE order_templates ALTER COLUMN time_created TYPE TIMESTAMPTZ;
@@ -0,0 +1,3 @@
+-- Add down migration script here
+-- renamed users keep their new names, the deleted demo users come back with `backend seed-demo`
+ALTER TABLE users DROP CONSTRAINT users_name_key;
@@ -0,0 +1,32 @@
+-- Add up migration script here
+
+-- 20240827151606_mock_data created these for development on every database. Accounts still
+-- on the demo password go, `backend seed-demo` creates them again where they are wanted
+DELETE FROM users WHERE name IN ('worker', 'boss') AND password = '123';
+
+-- logins are looked up by name. Of the accounts sharing a name the oldest keeps it, the others
+-- get their id appended and are listed in the log
+DO $$
+DECLARE
+    renamed TEXT;
+    clashing TEXT;
+BEGIN
+    WITH changed AS (
+        UPDATE users SET name = name || '-' || id
+        WHERE EXISTS (SELECT 1 FROM users older WHERE older.name = users.name AND older.id < users.id)
+        RETURNING id, name
+    )
+    SELECT string_agg(format('%s (id %s)', name, id), ', ' ORDER BY id) INTO renamed FROM changed;
+    IF renamed IS NOT NULL THEN
+        RAISE WARNING 'users sharing a name were renamed to %', renamed;
+    END IF;
+
+    SELECT string_agg(DISTINCT name, ', ') INTO clashing
+    FROM users WHERE name IN (SELECT name FROM users GROUP BY name HAVING COUNT(*) > 1);
+    IF clashing IS NOT NULL THEN
+        RAISE EXCEPTION 'user names % are still taken more than once, rename them and migrate again', clashing;
+    END IF;
+END;
+$$;
+
+ALTER TABLE users ADD CONSTRAINT users_name_key UNIQUE (name);
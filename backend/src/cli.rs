@@ -0,0 +1,446 @@
+// Command line of the backend binary: the server and the admin tools around its database.
+// Without a subcommand the server is started, like before there were any.
+
+use std::{io::BufRead, path::PathBuf, process::Command as Process};
+
+use anyhow::{anyhow, bail, Context};
+use clap::{Parser, Subcommand};
+use sqlx::migrate::Migrate;
+use tracing::info;
+
+use crate::{
+    config::ConfigArgs,
+    models::user::{hash_password, Privileges},
+    Db, MIGRATOR,
+};
+
+#[derive(Parser)]
+#[command(version, about = "Backend of the paint shop orders")]
+pub struct Cli {
+    #[command(flatten)]
+    pub config: ConfigArgs,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    #[command(about = "Migrate the database and serve the API and frontend (default)")]
+    Serve,
+    #[command(subcommand, about = "Apply, revert or list database migrations")]
+    Migrate(MigrateCommand),
+    #[command(subcommand, about = "Manage users")]
+    User(UserCommand),
+    #[command(about = "Dump the database with pg_dump")]
+    Backup { file: PathBuf },
+    #[command(about = "Replace the database contents with a dump made by backup")]
+    Restore { file: PathBuf },
+    #[command(about = "Add the demo users worker/123 and boss/123")]
+    SeedDemo,
+}
+
+#[derive(Subcommand)]
+pub enum MigrateCommand {
+    #[command(about = "Apply all pending migrations")]
+    Up,
+    #[command(about = "Revert the last migration, or all after --to")]
+    Down {
+        #[arg(long, help = "version to go back to")]
+        to: Option<i64>,
+    },
+    #[command(about = "List migrations and whether they are applied")]
+    Status,
+}
+
+#[derive(Subcommand)]
+pub enum UserCommand {
+    #[command(about = "Add a user, the password is read from stdin unless given")]
+    Create {
+        name: String,
+        #[arg(long, default_value = "basic", value_parser = parse_privileges, help = "basic or full")]
+        privileges: Privileges,
+        #[arg(long)]
+        password: Option<String>,
+    },
+    #[command(about = "Change the password of a user, read from stdin unless given")]
+    Passwd {
+        name: String,
+        #[arg(long)]
+        password: Option<String>,
+    },
+    #[command(about = "List users")]
+    List,
+}
+
+fn parse_privileges(s: &str) -> Result<Privileges, String> {
+    match s.to_lowercase().as_str() {
+        "basic" => Ok(Privileges::Basic),
+        "full" => Ok(Privileges::Full),
+        _ => Err("expected basic or full".to_owned()),
+    }
+}
+
+// --- migrations
+
+pub async fn migrate_up(db: &Db) -> anyhow::Result<()> {
+    MIGRATOR.run(db).await.context("migrating the database")?;
+    Ok(())
+}
+
+pub async fn migrate(command: MigrateCommand, db: &Db) -> anyhow::Result<()> {
+    match command {
+        MigrateCommand::Up => {
+            migrate_up(db).await?;
+            println!("database is up to date");
+        }
+        MigrateCommand::Down { to } => {
+            let mut applied = applied_versions(db).await?;
+            let Some(last) = applied.pop() else {
+                bail!("no migrations are applied");
+            };
+            // undo reverts everything newer than the target
+            let target = to.unwrap_or(applied.last().copied().unwrap_or(0));
+            if target >= last {
+                bail!("--to {target} is not before the last applied migration {last}");
+            }
+            MIGRATOR.undo(db, target).await?;
+            println!("reverted migrations after {target}");
+        }
+        MigrateCommand::Status => {
+            let mut conn = db.acquire().await?;
+            conn.ensure_migrations_table().await?;
+            let applied = conn.list_applied_migrations().await?;
+            for migration in MIGRATOR
+                .iter()
+                .filter(|m| m.migration_type.is_up_migration())
+            {
+                let status = match applied.iter().find(|a| a.version == migration.version) {
+                    None => "pending",
+                    Some(a) if a.checksum != migration.checksum => "applied, modified since",
+                    Some(_) => "applied",
+                };
+                println!(
+                    "{} {:<24} {status}",
+                    migration.version, migration.description
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn applied_versions(db: &Db) -> anyhow::Result<Vec<i64>> {
+    let mut conn = db.acquire().await?;
+    conn.ensure_migrations_table().await?;
+    let mut versions: Vec<i64> = conn
+        .list_applied_migrations()
+        .await?
+        .into_iter()
+        .map(|applied| applied.version)
+        .collect();
+    versions.sort();
+    Ok(versions)
+}
+
+// --- users
+
+pub async fn user(command: UserCommand, db: &Db) -> anyhow::Result<()> {
+    match command {
+        UserCommand::Create {
+            name,
+            privileges,
+            password,
+        } => {
+            let password = password_or_stdin(password)?;
+            let id = create_user(db, &name, &password, privileges).await?;
+            println!("created user {name} with id {id}");
+        }
+        UserCommand::Passwd { name, password } => {
+            let password = password_or_stdin(password)?;
+            set_password(db, &name, &password).await?;
+            println!("changed the password of {name}");
+        }
+        UserCommand::List => {
+            for (id, name, privileges) in list_users(db).await? {
+                let privileges = match privileges {
+                    Privileges::Basic => "basic",
+                    Privileges::Full => "full",
+                };
+                println!("{id:>4} {name:<24} {privileges}");
+            }
+        }
+    }
+    Ok(())
+}
+
+fn password_or_stdin(password: Option<String>) -> anyhow::Result<String> {
+    let password = match password {
+        Some(password) => password,
+        None => {
+            eprint!("password: ");
+            let mut line = String::new();
+            std::io::stdin().lock().read_line(&mut line)?;
+            line.trim_end_matches(['\r', '\n']).to_owned()
+        }
+    };
+    if password.is_empty() {
+        bail!("the password can't be empty");
+    }
+    Ok(password)
+}
+
+// create_user found the name taken
+#[derive(Debug)]
+pub struct UserExists(pub String);
+
+impl std::fmt::Display for UserExists {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "user {} already exists", self.0)
+    }
+}
+
+impl std::error::Error for UserExists {}
+
+pub async fn create_user(
+    db: &Db,
+    name: &str,
+    password: &str,
+    privileges: Privileges,
+) -> anyhow::Result<i32> {
+    if name.trim().is_empty() {
+        bail!("the name can't be empty");
+    }
+    let created: Result<(i32,), sqlx::Error> = sqlx::query_as(
+        "INSERT INTO users (name,password,privileges) VALUES ($1,$2,$3) RETURNING id",
+    )
+    .bind(name)
+    .bind(hash_password(password)?)
+    .bind(privileges)
+    .fetch_one(db)
+    .await;
+    match created {
+        Ok((id,)) => Ok(id),
+        // names are unique, logins are looked up by them
+        Err(sqlx::Error::Database(err)) if err.is_unique_violation() => {
+            Err(UserExists(name.to_owned()).into())
+        }
+        Err(err) => Err(err.into()),
+    }
+}
+
+pub async fn set_password(db: &Db, name: &str, password: &str) -> anyhow::Result<()> {
+    let result = sqlx::query("UPDATE users SET password=$1 WHERE name=$2")
+        .bind(hash_password(password)?)
+        .bind(name)
+        .execute(db)
+        .await?;
+    if result.rows_affected() == 0 {
+        bail!("no user named {name}");
+    }
+    Ok(())
+}
+
+pub async fn list_users(db: &Db) -> anyhow::Result<Vec<(i32, String, Privileges)>> {
+    let users = sqlx::query_as("SELECT id,name,privileges FROM users ORDER BY id")
+        .fetch_all(db)
+        .await?;
+    Ok(users)
+}
+
+// demo data for development, never part of the migrations
+pub async fn seed_demo(db: &Db) -> anyhow::Result<()> {
+    for (name, privileges) in [("worker", Privileges::Basic), ("boss", Privileges::Full)] {
+        match create_user(db, name, "123", privileges).await {
+            Ok(_) => info!("created user {name}"),
+            Err(err) if err.is::<UserExists>() => info!("user {name} already exists"),
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(())
+}
+
+// --- backups
+
+pub fn backup(database_url: &str, file: &PathBuf) -> anyhow::Result<()> {
+    run(Process::new("pg_dump")
+        .args(["--format=custom", "--dbname", database_url, "--file"])
+        .arg(file))?;
+    println!("saved the database to {}", file.display());
+    Ok(())
+}
+
+pub fn restore(database_url: &str, file: &PathBuf) -> anyhow::Result<()> {
+    if !file.exists() {
+        bail!("{} doesn't exist", file.display());
+    }
+    run(Process::new("pg_restore")
+        .args([
+            "--clean",
+            "--if-exists",
+            "--no-owner",
+            "--single-transaction",
+        ])
+        .args(["--dbname", database_url])
+        .arg(file))?;
+    println!("restored the database from {}", file.display());
+    Ok(())
+}
+
+fn run(process: &mut Process) -> anyhow::Result<()> {
+    let program = process.get_program().to_string_lossy().into_owned();
+    let status = process
+        .status()
+        .with_context(|| format!("starting {program}, is it installed?"))?;
+    if !status.success() {
+        return Err(anyhow!("{program} failed with {status}"));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{order::OrderStatus, user::User};
+    use anyhow::Result;
+
+    #[sqlx::test]
+    async fn cli_migrations_have_no_users(pool: Db) -> Result<()> {
+        assert!(list_users(&pool).await?.is_empty());
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn cli_migrate_down_to_nothing_and_up(pool: Db) -> Result<()> {
+        migrate(MigrateCommand::Down { to: Some(0) }, &pool).await?;
+        assert!(applied_versions(&pool).await?.is_empty());
+        let (tables,): (i64,) = sqlx::query_as(
+            "SELECT COUNT(*) FROM pg_tables WHERE schemaname='public' AND tablename<>'_sqlx_migrations'",
+        )
+        .fetch_one(&pool)
+        .await?;
+        assert_eq!(tables, 0);
+
+        migrate(MigrateCommand::Up, &pool).await?;
+        let expected = MIGRATOR.iter().map(|m| m.version).max();
+        assert_eq!(applied_versions(&pool).await?.last().copied(), expected);
+        Ok(())
+    }
+
+    // rows the migrations add to tables of old databases are stamped with the time they ran
+    #[sqlx::test]
+    async fn cli_migrated_times(pool: Db) -> Result<()> {
+        migrate(
+            MigrateCommand::Down {
+                to: Some(20261019120000),
+            },
+            &pool,
+        )
+        .await?;
+        sqlx::query(
+            "
+                WITH paid AS (
+                    INSERT INTO orders (creator_id, time_created, receiver, status)
+                    VALUES (1, now() AT TIME ZONE 'Europe/Warsaw', 'tomek', 'Paid')
+                    RETURNING id
+                )
+                INSERT INTO items
+                    (order_id, creator_id, time_created, quantity, name, value, deleted)
+                SELECT id, 1, now() AT TIME ZONE 'Europe/Warsaw', '2', 'farba',
+                    ROW(3000, 'PLN')::money_amount, false
+                FROM paid
+            ",
+        )
+        .execute(&pool)
+        .await?;
+        migrate(MigrateCommand::Up, &pool).await?;
+
+        for table in ["payments", "customers"] {
+            let (seconds,): (f64,) = sqlx::query_as(&format!(
+                "SELECT extract(epoch FROM now() - time_created)::FLOAT8 FROM {table}"
+            ))
+            .fetch_one(&pool)
+            .await?;
+            assert!(seconds.abs() < 60.0, "{table} {seconds}s off");
+        }
+        Ok(())
+    }
+
+    // payments move some money, paid orders with nothing to pay stay paid without one
+    #[sqlx::test]
+    async fn cli_migrated_paid_orders_without_total(pool: Db) -> Result<()> {
+        migrate(
+            MigrateCommand::Down {
+                to: Some(20261019120000),
+            },
+            &pool,
+        )
+        .await?;
+        sqlx::query(
+            "
+                WITH paid AS (
+                    INSERT INTO orders (creator_id, time_created, receiver, status)
+                    VALUES (1, '2024-05-01 12:00', 'tomek', 'Paid'),
+                        (1, '2024-05-01 12:00', 'ania', 'Paid')
+                    RETURNING id
+                )
+                INSERT INTO items
+                    (order_id, creator_id, time_created, quantity, name, value, deleted)
+                SELECT MAX(id), 1, '2024-05-01 12:00', 'dużo', 'farba',
+                    ROW(3000, 'PLN')::money_amount, false
+                FROM paid
+            ",
+        )
+        .execute(&pool)
+        .await?;
+        migrate(MigrateCommand::Up, &pool).await?;
+
+        let (payments,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM payments")
+            .fetch_one(&pool)
+            .await?;
+        assert_eq!(payments, 0);
+        let statuses: Vec<(OrderStatus,)> = sqlx::query_as("SELECT status FROM orders")
+            .fetch_all(&pool)
+            .await?;
+        assert_eq!(statuses, [(OrderStatus::Paid,), (OrderStatus::Paid,)]);
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn cli_user_create_and_passwd(pool: Db) -> Result<()> {
+        let id = create_user(&pool, "ania", "tajne", Privileges::Full).await?;
+        let should_err = create_user(&pool, "ania", "inne", Privileges::Basic).await;
+        assert!(should_err.is_err_and(|err| err.is::<UserExists>()));
+        assert!(create_user(&pool, " ", "inne", Privileges::Basic)
+            .await
+            .is_err());
+
+        set_password(&pool, "ania", "nowe").await?;
+        assert!(set_password(&pool, "ola", "nowe").await.is_err());
+
+        let user: User = sqlx::query_as("SELECT * FROM users WHERE id=$1")
+            .bind(id)
+            .fetch_one(&pool)
+            .await?;
+        assert!(user.password_is_hashed());
+        assert!(user.password_matches("nowe"));
+
+        let users = list_users(&pool).await?;
+        assert_eq!(users.len(), 1);
+        assert!(matches!(users[0], (_, ref name, Privileges::Full) if name == "ania"));
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn cli_seed_demo_twice(pool: Db) -> Result<()> {
+        seed_demo(&pool).await?;
+        seed_demo(&pool).await?;
+
+        let names: Vec<String> = list_users(&pool).await?.into_iter().map(|u| u.1).collect();
+        assert_eq!(names, ["worker", "boss"]);
+
+        // only taken names are skipped
+        pool.close().await;
+        assert!(seed_demo(&pool).await.is_err());
+        Ok(())
+    }
+}
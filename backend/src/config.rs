@@ -0,0 +1,484 @@
+// Server configuration, read once at startup.
+// Each key is looked up on the command line, then in the environment (.env included),
+// then in the config file and falls back to its default. All bad keys are reported at once.
+
+use std::{
+    collections::BTreeMap,
+    fmt::Display,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Duration,
+};
+
+use axum::http::HeaderValue;
+use chrono_tz::Tz;
+use tower_cookies::cookie::SameSite;
+
+use crate::models::{
+    receipt::{ReceiptLayout, ShopInfo},
+    time::DEFAULT_TIMEZONE,
+};
+
+// used when neither --config nor CONFIG_FILE is given, may not exist
+const DEFAULT_CONFIG_FILE: &str = "backend.toml";
+
+#[derive(clap::Args, Clone, Debug, Default)]
+pub struct ConfigArgs {
+    #[arg(
+        long,
+        env = "CONFIG_FILE",
+        help = "TOML file with the keys below in lowercase"
+    )]
+    pub config: Option<PathBuf>,
+    #[arg(long, env = "DATABASE_URL", hide_env_values = true)]
+    pub database_url: Option<String>,
+    #[arg(
+        long,
+        env = "SERVER_FULL_ADDRESS",
+        help = "host:port to listen on, required to serve"
+    )]
+    pub server_full_address: Option<String>,
+    #[arg(long, env = "DB_POOL_SIZE", help = "[default: 10]")]
+    pub db_pool_size: Option<String>,
+    #[arg(long, env = "STATIC_DIR", help = "built frontend [default: ./dist]")]
+    pub static_dir: Option<String>,
+    #[arg(
+        long,
+        env = "CORS_ORIGINS",
+        help = "comma separated, * allows any [default: *]"
+    )]
+    pub cors_origins: Option<String>,
+    #[arg(long, env = "COOKIE_SECURE", help = "[default: false]")]
+    pub cookie_secure: Option<String>,
+    #[arg(
+        long,
+        env = "COOKIE_SAME_SITE",
+        help = "strict, lax or none [default: lax]"
+    )]
+    pub cookie_same_site: Option<String>,
+    #[arg(long, env = "TOKEN_LIFETIME_HOURS", help = "[default: 2]")]
+    pub token_lifetime_hours: Option<String>,
+    #[arg(
+        long,
+        env = "DRAIN_TIMEOUT_SECONDS",
+        help = "wait for requests on shutdown [default: 30]"
+    )]
+    pub drain_timeout_seconds: Option<String>,
+    #[arg(long, env = "LOG_FORMAT", help = "pretty or json [default: pretty]")]
+    pub log_format: Option<String>,
+    #[arg(long, env = "SHOP_NAME")]
+    pub shop_name: Option<String>,
+    #[arg(long, env = "SHOP_ADDRESS")]
+    pub shop_address: Option<String>,
+    #[arg(long, env = "SHOP_NIP")]
+    pub shop_nip: Option<String>,
+    #[arg(long, env = "RECEIPT_LAYOUT", help = "a4 or thermal [default: a4]")]
+    pub receipt_layout: Option<String>,
+    // migration 20261019220000_timestamptz read the times saved before it as Europe/Warsaw,
+    // the zone the servers ran in, not this one. Databases of shops elsewhere that were running
+    // before it need those times shifted by hand
+    #[arg(
+        long,
+        env = "SHOP_TIMEZONE",
+        help = "IANA name [default: Europe/Warsaw]"
+    )]
+    pub shop_timezone: Option<String>,
+}
+
+impl ConfigArgs {
+    fn entries(&self) -> [(&'static str, &Option<String>); 15] {
+        [
+            ("DATABASE_URL", &self.database_url),
+            ("SERVER_FULL_ADDRESS", &self.server_full_address),
+            ("DB_POOL_SIZE", &self.db_pool_size),
+            ("STATIC_DIR", &self.static_dir),
+            ("CORS_ORIGINS", &self.cors_origins),
+            ("COOKIE_SECURE", &self.cookie_secure),
+            ("COOKIE_SAME_SITE", &self.cookie_same_site),
+            ("TOKEN_LIFETIME_HOURS", &self.token_lifetime_hours),
+            ("DRAIN_TIMEOUT_SECONDS", &self.drain_timeout_seconds),
+            ("LOG_FORMAT", &self.log_format),
+            ("SHOP_NAME", &self.shop_name),
+            ("SHOP_ADDRESS", &self.shop_address),
+            ("SHOP_NIP", &self.shop_nip),
+            ("RECEIPT_LAYOUT", &self.receipt_layout),
+            ("SHOP_TIMEZONE", &self.shop_timezone),
+        ]
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Config {
+    pub database_url: String,
+    pub server_address: String, // empty unless loaded to serve
+    pub db_pool_size: u32,
+    pub static_dir: PathBuf,
+    pub cors_origins: Vec<HeaderValue>, // empty allows any origin
+    pub auth: AuthConfig,
+    pub drain_timeout: Duration, // then open connections are dropped
+    pub log_format: LogFormat,
+    pub shop: ShopInfo,
+}
+
+// login token and the cookie carrying it
+#[derive(Clone, Debug)]
+pub struct AuthConfig {
+    pub token_lifetime_hours: u64,
+    pub cookie_secure: bool,
+    pub cookie_same_site: SameSite,
+}
+
+impl Default for AuthConfig {
+    fn default() -> Self {
+        AuthConfig {
+            token_lifetime_hours: 2,
+            cookie_secure: false,
+            cookie_same_site: SameSite::Lax,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LogFormat {
+    #[default]
+    Pretty,
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "pretty" => Ok(LogFormat::Pretty),
+            "json" => Ok(LogFormat::Json),
+            _ => Err(()),
+        }
+    }
+}
+
+// every offending key with what is wrong with it
+#[derive(Debug, PartialEq)]
+pub struct ConfigError(pub Vec<String>);
+
+impl Display for ConfigError {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(fmt, "invalid configuration")?;
+        for problem in &self.0 {
+            write!(fmt, "\n  {problem}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl Config {
+    // the other commands only talk to the database, they run without a server address
+    pub fn load(args: &ConfigArgs, serving: bool) -> Result<Config, ConfigError> {
+        let mut problems = Vec::new();
+
+        let file = match &args.config {
+            Some(path) => read_file(path, &mut problems),
+            None if Path::new(DEFAULT_CONFIG_FILE).exists() => {
+                read_file(Path::new(DEFAULT_CONFIG_FILE), &mut problems)
+            }
+            None => BTreeMap::new(),
+        };
+        let known = args.entries().map(|(key, _)| key.to_lowercase());
+        for key in file.keys().filter(|key| !known.contains(key)) {
+            problems.push(format!("{key}: unknown key in the config file"));
+        }
+
+        let mut values = BTreeMap::new();
+        for (key, arg) in args.entries() {
+            let value = arg
+                .clone()
+                .or_else(|| file.get(&key.to_lowercase()).cloned());
+            if let Some(value) = value {
+                values.insert(key, value);
+            }
+        }
+        let mut keys = Keys { values, problems };
+
+        let database_url = keys.required("DATABASE_URL");
+        let server_address = match serving {
+            true => keys.required("SERVER_FULL_ADDRESS"),
+            false => keys.optional("SERVER_FULL_ADDRESS"),
+        };
+        let db_pool_size = keys.parse("DB_POOL_SIZE", 10, "a number from 1 to 1000", |s| {
+            s.parse().ok().filter(|size| (1..=1000).contains(size))
+        });
+        let static_dir = keys.parse("STATIC_DIR", "./dist".into(), "a path", |s| {
+            Some(PathBuf::from(s)).filter(|_| !s.is_empty())
+        });
+        let cors_origins = keys.parse(
+            "CORS_ORIGINS",
+            Vec::new(),
+            "* or origins like https://sklep.pl",
+            |s| {
+                if s.trim() == "*" {
+                    return Some(Vec::new());
+                }
+                s.split(',')
+                    .map(|origin| {
+                        HeaderValue::from_str(origin.trim())
+                            .ok()
+                            .filter(|_| origin.contains("://"))
+                    })
+                    .collect()
+            },
+        );
+        let defaults = AuthConfig::default();
+        let auth = AuthConfig {
+            token_lifetime_hours: keys.parse(
+                "TOKEN_LIFETIME_HOURS",
+                defaults.token_lifetime_hours,
+                "a number of hours from 1 to 720",
+                |s| s.parse().ok().filter(|hours| (1..=720).contains(hours)),
+            ),
+            cookie_secure: keys.parse(
+                "COOKIE_SECURE",
+                defaults.cookie_secure,
+                "true or false",
+                |s| s.parse().ok(),
+            ),
+            cookie_same_site: keys.parse(
+                "COOKIE_SAME_SITE",
+                defaults.cookie_same_site,
+                "strict, lax or none",
+                |s| match s.to_lowercase().as_str() {
+                    "strict" => Some(SameSite::Strict),
+                    "lax" => Some(SameSite::Lax),
+                    "none" => Some(SameSite::None),
+                    _ => None,
+                },
+            ),
+        };
+        // browsers drop SameSite=None cookies without Secure
+        if auth.cookie_same_site == SameSite::None && !auth.cookie_secure {
+            keys.problems
+                .push("COOKIE_SAME_SITE: none requires COOKIE_SECURE=true".to_owned());
+        }
+        let drain_timeout = keys.parse(
+            "DRAIN_TIMEOUT_SECONDS",
+            Duration::from_secs(30),
+            "a number of seconds up to 3600",
+            |s| {
+                s.parse()
+                    .ok()
+                    .filter(|s| *s <= 3600)
+                    .map(Duration::from_secs)
+            },
+        );
+        let log_format = keys.parse("LOG_FORMAT", LogFormat::Pretty, "pretty or json", |s| {
+            s.parse().ok()
+        });
+
+        let shop_defaults = ShopInfo::default();
+        let shop = ShopInfo {
+            name: keys.optional("SHOP_NAME"),
+            address: keys.optional("SHOP_ADDRESS"),
+            nip: keys.optional("SHOP_NIP"),
+            receipt_layout: keys.parse(
+                "RECEIPT_LAYOUT",
+                shop_defaults.receipt_layout,
+                "a4 or thermal",
+                |s| s.parse::<ReceiptLayout>().ok(),
+            ),
+            timezone: keys.parse(
+                "SHOP_TIMEZONE",
+                DEFAULT_TIMEZONE,
+                "an IANA name like Europe/Warsaw",
+                |s| s.parse::<Tz>().ok(),
+            ),
+        };
+
+        if !keys.problems.is_empty() {
+            return Err(ConfigError(keys.problems));
+        }
+        Ok(Config {
+            database_url,
+            server_address,
+            db_pool_size,
+            static_dir,
+            cors_origins,
+            auth,
+            drain_timeout,
+            log_format,
+            shop,
+        })
+    }
+}
+
+// keys of the file as lowercase names, non-string values as written
+fn read_file(path: &Path, problems: &mut Vec<String>) -> BTreeMap<String, String> {
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(err) => {
+            problems.push(format!(
+                "CONFIG_FILE: cannot read {}: {err}",
+                path.display()
+            ));
+            return BTreeMap::new();
+        }
+    };
+    let table = match content.parse::<toml::Table>() {
+        Ok(table) => table,
+        Err(err) => {
+            problems.push(format!(
+                "CONFIG_FILE: {} is not valid TOML: {}",
+                path.display(),
+                err.message()
+            ));
+            return BTreeMap::new();
+        }
+    };
+    table
+        .into_iter()
+        .map(|(key, value)| {
+            let value = match value {
+                toml::Value::String(s) => s,
+                other => other.to_string(),
+            };
+            (key.to_lowercase(), value)
+        })
+        .collect()
+}
+
+struct Keys {
+    values: BTreeMap<&'static str, String>,
+    problems: Vec<String>,
+}
+
+impl Keys {
+    fn required(&mut self, key: &'static str) -> String {
+        match self.values.get(key) {
+            Some(value) if !value.is_empty() => value.clone(),
+            _ => {
+                self.problems.push(format!("{key}: required"));
+                String::new()
+            }
+        }
+    }
+
+    fn optional(&mut self, key: &'static str) -> String {
+        self.values.get(key).cloned().unwrap_or_default()
+    }
+
+    fn parse<T>(
+        &mut self,
+        key: &'static str,
+        default: T,
+        expected: &str,
+        parse: impl FnOnce(&str) -> Option<T>,
+    ) -> T {
+        let Some(value) = self.values.get(key) else {
+            return default;
+        };
+        match parse(value) {
+            Some(parsed) => parsed,
+            None => {
+                self.problems
+                    .push(format!("{key}: expected {expected}, got {value:?}"));
+                default
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args() -> ConfigArgs {
+        ConfigArgs {
+            database_url: Some("postgresql://localhost/test".to_owned()),
+            server_full_address: Some("localhost:3000".to_owned()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn config_defaults() {
+        let config = Config::load(&args(), true).unwrap();
+        assert_eq!(config.db_pool_size, 10);
+        assert_eq!(config.static_dir, PathBuf::from("./dist"));
+        assert!(config.cors_origins.is_empty());
+        assert_eq!(config.auth.token_lifetime_hours, 2);
+        assert_eq!(config.auth.cookie_same_site, SameSite::Lax);
+        assert_eq!(config.drain_timeout, Duration::from_secs(30));
+        assert_eq!(config.log_format, LogFormat::Pretty);
+        assert_eq!(config.shop.timezone, DEFAULT_TIMEZONE);
+    }
+
+    #[test]
+    fn config_file_below_args() {
+        let path = std::env::temp_dir().join(format!("backend-config-{}.toml", std::process::id()));
+        std::fs::write(
+            &path,
+            "db_pool_size = 20\nlog_format = \"json\"\ncors_origins = \"https://a.pl, https://b.pl\"\nshop_name = \"Farby\"\n",
+        )
+        .unwrap();
+        let config = Config::load(
+            &ConfigArgs {
+                config: Some(path.clone()),
+                shop_name: Some("Farby Kowalski".to_owned()),
+                ..args()
+            },
+            true,
+        )
+        .unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        assert_eq!(config.db_pool_size, 20);
+        assert_eq!(config.log_format, LogFormat::Json);
+        assert_eq!(config.cors_origins, ["https://a.pl", "https://b.pl"]);
+        assert_eq!(config.shop.name, "Farby Kowalski");
+    }
+
+    #[test]
+    fn config_server_address_to_serve() {
+        let args = ConfigArgs {
+            server_full_address: None,
+            ..args()
+        };
+        assert!(Config::load(&args, false).is_ok());
+        let err = Config::load(&args, true).unwrap_err();
+        assert_eq!(err.0, ["SERVER_FULL_ADDRESS: required"]);
+    }
+
+    #[test]
+    fn config_errors_name_keys() {
+        let err = Config::load(
+            &ConfigArgs {
+                database_url: None,
+                db_pool_size: Some("0".to_owned()),
+                cookie_same_site: Some("none".to_owned()),
+                shop_timezone: Some("Europe/Kraków".to_owned()),
+                config: Some("/nonexistent/backend.toml".into()),
+                ..args()
+            },
+            true,
+        )
+        .unwrap_err();
+
+        let keys: Vec<&str> = err
+            .0
+            .iter()
+            .map(|problem| problem.split(':').next().unwrap())
+            .collect();
+        assert_eq!(
+            keys,
+            [
+                "CONFIG_FILE",
+                "DATABASE_URL",
+                "DB_POOL_SIZE",
+                "COOKIE_SAME_SITE",
+                "SHOP_TIMEZONE"
+            ]
+        );
+        assert!(err
+            .to_string()
+            .contains("DB_POOL_SIZE: expected a number from 1 to 1000, got \"0\""));
+    }
+}
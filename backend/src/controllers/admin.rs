@@ -2,15 +2,24 @@ pub mod order {
     use tracing::trace;
 
     use crate::{
+        controllers::payment,
         models::{
             item::Item,
             order::{Order, OrderResponseFull},
+            tax::OrderTotals,
             user::Privileges,
         },
         session::Session,
         Db, Error, Result,
     };
 
+    // deleted items are listed but not counted
+    fn totals(order: &Order, items: &[Item]) -> Result<OrderTotals> {
+        let items: Vec<Item> = items.iter().filter(|item| !item.deleted).cloned().collect();
+        let (_, totals) = payment::totals(&items, order.discount())?;
+        Ok(totals)
+    }
+
     pub async fn list(session: Session, db: Db) -> Result<Vec<OrderResponseFull>> {
         trace!(" -- CONTROLLER admin::order::list");
         if matches!(session.privileges(), Privileges::Basic) {
@@ -22,72 +31,593 @@ pub mod order {
         let mut mapped = vec![];
         for order in res {
             let its: Vec<Item> =
-                sqlx::query_as("SELECT * FROM items WHERE order_id=$1 ORDER BY id")
+                sqlx::query_as("SELECT * FROM items WHERE order_id=$1 ORDER BY position, id")
                     .bind(order.id)
                     .fetch_all(&db)
                     .await?;
 
+            let totals = totals(&order, &its)?;
+            let discount = order.discount();
             let response = OrderResponseFull {
                 id: order.id,
+                number: order.number,
                 creator_id: order.creator_id,
                 time_created: order.time_created,
                 receiver: order.receiver,
                 additional_info: order.additional_info,
-                deleted: order.deleted,
-                paid: order.paid,
+                status: order.status,
+                picked_up: order.picked_up,
+                customer_id: order.customer_id,
+                discount,
+                totals,
                 items: its,
+                version: order.version,
+                updated_at: order.updated_at,
+                updated_by: order.updated_by,
             };
             mapped.push(response);
         }
         Ok(mapped)
     }
-    pub async fn pay(session: Session, order_id: i32, payload: bool, db: Db) -> Result<()> {
-        trace!(" -- CONTROLLER admin::order::pay");
+    pub async fn read(session: Session, order_id: i32, db: Db) -> Result<OrderResponseFull> {
+        trace!(" -- CONTROLLER admin::order::read");
         if matches!(session.privileges(), Privileges::Basic) {
             return Err(Error::AuthNoAccess);
         }
-        let output = sqlx::query!("UPDATE orders SET paid=$1 WHERE id = $2", payload, order_id)
-            .execute(&db)
+        let order: Option<Order> = sqlx::query_as("SELECT * FROM orders WHERE id=$1")
+            .bind(order_id)
+            .fetch_optional(&db)
             .await?;
-        if output.rows_affected() == 0 {
+        let Some(order) = order else {
             return Err(Error::SQLEntityNotFound {
                 entity_type: "order",
                 id: order_id,
             });
+        };
+        let items: Vec<Item> =
+            sqlx::query_as("SELECT * FROM items WHERE order_id = $1 ORDER BY position, id")
+                .bind(order_id)
+                .fetch_all(&db)
+                .await?;
+        let totals = totals(&order, &items)?;
+        let discount = order.discount();
+        let mapped_order = OrderResponseFull {
+            id: order.id,
+            number: order.number,
+            creator_id: order.creator_id,
+            time_created: order.time_created,
+            receiver: order.receiver,
+            additional_info: order.additional_info,
+            status: order.status,
+            picked_up: order.picked_up,
+            customer_id: order.customer_id,
+            discount,
+            totals,
+            items,
+            version: order.version,
+            updated_at: order.updated_at,
+            updated_by: order.updated_by,
+        };
+        Ok(mapped_order)
+    }
+}
+
+pub mod payment {
+    use sqlx::PgConnection;
+    use tracing::trace;
+
+    use crate::{
+        controllers::{self, payment},
+        models::{
+            order::OrderStatus,
+            payment::{OrderBalance, Payment, PaymentForCreate},
+            user::Privileges,
+        },
+        session::Session,
+        Db, Error, Result,
+    };
+
+    // negative amounts are refunds, which can't give back more than was paid. Cancelled orders
+    // take neither
+    pub async fn create(
+        session: Session,
+        order_id: i32,
+        payload: PaymentForCreate,
+        db: Db,
+    ) -> Result<i32> {
+        trace!(" -- CONTROLLER admin::payment::create");
+        if matches!(session.privileges(), Privileges::Basic) {
+            return Err(Error::AuthNoAccess);
         }
-        Ok(())
+        let mut tx = db.begin().await?;
+        let id = insert(&mut tx, session.id(), order_id, payload).await?;
+        tx.commit().await?;
+        Ok(id)
     }
-    pub async fn read(session: Session, order_id: i32, db: Db) -> Result<OrderResponseFull> {
-        trace!(" -- CONTROLLER admin::order::read");
+    pub async fn insert(
+        conn: &mut PgConnection,
+        creator_id: i32,
+        order_id: i32,
+        payload: PaymentForCreate,
+    ) -> Result<i32> {
+        if payload.amount.amount == 0 {
+            return Err(Error::PaymentInvalidAmount);
+        }
+        if controllers::order::lock_status(conn, order_id).await? == OrderStatus::Cancelled {
+            return Err(Error::PaymentOrderCancelled);
+        }
+        if payload.amount.is_negative() {
+            let balance = payment::balance(conn, order_id).await?;
+            let remaining = balance.paid.checked_add(payload.amount);
+            if remaining.is_none_or(|remaining| remaining.is_negative()) {
+                return Err(Error::PaymentInvalidAmount);
+            }
+        }
+        let time_created = chrono::Utc::now();
+        let res: (i32,) = sqlx::query_as(
+            "
+            INSERT INTO payments
+                (order_id,amount,method,note,creator_id,time_created)
+            VALUES
+                ($1,$2,$3,$4,$5,$6)
+            RETURNING id
+        ",
+        )
+        .bind(order_id)
+        .bind(payload.amount)
+        .bind(payload.method)
+        .bind(payload.note)
+        .bind(creator_id)
+        .bind(time_created)
+        .fetch_one(&mut *conn)
+        .await?;
+        controllers::order::sync_with_payments(conn, creator_id, order_id).await?;
+        Ok(res.0)
+    }
+    pub async fn list(session: Session, order_id: i32, db: Db) -> Result<Vec<Payment>> {
+        trace!(" -- CONTROLLER admin::payment::list");
         if matches!(session.privileges(), Privileges::Basic) {
             return Err(Error::AuthNoAccess);
         }
-        let order: Option<Order> = sqlx::query_as("SELECT * FROM orders WHERE id=$1")
+        let res: Vec<Payment> =
+            sqlx::query_as("SELECT * FROM payments WHERE order_id=$1 ORDER BY id")
+                .bind(order_id)
+                .fetch_all(&db)
+                .await?;
+        Ok(res)
+    }
+    pub async fn balance(session: Session, order_id: i32, db: Db) -> Result<OrderBalance> {
+        trace!(" -- CONTROLLER admin::payment::balance");
+        if matches!(session.privileges(), Privileges::Basic) {
+            return Err(Error::AuthNoAccess);
+        }
+        let mut conn = db.acquire().await?;
+        let exists: Option<(i32,)> = sqlx::query_as("SELECT id FROM orders WHERE id=$1")
             .bind(order_id)
-            .fetch_optional(&db)
+            .fetch_optional(&mut *conn)
             .await?;
-        if order.is_none() {
+        if exists.is_none() {
             return Err(Error::SQLEntityNotFound {
                 entity_type: "order",
                 id: order_id,
             });
         }
-        let order = order.unwrap();
-        let items: Vec<Item> = sqlx::query_as("SELECT * FROM items WHERE order_id = $1")
-            .bind(order_id)
+        payment::balance(&mut conn, order_id).await
+    }
+}
+
+pub mod customer {
+    use tracing::trace;
+
+    use crate::{models::user::Privileges, session::Session, Db, Error, Result};
+
+    // moves the orders of the duplicates to the target customer, fills its missing contact
+    // data from them and leaves the duplicates pointing to it. Returns the moved orders
+    pub async fn merge(
+        session: Session,
+        target_id: i32,
+        duplicate_ids: Vec<i32>,
+        db: Db,
+    ) -> Result<Vec<i32>> {
+        trace!(" -- CONTROLLER admin::customer::merge");
+        if matches!(session.privileges(), Privileges::Basic) {
+            return Err(Error::AuthNoAccess);
+        }
+        if duplicate_ids.is_empty() || duplicate_ids.contains(&target_id) {
+            return Err(Error::CustomerInvalidMerge);
+        }
+        let mut tx = db.begin().await?;
+        let mut ids = vec![target_id];
+        ids.extend(&duplicate_ids);
+        for id in ids {
+            let active: Option<(i32,)> = sqlx::query_as(
+                "SELECT id FROM customers WHERE id=$1 AND merged_into IS NULL FOR UPDATE",
+            )
+            .bind(id)
+            .fetch_optional(&mut *tx)
+            .await?;
+            if active.is_none() {
+                return Err(Error::SQLEntityNotFound {
+                    entity_type: "customer",
+                    id,
+                });
+            }
+        }
+
+        let moved: Vec<(i32,)> = sqlx::query_as(
+            "UPDATE orders SET customer_id=$1, updated_by=$2 WHERE customer_id=ANY($3) RETURNING id",
+        )
+        .bind(target_id)
+        .bind(session.id())
+        .bind(&duplicate_ids)
+        .fetch_all(&mut *tx)
+        .await?;
+        sqlx::query(
+            "
+            UPDATE customers SET
+                phone=COALESCE(phone,(SELECT phone FROM customers WHERE id=ANY($2) AND phone IS NOT NULL LIMIT 1)),
+                email=COALESCE(email,(SELECT email FROM customers WHERE id=ANY($2) AND email IS NOT NULL LIMIT 1)),
+                nip=COALESCE(nip,(SELECT nip FROM customers WHERE id=ANY($2) AND nip IS NOT NULL LIMIT 1)),
+                notes=COALESCE(notes,(SELECT notes FROM customers WHERE id=ANY($2) AND notes IS NOT NULL LIMIT 1))
+            WHERE id=$1
+        ",
+        )
+        .bind(target_id)
+        .bind(&duplicate_ids)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query("UPDATE customers SET merged_into=$1 WHERE id=ANY($2) OR merged_into=ANY($2)")
+            .bind(target_id)
+            .bind(&duplicate_ids)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(moved.into_iter().map(|order| order.0).collect())
+    }
+}
+
+pub mod product {
+    use tracing::trace;
+
+    use crate::{
+        models::{
+            product::{Product, ProductForCreate, ProductForUpdate},
+            quantity::Unit,
+            user::Privileges,
+        },
+        session::Session,
+        Db, Error, Result,
+    };
+
+    pub async fn create(session: Session, payload: ProductForCreate, db: Db) -> Result<i32> {
+        trace!(" -- CONTROLLER admin::product::create");
+        if matches!(session.privileges(), Privileges::Basic) {
+            return Err(Error::AuthNoAccess);
+        }
+        let res: (i32,) = sqlx::query_as(
+            "
+            INSERT INTO products
+                (name,default_unit,unit_price,category,low_stock_threshold,active)
+            VALUES
+                ($1,$2,$3,$4,$5,true)
+            RETURNING id
+            ",
+        )
+        .bind(payload.name)
+        .bind(payload.default_unit)
+        .bind(payload.unit_price)
+        .bind(payload.category)
+        .bind(payload.low_stock_threshold)
+        .fetch_one(&db)
+        .await?;
+        Ok(res.0)
+    }
+    pub async fn list(session: Session, db: Db) -> Result<Vec<Product>> {
+        trace!(" -- CONTROLLER admin::product::list");
+        if matches!(session.privileges(), Privileges::Basic) {
+            return Err(Error::AuthNoAccess);
+        }
+        let res: Vec<Product> = sqlx::query_as("SELECT * FROM products ORDER BY id")
             .fetch_all(&db)
             .await?;
-        let mapped_order = OrderResponseFull {
-            id: order.id,
-            creator_id: order.creator_id,
-            time_created: order.time_created,
-            receiver: order.receiver,
-            additional_info: order.additional_info,
-            deleted: order.deleted,
-            paid: order.paid,
-            items,
+        Ok(res)
+    }
+    pub async fn read(session: Session, product_id: i32, db: Db) -> Result<Product> {
+        trace!(" -- CONTROLLER admin::product::read");
+        if matches!(session.privileges(), Privileges::Basic) {
+            return Err(Error::AuthNoAccess);
+        }
+        let res: Product = sqlx::query_as("SELECT * FROM products WHERE id=$1")
+            .bind(product_id)
+            .fetch_optional(&db)
+            .await?
+            .ok_or(Error::SQLEntityNotFound {
+                entity_type: "product",
+                id: product_id,
+            })?;
+        Ok(res)
+    }
+    pub async fn update(
+        session: Session,
+        product_id: i32,
+        payload: ProductForUpdate,
+        db: Db,
+    ) -> Result<()> {
+        trace!(" -- CONTROLLER admin::product::update");
+        if matches!(session.privileges(), Privileges::Basic) {
+            return Err(Error::AuthNoAccess);
+        }
+        let mut tx = db.begin().await?;
+        // stock and the threshold are kept in the unit, changing it would rescale them
+        if let Some(unit) = payload.default_unit {
+            let current: Option<(Unit, bool, bool)> = sqlx::query_as(
+                "
+                SELECT default_unit,
+                    EXISTS (SELECT 1 FROM stock_movements WHERE product_id=products.id),
+                    low_stock_threshold IS NOT NULL
+                FROM products WHERE id=$1 FOR UPDATE
+                ",
+            )
+            .bind(product_id)
+            .fetch_optional(&mut *tx)
+            .await?;
+            if let Some((current, moved, threshold)) = current {
+                let keeps_threshold = threshold && payload.low_stock_threshold.is_none();
+                if unit != current && (moved || keeps_threshold) {
+                    return Err(Error::StockUnitInUse);
+                }
+            }
+        }
+        let output = sqlx::query(
+            "
+            UPDATE products SET
+                name=COALESCE($1,name),
+                default_unit=COALESCE($2,default_unit),
+                unit_price=COALESCE($3,unit_price),
+                category=COALESCE($4,category),
+                active=COALESCE($5,active),
+                low_stock_threshold=COALESCE($6,low_stock_threshold)
+            WHERE id=$7
+            ",
+        )
+        .bind(payload.name)
+        .bind(payload.default_unit)
+        .bind(payload.unit_price)
+        .bind(payload.category)
+        .bind(payload.active)
+        .bind(payload.low_stock_threshold)
+        .bind(product_id)
+        .execute(&mut *tx)
+        .await?;
+        if output.rows_affected() == 0 {
+            return Err(Error::SQLEntityNotFound {
+                entity_type: "product",
+                id: product_id,
+            });
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+    // products stay referenced by items, so they are only deactivated
+    pub async fn delete(session: Session, product_id: i32, db: Db) -> Result<()> {
+        trace!(" -- CONTROLLER admin::product::delete");
+        let payload = ProductForUpdate {
+            active: Some(false),
+            ..Default::default()
         };
-        Ok(mapped_order)
+        update(session, product_id, payload, db).await
+    }
+}
+
+pub mod stock {
+    use tracing::trace;
+
+    use crate::{
+        models::{
+            quantity::{Quantity, Unit},
+            stock::{StockLevel, StockMovement, StockMovementForCreate, StockMovementKind},
+            user::Privileges,
+        },
+        session::Session,
+        Db, Error, Result,
+    };
+
+    async fn insert(
+        session: Session,
+        kind: StockMovementKind,
+        payload: StockMovementForCreate,
+        db: Db,
+    ) -> Result<i32> {
+        let unit: (Unit,) = sqlx::query_as("SELECT default_unit FROM products WHERE id=$1")
+            .bind(payload.product_id)
+            .fetch_optional(&db)
+            .await?
+            .ok_or(Error::SQLEntityNotFound {
+                entity_type: "product",
+                id: payload.product_id,
+            })?;
+        let quantity = payload
+            .quantity
+            .convert(unit.0)
+            .ok_or(Error::StockIncompatibleUnit)?;
+        let time_created = chrono::Utc::now();
+        let res: (i32,) = sqlx::query_as(
+            "
+            INSERT INTO stock_movements
+                (product_id,kind,amount,reason,creator_id,time_created)
+            VALUES
+                ($1,$2,$3,$4,$5,$6)
+            RETURNING id
+            ",
+        )
+        .bind(payload.product_id)
+        .bind(kind)
+        .bind(quantity.amount)
+        .bind(payload.reason)
+        .bind(session.id())
+        .bind(time_created)
+        .fetch_one(&db)
+        .await?;
+        Ok(res.0)
+    }
+
+    // goods in
+    pub async fn receive(session: Session, payload: StockMovementForCreate, db: Db) -> Result<i32> {
+        trace!(" -- CONTROLLER admin::stock::receive");
+        if matches!(session.privileges(), Privileges::Basic) {
+            return Err(Error::AuthNoAccess);
+        }
+        if payload.quantity.amount <= 0 {
+            return Err(Error::StockInvalidQuantity);
+        }
+        insert(session, StockMovementKind::Receipt, payload, db).await
+    }
+
+    // manual correction, negative quantity for losses
+    pub async fn adjust(session: Session, payload: StockMovementForCreate, db: Db) -> Result<i32> {
+        trace!(" -- CONTROLLER admin::stock::adjust");
+        if matches!(session.privileges(), Privileges::Basic) {
+            return Err(Error::AuthNoAccess);
+        }
+        if payload.quantity.amount == 0 {
+            return Err(Error::StockInvalidQuantity);
+        }
+        if payload
+            .reason
+            .as_ref()
+            .is_none_or(|reason| reason.trim().is_empty())
+        {
+            return Err(Error::StockMissingReason);
+        }
+        insert(session, StockMovementKind::Adjustment, payload, db).await
+    }
+
+    pub async fn movements(
+        session: Session,
+        product_id: i32,
+        db: Db,
+    ) -> Result<Vec<StockMovement>> {
+        trace!(" -- CONTROLLER admin::stock::movements");
+        if matches!(session.privileges(), Privileges::Basic) {
+            return Err(Error::AuthNoAccess);
+        }
+        let res: Vec<StockMovement> =
+            sqlx::query_as("SELECT * FROM stock_movements WHERE product_id=$1 ORDER BY id")
+                .bind(product_id)
+                .fetch_all(&db)
+                .await?;
+        Ok(res)
+    }
+
+    pub async fn levels(session: Session, db: Db) -> Result<Vec<StockLevel>> {
+        trace!(" -- CONTROLLER admin::stock::levels");
+        if matches!(session.privileges(), Privileges::Basic) {
+            return Err(Error::AuthNoAccess);
+        }
+        let rows: Vec<(i32, String, Unit, i64, Option<i64>)> = sqlx::query_as(
+            "
+            SELECT products.id, products.name, products.default_unit,
+                COALESCE(SUM(stock_movements.amount),0)::BIGINT, products.low_stock_threshold
+            FROM products
+            LEFT JOIN stock_movements ON stock_movements.product_id = products.id
+            WHERE products.active=true
+            GROUP BY products.id
+            ORDER BY products.id
+            ",
+        )
+        .fetch_all(&db)
+        .await?;
+        let mapped = rows
+            .into_iter()
+            .map(|(product_id, name, unit, amount, threshold)| StockLevel {
+                product_id,
+                name,
+                stock: Quantity::new(amount, unit),
+                low_stock_threshold: threshold.map(|threshold| Quantity::new(threshold, unit)),
+            })
+            .collect();
+        Ok(mapped)
+    }
+
+    // products at or below their threshold
+    pub async fn low(session: Session, db: Db) -> Result<Vec<StockLevel>> {
+        trace!(" -- CONTROLLER admin::stock::low");
+        let levels = levels(session, db).await?;
+        let low = levels
+            .into_iter()
+            .filter(|level| {
+                level
+                    .low_stock_threshold
+                    .is_some_and(|threshold| level.stock.amount <= threshold.amount)
+            })
+            .collect();
+        Ok(low)
+    }
+}
+
+pub mod report {
+    use std::collections::BTreeMap;
+
+    use chrono_tz::Tz;
+    use tracing::trace;
+
+    use crate::{
+        models::{
+            order::OrderListParams,
+            quantity::{Quantity, Unit},
+            report::QuantityTotal,
+            time,
+            user::Privileges,
+        },
+        session::Session,
+        Db, Error, Result,
+    };
+
+    // sums quantities of non deleted items per name and base unit (g are totaled as kg, ml as l),
+    // dates are days in the shop timezone
+    pub async fn quantities(
+        session: Session,
+        params: OrderListParams,
+        timezone: Tz,
+        db: Db,
+    ) -> Result<Vec<QuantityTotal>> {
+        trace!(" -- CONTROLLER admin::report::quantities");
+        if matches!(session.privileges(), Privileges::Basic) {
+            return Err(Error::AuthNoAccess);
+        }
+        let mut builder = sqlx::QueryBuilder::new(
+            "
+            SELECT items.name, items.quantity_parsed FROM items
+            JOIN orders ON orders.id = items.order_id
+            WHERE items.deleted=false AND orders.status<>'Cancelled' AND items.quantity_parsed IS NOT NULL
+            ",
+        );
+        if let Some(ds) = params.date_start {
+            builder.push(" AND orders.time_created >= ");
+            builder.push_bind(time::day_start(timezone, ds));
+        }
+        if let Some(de) = params.date_end {
+            builder.push(" AND orders.time_created < ");
+            builder.push_bind(time::day_end(timezone, de));
+        }
+        let rows: Vec<(String, Quantity)> = builder.build_query_as().fetch_all(&db).await?;
+
+        let mut totals: BTreeMap<(String, Unit), i64> = BTreeMap::new();
+        for (name, quantity) in rows {
+            let quantity = quantity.to_base();
+            let total = totals.entry((name, quantity.unit)).or_insert(0);
+            *total = total
+                .checked_add(quantity.amount)
+                .ok_or(Error::ReportOverflow)?;
+        }
+        let mapped = totals
+            .into_iter()
+            .map(|((name, unit), amount)| QuantityTotal {
+                name,
+                quantity: Quantity::new(amount, unit),
+            })
+            .collect();
+        Ok(mapped)
     }
 }
 #[cfg(test)]
@@ -95,8 +625,17 @@ mod tests {
     use crate::*;
     use anyhow::Result;
     use models::{
+        customer::CustomerForCreate,
         item::ItemForCreate,
-        order::{OrderForCreate, OrderResponseFull},
+        money::Money,
+        order::{OrderForCreate, OrderListParams, OrderStatus},
+        payment::{OrderBalance, PaymentForCreate, PaymentMethod},
+        product::{ProductForCreate, ProductForUpdate},
+        quantity::{Quantity, Unit},
+        report::QuantityTotal,
+        stock::StockMovementForCreate,
+        tax::VatRate,
+        time::DEFAULT_TIMEZONE,
     };
     use session::Session;
 
@@ -113,19 +652,35 @@ mod tests {
         let payload = OrderForCreate {
             receiver: "Eryk".to_string(),
             additional_info: None,
+            customer_id: None,
         };
-        let itemed_id =
-            controllers::order::create(Session::BASIC(), payload.clone(), pool.clone()).await?;
-        let _ = controllers::order::create(Session::BASIC(), payload.clone(), pool.clone()).await?;
+        let itemed_id = controllers::order::create(
+            Session::BASIC(),
+            payload.clone(),
+            DEFAULT_TIMEZONE,
+            pool.clone(),
+        )
+        .await?;
+        let _ = controllers::order::create(
+            Session::BASIC(),
+            payload.clone(),
+            DEFAULT_TIMEZONE,
+            pool.clone(),
+        )
+        .await?;
         let deleted_id =
-            controllers::order::create(Session::BASIC(), payload, pool.clone()).await?;
+            controllers::order::create(Session::BASIC(), payload, DEFAULT_TIMEZONE, pool.clone())
+                .await?;
 
-        controllers::order::delete(Session::BASIC(), deleted_id, pool.clone()).await?;
+        controllers::order::delete(Session::BASIC(), deleted_id, None, pool.clone()).await?;
         let item = ItemForCreate {
-            quantity: "foidaj".to_string(),
+            quantity: "2kg".to_string(),
             name: "oije".to_string(),
-            value: 2000,
+            value: Money::pln(2000),
             additional_info: None,
+            product_id: None,
+            vat_rate: VatRate::Vat23,
+            discount: None,
         };
         controllers::item::create(Session::BASIC(), item.clone(), itemed_id, pool.clone()).await?;
         controllers::item::create(Session::BASIC(), item, itemed_id, pool.clone()).await?;
@@ -133,43 +688,217 @@ mod tests {
         let output = controllers::admin::order::list(Session::FULL(), pool.clone()).await?;
         assert_eq!(output.len(), 3);
         assert_eq!(output[0].items.len(), 2);
-        assert_eq!(output[0].items[0].value, 2000);
+        assert_eq!(output[0].items[0].value, Money::pln(2000));
 
         Ok(())
     }
+    fn payment(amount: i64, method: PaymentMethod) -> PaymentForCreate {
+        PaymentForCreate {
+            amount: Money::pln(amount),
+            method,
+            note: None,
+        }
+    }
+    // order of 2 pieces for 20zł each
+    async fn order_to_pay(pool: &Db) -> Result<i32> {
+        let payload = OrderForCreate {
+            receiver: "Eryk".to_string(),
+            additional_info: None,
+            customer_id: None,
+        };
+        let id =
+            controllers::order::create(Session::BASIC(), payload, DEFAULT_TIMEZONE, pool.clone())
+                .await?;
+        let item = ItemForCreate {
+            quantity: "2".to_string(),
+            name: "pędzel".to_string(),
+            value: Money::pln(2000),
+            additional_info: None,
+            product_id: None,
+            vat_rate: VatRate::Vat23,
+            discount: None,
+        };
+        controllers::item::create(Session::BASIC(), item, id, pool.clone()).await?;
+        Ok(id)
+    }
     #[sqlx::test]
-    async fn order_pay_no_access(pool: Db) -> Result<()> {
-        let output = controllers::admin::order::pay(Session::BASIC(), 0, true, pool).await;
-        assert!(matches!(output, Err(crate::Error::AuthNoAccess)));
+    async fn payment_no_access(pool: Db) -> Result<()> {
+        let output = controllers::admin::payment::create(
+            Session::BASIC(),
+            0,
+            payment(100, PaymentMethod::Cash),
+            pool.clone(),
+        )
+        .await;
+        assert_eq!(output, Err(Error::AuthNoAccess));
+        let output = controllers::admin::payment::list(Session::BASIC(), 0, pool.clone()).await;
+        assert_eq!(output, Err(Error::AuthNoAccess));
+        let output = controllers::admin::payment::balance(Session::BASIC(), 0, pool).await;
+        assert_eq!(output, Err(Error::AuthNoAccess));
         Ok(())
     }
     #[sqlx::test]
-    async fn order_pay_entity_not_found(pool: Db) -> Result<()> {
-        let output = controllers::admin::order::pay(Session::FULL(), 0, true, pool).await;
-        let _err = Error::SQLEntityNotFound {
+    async fn payment_entity_not_found(pool: Db) -> Result<()> {
+        let output = controllers::admin::payment::create(
+            Session::FULL(),
+            0,
+            payment(100, PaymentMethod::Cash),
+            pool.clone(),
+        )
+        .await;
+        let err = || Error::SQLEntityNotFound {
             entity_type: "order",
             id: 0,
         };
-        assert!(matches!(output, Err(_err)));
+        assert_eq!(output, Err(err()));
+        let output = controllers::admin::payment::balance(Session::FULL(), 0, pool).await;
+        assert_eq!(output, Err(err()));
         Ok(())
     }
     #[sqlx::test]
-    async fn order_pay(pool: Db) -> Result<()> {
-        let payload = OrderForCreate {
-            receiver: "Eryk".to_string(),
-            additional_info: None,
+    async fn payment_partial(pool: Db) -> Result<()> {
+        let id = order_to_pay(&pool).await?;
+        let status = || async {
+            let order = controllers::admin::order::read(Session::FULL(), id, pool.clone()).await?;
+            Ok::<_, Error>(order.status)
         };
-        let id =
-            controllers::order::create(Session::BASIC(), payload.clone(), pool.clone()).await?;
-        // default paid should be true
-        let order: OrderResponseFull =
-            controllers::admin::order::read(Session::FULL(), id, pool.clone()).await?;
-        assert_eq!(order.paid, false);
+        let balance = || controllers::admin::payment::balance(Session::FULL(), id, pool.clone());
 
-        controllers::admin::order::pay(Session::FULL(), id, true, pool.clone()).await?;
-        let order: OrderResponseFull =
-            controllers::admin::order::read(Session::FULL(), id, pool.clone()).await?;
-        assert_eq!(order.paid, true);
+        // deposit
+        controllers::admin::payment::create(
+            Session::FULL(),
+            id,
+            payment(1500, PaymentMethod::Cash),
+            pool.clone(),
+        )
+        .await?;
+        assert_eq!(status().await?, OrderStatus::Submitted);
+        let expected = OrderBalance {
+            total: Money::pln(4000),
+            paid: Money::pln(1500),
+            balance: Money::pln(2500),
+        };
+        assert_eq!(balance().await?, expected);
+
+        controllers::admin::payment::create(
+            Session::FULL(),
+            id,
+            payment(2500, PaymentMethod::Card),
+            pool.clone(),
+        )
+        .await?;
+        assert_eq!(status().await?, OrderStatus::Paid);
+        assert_eq!(balance().await?.balance, Money::pln(0));
+
+        // a refund reopens the order
+        controllers::admin::payment::create(
+            Session::FULL(),
+            id,
+            payment(-1000, PaymentMethod::Cash),
+            pool.clone(),
+        )
+        .await?;
+        assert_eq!(status().await?, OrderStatus::Submitted);
+        assert_eq!(balance().await?.balance, Money::pln(1000));
+
+        let payments = controllers::admin::payment::list(Session::FULL(), id, pool.clone()).await?;
+        let amounts: Vec<_> = payments
+            .iter()
+            .map(|payment| (payment.amount.amount, payment.method))
+            .collect();
+        assert_eq!(
+            amounts,
+            vec![
+                (1500, PaymentMethod::Cash),
+                (2500, PaymentMethod::Card),
+                (-1000, PaymentMethod::Cash)
+            ]
+        );
+        Ok(())
+    }
+    #[sqlx::test]
+    async fn payment_invalid_amount(pool: Db) -> Result<()> {
+        let id = order_to_pay(&pool).await?;
+        let output = controllers::admin::payment::create(
+            Session::FULL(),
+            id,
+            payment(0, PaymentMethod::Cash),
+            pool.clone(),
+        )
+        .await;
+        assert_eq!(output, Err(Error::PaymentInvalidAmount));
+
+        controllers::admin::payment::create(
+            Session::FULL(),
+            id,
+            payment(1000, PaymentMethod::Cash),
+            pool.clone(),
+        )
+        .await?;
+        // can't refund more than was paid
+        let output = controllers::admin::payment::create(
+            Session::FULL(),
+            id,
+            payment(-1001, PaymentMethod::Cash),
+            pool.clone(),
+        )
+        .await;
+        assert_eq!(output, Err(Error::PaymentInvalidAmount));
+        Ok(())
+    }
+    #[sqlx::test]
+    async fn payment_cancelled_order(pool: Db) -> Result<()> {
+        let id = order_to_pay(&pool).await?;
+        controllers::admin::payment::create(
+            Session::FULL(),
+            id,
+            payment(1000, PaymentMethod::Cash),
+            pool.clone(),
+        )
+        .await?;
+        controllers::order::delete(Session::BASIC(), id, None, pool.clone()).await?;
+
+        for amount in [3000, -1000] {
+            let output = controllers::admin::payment::create(
+                Session::FULL(),
+                id,
+                payment(amount, PaymentMethod::Cash),
+                pool.clone(),
+            )
+            .await;
+            assert_eq!(output, Err(Error::PaymentOrderCancelled));
+        }
+        let balance = controllers::admin::payment::balance(Session::FULL(), id, pool).await?;
+        assert_eq!(balance.paid, Money::pln(1000));
+        Ok(())
+    }
+    #[sqlx::test]
+    async fn payment_new_item_reopens(pool: Db) -> Result<()> {
+        let id = order_to_pay(&pool).await?;
+        controllers::order::transition(Session::BASIC(), id, OrderStatus::Ready, pool.clone())
+            .await?;
+        controllers::admin::payment::create(
+            Session::FULL(),
+            id,
+            payment(4000, PaymentMethod::Transfer),
+            pool.clone(),
+        )
+        .await?;
+        let order = controllers::admin::order::read(Session::FULL(), id, pool.clone()).await?;
+        assert_eq!(order.status, OrderStatus::Paid);
+
+        let item = ItemForCreate {
+            quantity: "1".to_string(),
+            name: "taśma".to_string(),
+            value: Money::pln(500),
+            additional_info: None,
+            product_id: None,
+            vat_rate: VatRate::Vat23,
+            discount: None,
+        };
+        controllers::item::create(Session::BASIC(), item, id, pool.clone()).await?;
+        let order = controllers::admin::order::read(Session::FULL(), id, pool.clone()).await?;
+        assert_eq!(order.status, OrderStatus::Ready);
         Ok(())
     }
     #[sqlx::test]
@@ -193,15 +922,24 @@ mod tests {
         let payload = OrderForCreate {
             receiver: "Eryk".to_string(),
             additional_info: None,
+            customer_id: None,
         };
-        let id =
-            controllers::order::create(Session::BASIC(), payload.clone(), pool.clone()).await?;
+        let id = controllers::order::create(
+            Session::BASIC(),
+            payload.clone(),
+            DEFAULT_TIMEZONE,
+            pool.clone(),
+        )
+        .await?;
 
         let item = ItemForCreate {
-            quantity: "foidaj".to_string(),
+            quantity: "2kg".to_string(),
             name: "oije".to_string(),
-            value: 2000,
+            value: Money::pln(2000),
             additional_info: None,
+            product_id: None,
+            vat_rate: VatRate::Vat23,
+            discount: None,
         };
         controllers::item::create(Session::BASIC(), item.clone(), id, pool.clone()).await?;
         controllers::item::create(Session::BASIC(), item, id, pool.clone()).await?;
@@ -210,10 +948,430 @@ mod tests {
 
         assert_eq!(output.receiver, "Eryk".to_owned());
         assert_eq!(output.additional_info, None);
-        assert_eq!(output.paid, false);
-        assert_eq!(output.deleted, false);
+        assert_eq!(output.status, OrderStatus::Submitted);
         assert_eq!(output.items.len(), 2);
-        assert_eq!(output.items[0].value, 2000);
+        assert_eq!(output.items[0].value, Money::pln(2000));
+        Ok(())
+    }
+    fn customer(name: &str, phone: Option<&str>) -> CustomerForCreate {
+        CustomerForCreate {
+            name: name.to_owned(),
+            phone: phone.map(str::to_owned),
+            email: None,
+            nip: None,
+            notes: None,
+        }
+    }
+    #[sqlx::test]
+    async fn customer_merge(pool: Db) -> Result<()> {
+        let target = controllers::customer::create(
+            Session::BASIC(),
+            customer("Jan Kowalski", None),
+            pool.clone(),
+        )
+        .await?;
+        let duplicate = controllers::customer::create(
+            Session::BASIC(),
+            customer("kowalski", Some("600100200")),
+            pool.clone(),
+        )
+        .await?;
+        let order_fc = OrderForCreate {
+            receiver: "kowalski".to_string(),
+            additional_info: None,
+            customer_id: Some(duplicate),
+        };
+        let order_id =
+            controllers::order::create(Session::BASIC(), order_fc, DEFAULT_TIMEZONE, pool.clone())
+                .await?;
+
+        let moved = controllers::admin::customer::merge(
+            Session::FULL(),
+            target,
+            vec![duplicate],
+            pool.clone(),
+        )
+        .await?;
+        assert_eq!(moved, vec![order_id]);
+
+        let detail = controllers::customer::read(Session::BASIC(), target, pool.clone()).await?;
+        assert_eq!(detail.customer.phone, Some("600100200".to_owned()));
+        assert_eq!(detail.orders.len(), 1);
+        assert_eq!(detail.orders[0].id, order_id);
+        assert_eq!(detail.orders[0].customer_id, Some(target));
+
+        // duplicates are out of the autocomplete and can't get new orders
+        let found =
+            controllers::customer::search(Session::BASIC(), "kowalski".to_owned(), pool.clone())
+                .await?;
+        assert_eq!(found.len(), 1);
+        let detail = controllers::customer::read(Session::BASIC(), duplicate, pool.clone()).await?;
+        assert_eq!(detail.customer.merged_into, Some(target));
+        Ok(())
+    }
+    #[sqlx::test]
+    async fn customer_merge_invalid(pool: Db) -> Result<()> {
+        let output =
+            controllers::admin::customer::merge(Session::BASIC(), 1, vec![2], pool.clone()).await;
+        assert_eq!(output, Err(Error::AuthNoAccess));
+
+        let target = controllers::customer::create(
+            Session::BASIC(),
+            customer("Kowalski", None),
+            pool.clone(),
+        )
+        .await?;
+        let output = controllers::admin::customer::merge(
+            Session::FULL(),
+            target,
+            vec![target],
+            pool.clone(),
+        )
+        .await;
+        assert_eq!(output, Err(Error::CustomerInvalidMerge));
+        let output =
+            controllers::admin::customer::merge(Session::FULL(), target, vec![], pool.clone())
+                .await;
+        assert_eq!(output, Err(Error::CustomerInvalidMerge));
+        let output =
+            controllers::admin::customer::merge(Session::FULL(), target, vec![0], pool).await;
+        assert_eq!(
+            output,
+            Err(Error::SQLEntityNotFound {
+                entity_type: "customer",
+                id: 0
+            })
+        );
+        Ok(())
+    }
+    #[sqlx::test]
+    async fn product_no_access(pool: Db) -> Result<()> {
+        let payload = ProductForCreate {
+            name: "czerwona farba".to_string(),
+            default_unit: Unit::L,
+            unit_price: Money::pln(3000),
+            category: None,
+            low_stock_threshold: None,
+        };
+        let output =
+            controllers::admin::product::create(Session::BASIC(), payload, pool.clone()).await;
+        assert!(matches!(output, Err(crate::Error::AuthNoAccess)));
+        let output = controllers::admin::product::list(Session::BASIC(), pool.clone()).await;
+        assert!(matches!(output, Err(crate::Error::AuthNoAccess)));
+        let output = controllers::admin::product::delete(Session::BASIC(), 1, pool).await;
+        assert!(matches!(output, Err(crate::Error::AuthNoAccess)));
+        Ok(())
+    }
+    #[sqlx::test]
+    async fn product_crud(pool: Db) -> Result<()> {
+        let payload = ProductForCreate {
+            name: "czerwona farba".to_string(),
+            default_unit: Unit::L,
+            unit_price: Money::pln(3000),
+            category: Some("farby".to_string()),
+            low_stock_threshold: None,
+        };
+        let id =
+            controllers::admin::product::create(Session::FULL(), payload, pool.clone()).await?;
+
+        let product = controllers::admin::product::read(Session::FULL(), id, pool.clone()).await?;
+        assert_eq!(product.name, "czerwona farba");
+        assert_eq!(product.default_unit, Unit::L);
+        assert_eq!(product.unit_price, Money::pln(3000));
+        assert!(product.active);
+
+        let update = ProductForUpdate {
+            unit_price: Some(Money::pln(3500)),
+            ..Default::default()
+        };
+        controllers::admin::product::update(Session::FULL(), id, update, pool.clone()).await?;
+        let product = controllers::admin::product::read(Session::FULL(), id, pool.clone()).await?;
+        assert_eq!(product.unit_price, Money::pln(3500));
+        assert_eq!(product.category, Some("farby".to_string()));
+
+        controllers::admin::product::delete(Session::FULL(), id, pool.clone()).await?;
+        let list = controllers::admin::product::list(Session::FULL(), pool.clone()).await?;
+        assert_eq!(list.len(), 1);
+        assert!(!list[0].active);
+        Ok(())
+    }
+    #[sqlx::test]
+    async fn product_entity_not_found(pool: Db) -> Result<()> {
+        let output = controllers::admin::product::read(Session::FULL(), 0, pool.clone()).await;
+        assert_eq!(
+            output,
+            Err(crate::Error::SQLEntityNotFound {
+                entity_type: "product",
+                id: 0
+            })
+        );
+        let output = controllers::admin::product::delete(Session::FULL(), 0, pool).await;
+        assert_eq!(
+            output,
+            Err(crate::Error::SQLEntityNotFound {
+                entity_type: "product",
+                id: 0
+            })
+        );
+        Ok(())
+    }
+    #[sqlx::test]
+    async fn stock_no_access(pool: Db) -> Result<()> {
+        let payload = StockMovementForCreate {
+            product_id: 1,
+            quantity: Quantity::new(1000, Unit::L),
+            reason: Some("inwentaryzacja".to_string()),
+        };
+        let output =
+            controllers::admin::stock::receive(Session::BASIC(), payload.clone(), pool.clone())
+                .await;
+        assert!(matches!(output, Err(crate::Error::AuthNoAccess)));
+        let output =
+            controllers::admin::stock::adjust(Session::BASIC(), payload, pool.clone()).await;
+        assert!(matches!(output, Err(crate::Error::AuthNoAccess)));
+        let output = controllers::admin::stock::low(Session::BASIC(), pool).await;
+        assert!(matches!(output, Err(crate::Error::AuthNoAccess)));
+        Ok(())
+    }
+    #[sqlx::test]
+    async fn stock_receive_and_adjust(pool: Db) -> Result<()> {
+        let product = ProductForCreate {
+            name: "klej".to_string(),
+            default_unit: Unit::Kg,
+            unit_price: Money::pln(2000),
+            category: None,
+            low_stock_threshold: None,
+        };
+        let product_id =
+            controllers::admin::product::create(Session::FULL(), product, pool.clone()).await?;
+
+        // grams are converted to the product unit
+        let receipt = StockMovementForCreate {
+            product_id,
+            quantity: Quantity::new(500000, Unit::G),
+            reason: None,
+        };
+        controllers::admin::stock::receive(Session::FULL(), receipt, pool.clone()).await?;
+
+        let adjustment = StockMovementForCreate {
+            product_id,
+            quantity: Quantity::new(-100, Unit::Kg),
+            reason: Some("rozsypany worek".to_string()),
+        };
+        controllers::admin::stock::adjust(Session::FULL(), adjustment.clone(), pool.clone())
+            .await?;
+
+        let levels = controllers::admin::stock::levels(Session::FULL(), pool.clone()).await?;
+        assert_eq!(levels.len(), 1);
+        assert_eq!(levels[0].stock, Quantity::new(400, Unit::Kg));
+
+        let no_reason = StockMovementForCreate {
+            reason: Some(" ".to_string()),
+            ..adjustment.clone()
+        };
+        let output =
+            controllers::admin::stock::adjust(Session::FULL(), no_reason, pool.clone()).await;
+        assert_eq!(output, Err(crate::Error::StockMissingReason));
+
+        let negative_receipt = StockMovementForCreate {
+            reason: None,
+            ..adjustment.clone()
+        };
+        let output =
+            controllers::admin::stock::receive(Session::FULL(), negative_receipt, pool.clone())
+                .await;
+        assert_eq!(output, Err(crate::Error::StockInvalidQuantity));
+
+        let litres = StockMovementForCreate {
+            quantity: Quantity::new(1000, Unit::L),
+            ..adjustment
+        };
+        let output =
+            controllers::admin::stock::receive(Session::FULL(), litres, pool.clone()).await;
+        assert_eq!(output, Err(crate::Error::StockIncompatibleUnit));
+        Ok(())
+    }
+    #[sqlx::test]
+    async fn stock_product_unit_change(pool: Db) -> Result<()> {
+        let product = ProductForCreate {
+            name: "klej".to_string(),
+            default_unit: Unit::Kg,
+            unit_price: Money::pln(2000),
+            category: None,
+            low_stock_threshold: Some(1000),
+        };
+        let product_id =
+            controllers::admin::product::create(Session::FULL(), product, pool.clone()).await?;
+        let to_grams = |low_stock_threshold| ProductForUpdate {
+            default_unit: Some(Unit::G),
+            low_stock_threshold,
+            ..Default::default()
+        };
+
+        // the threshold would turn from 1kg into 1g
+        let output = controllers::admin::product::update(
+            Session::FULL(),
+            product_id,
+            to_grams(None),
+            pool.clone(),
+        )
+        .await;
+        assert_eq!(output, Err(crate::Error::StockUnitInUse));
+        controllers::admin::product::update(
+            Session::FULL(),
+            product_id,
+            to_grams(Some(1000000)),
+            pool.clone(),
+        )
+        .await?;
+
+        let receipt = StockMovementForCreate {
+            product_id,
+            quantity: Quantity::new(500000, Unit::G),
+            reason: None,
+        };
+        controllers::admin::stock::receive(Session::FULL(), receipt, pool.clone()).await?;
+        let to_kg = ProductForUpdate {
+            default_unit: Some(Unit::Kg),
+            low_stock_threshold: Some(1000),
+            ..Default::default()
+        };
+        let output =
+            controllers::admin::product::update(Session::FULL(), product_id, to_kg, pool.clone())
+                .await;
+        assert_eq!(output, Err(crate::Error::StockUnitInUse));
+
+        // the same unit again is not a change
+        controllers::admin::product::update(
+            Session::FULL(),
+            product_id,
+            to_grams(None),
+            pool.clone(),
+        )
+        .await?;
+        let levels = controllers::admin::stock::levels(Session::FULL(), pool.clone()).await?;
+        assert_eq!(levels[0].stock, Quantity::new(500000, Unit::G));
+        Ok(())
+    }
+    #[sqlx::test]
+    async fn stock_low(pool: Db) -> Result<()> {
+        for (name, threshold) in [
+            ("klej", Some(1000)),
+            ("bejca", Some(5000)),
+            ("lakier", None),
+        ] {
+            let product = ProductForCreate {
+                name: name.to_string(),
+                default_unit: Unit::L,
+                unit_price: Money::pln(2000),
+                category: None,
+                low_stock_threshold: threshold,
+            };
+            let product_id =
+                controllers::admin::product::create(Session::FULL(), product, pool.clone()).await?;
+            let receipt = StockMovementForCreate {
+                product_id,
+                quantity: Quantity::new(3000, Unit::L),
+                reason: None,
+            };
+            controllers::admin::stock::receive(Session::FULL(), receipt, pool.clone()).await?;
+        }
+        let low = controllers::admin::stock::low(Session::FULL(), pool.clone()).await?;
+        assert_eq!(low.len(), 1);
+        assert_eq!(low[0].name, "bejca");
+        assert_eq!(low[0].stock, Quantity::new(3000, Unit::L));
+        assert_eq!(
+            low[0].low_stock_threshold,
+            Some(Quantity::new(5000, Unit::L))
+        );
+        Ok(())
+    }
+    #[sqlx::test]
+    async fn report_quantities_no_access(pool: Db) -> Result<()> {
+        let params = OrderListParams {
+            date_start: None,
+            date_end: None,
+            number: None,
+            modified_since: None,
+        };
+        let output = controllers::admin::report::quantities(
+            Session::BASIC(),
+            params,
+            DEFAULT_TIMEZONE,
+            pool,
+        )
+        .await;
+        assert!(matches!(output, Err(crate::Error::AuthNoAccess)));
+        Ok(())
+    }
+    #[sqlx::test]
+    async fn report_quantities(pool: Db) -> Result<()> {
+        let payload = OrderForCreate {
+            receiver: "Eryk".to_string(),
+            additional_info: None,
+            customer_id: None,
+        };
+        let id = controllers::order::create(
+            Session::BASIC(),
+            payload.clone(),
+            DEFAULT_TIMEZONE,
+            pool.clone(),
+        )
+        .await?;
+        let deleted_id =
+            controllers::order::create(Session::BASIC(), payload, DEFAULT_TIMEZONE, pool.clone())
+                .await?;
+
+        let items = [
+            (id, "farba", "1l"),
+            (id, "farba", "500ml"),
+            (id, "farba", "2 szt"),
+            (id, "klej", "250g"),
+            (id, "klej", "1,5kg"),
+            (deleted_id, "farba", "10l"),
+        ];
+        for (order_id, name, quantity) in items {
+            let item = ItemForCreate {
+                quantity: quantity.to_string(),
+                name: name.to_string(),
+                value: Money::pln(2000),
+                additional_info: None,
+                product_id: None,
+                vat_rate: VatRate::Vat23,
+                discount: None,
+            };
+            controllers::item::create(Session::BASIC(), item, order_id, pool.clone()).await?;
+        }
+        controllers::order::delete(Session::BASIC(), deleted_id, None, pool.clone()).await?;
+
+        let params = OrderListParams {
+            date_start: None,
+            date_end: None,
+            number: None,
+            modified_since: None,
+        };
+        let output = controllers::admin::report::quantities(
+            Session::FULL(),
+            params,
+            DEFAULT_TIMEZONE,
+            pool.clone(),
+        )
+        .await?;
+        let expected = vec![
+            QuantityTotal {
+                name: "farba".to_string(),
+                quantity: Quantity::new(2000, Unit::Szt),
+            },
+            QuantityTotal {
+                name: "farba".to_string(),
+                quantity: Quantity::new(1500, Unit::L),
+            },
+            QuantityTotal {
+                name: "klej".to_string(),
+                quantity: Quantity::new(1750, Unit::Kg),
+            },
+        ];
+        assert_eq!(output, expected);
         Ok(())
     }
 }
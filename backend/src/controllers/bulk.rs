@@ -0,0 +1,445 @@
+// Changes of many orders or items in one request. Everything runs in one transaction with
+// a savepoint per entity, so the entities that fail are rolled back and reported while the
+// rest is committed together.
+
+use sqlx::{Connection, PgConnection, Postgres, Transaction};
+use tracing::trace;
+
+use crate::{
+    controllers::{self, payment},
+    models::{
+        bulk::BulkReport,
+        item::{Item, ItemForCreate},
+        money::Money,
+        order::OrderStatus,
+        payment::{PaymentForCreate, PaymentMethod},
+        user::Privileges,
+    },
+    session::Session,
+    Db, Error, Result,
+};
+
+// entities per request
+pub const LIMIT: u64 = 500;
+
+fn check_size(len: usize) -> Result<()> {
+    if len as u64 > LIMIT {
+        return Err(Error::BulkTooLarge);
+    }
+    Ok(())
+}
+
+// keeps or rolls back the changes of one entity, id is reported for failures
+async fn settle(
+    report: &mut BulkReport,
+    savepoint: Transaction<'_, Postgres>,
+    id: Option<i32>,
+    result: Result<i32>,
+) -> Result<()> {
+    match result {
+        Ok(id) => {
+            savepoint.commit().await?;
+            report.succeed(id);
+        }
+        Err(error) => {
+            savepoint.rollback().await?;
+            report.fail(id, error);
+        }
+    }
+    Ok(())
+}
+
+// pays what is left to pay of every order
+pub async fn pay(
+    session: Session,
+    order_ids: Vec<i32>,
+    method: PaymentMethod,
+    db: Db,
+) -> Result<BulkReport> {
+    trace!(" -- CONTROLLER bulk::pay");
+    if matches!(session.privileges(), Privileges::Basic) {
+        return Err(Error::AuthNoAccess);
+    }
+    check_size(order_ids.len())?;
+    let mut report = BulkReport::default();
+    let mut tx = db.begin().await?;
+    for order_id in order_ids {
+        let mut savepoint = tx.begin().await?;
+        let result = pay_one(&mut savepoint, session.id(), order_id, method, false).await;
+        settle(
+            &mut report,
+            savepoint,
+            Some(order_id),
+            result.map(|_| order_id),
+        )
+        .await?;
+    }
+    tx.commit().await?;
+    Ok(report)
+}
+
+// refunds everything paid for every order
+pub async fn unpay(
+    session: Session,
+    order_ids: Vec<i32>,
+    method: PaymentMethod,
+    db: Db,
+) -> Result<BulkReport> {
+    trace!(" -- CONTROLLER bulk::unpay");
+    if matches!(session.privileges(), Privileges::Basic) {
+        return Err(Error::AuthNoAccess);
+    }
+    check_size(order_ids.len())?;
+    let mut report = BulkReport::default();
+    let mut tx = db.begin().await?;
+    for order_id in order_ids {
+        let mut savepoint = tx.begin().await?;
+        let result = pay_one(&mut savepoint, session.id(), order_id, method, true).await;
+        settle(
+            &mut report,
+            savepoint,
+            Some(order_id),
+            result.map(|_| order_id),
+        )
+        .await?;
+    }
+    tx.commit().await?;
+    Ok(report)
+}
+
+// orders with nothing to pay (or refund) fail with PaymentInvalidAmount
+async fn pay_one(
+    conn: &mut PgConnection,
+    user_id: i32,
+    order_id: i32,
+    method: PaymentMethod,
+    refund: bool,
+) -> Result<i32> {
+    controllers::order::lock_status(conn, order_id).await?;
+    let balance = payment::balance(conn, order_id).await?;
+    let amount = match refund {
+        false => balance.balance,
+        true => Money {
+            amount: balance
+                .paid
+                .amount
+                .checked_neg()
+                .ok_or(Error::OrderTotalOverflow)?,
+            ..balance.paid
+        },
+    };
+    if amount.amount == 0 || amount.is_negative() != refund {
+        return Err(Error::PaymentInvalidAmount);
+    }
+    let payment = PaymentForCreate {
+        amount,
+        method,
+        note: None,
+    };
+    controllers::admin::payment::insert(conn, user_id, order_id, payment).await
+}
+
+// cancels every order
+pub async fn delete_orders(session: Session, order_ids: Vec<i32>, db: Db) -> Result<BulkReport> {
+    trace!(" -- CONTROLLER bulk::delete_orders");
+    check_size(order_ids.len())?;
+    let mut report = BulkReport::default();
+    let mut tx = db.begin().await?;
+    for order_id in order_ids {
+        let mut savepoint = tx.begin().await?;
+        let result = controllers::order::change_status(
+            &mut savepoint,
+            session.id(),
+            order_id,
+            OrderStatus::Cancelled,
+        )
+        .await;
+        settle(
+            &mut report,
+            savepoint,
+            Some(order_id),
+            result.map(|_| order_id),
+        )
+        .await?;
+    }
+    tx.commit().await?;
+    Ok(report)
+}
+
+// checks or unchecks every item of the order, items already in that state are reported as
+// unchanged. Also tells whether the order status moved with them
+pub async fn check_items(
+    session: Session,
+    order_id: i32,
+    checked: bool,
+    db: Db,
+) -> Result<(BulkReport, bool)> {
+    trace!(" -- CONTROLLER bulk::check_items");
+    let mut report = BulkReport::default();
+    let mut tx = db.begin().await?;
+    let status = controllers::order::lock_status(&mut tx, order_id).await?;
+    controllers::item::ensure_not_cancelled(status)?;
+    let items: Vec<Item> = sqlx::query_as(
+        "SELECT * FROM items WHERE order_id=$1 AND deleted=false ORDER BY position, id FOR UPDATE",
+    )
+    .bind(order_id)
+    .fetch_all(&mut *tx)
+    .await?;
+    for item in items {
+        if item.checked == checked {
+            report.leave(item.id);
+            continue;
+        }
+        let mut savepoint = tx.begin().await?;
+        let result =
+            controllers::item::set_checked(&mut savepoint, session.id(), &item, checked).await;
+        settle(
+            &mut report,
+            savepoint,
+            Some(item.id),
+            result.map(|_| item.id),
+        )
+        .await?;
+    }
+    let status_moved = controllers::order::sync(&mut tx, session.id(), order_id).await?;
+    tx.commit().await?;
+    Ok((report, status_moved))
+}
+
+// reports the ids of the new items, and whether the order status moved with them
+pub async fn create_items(
+    session: Session,
+    order_id: i32,
+    items: Vec<ItemForCreate>,
+    db: Db,
+) -> Result<(BulkReport, bool)> {
+    trace!(" -- CONTROLLER bulk::create_items");
+    check_size(items.len())?;
+    let mut report = BulkReport::default();
+    let mut tx = db.begin().await?;
+    let status = controllers::order::lock_status(&mut tx, order_id).await?;
+    controllers::item::ensure_not_cancelled(status)?;
+    for item_fc in items {
+        let mut savepoint = tx.begin().await?;
+        let result =
+            controllers::item::insert(&mut savepoint, session.id(), order_id, item_fc).await;
+        settle(&mut report, savepoint, None, result).await?;
+    }
+    let status_moved = controllers::order::sync(&mut tx, session.id(), order_id).await?;
+    tx.commit().await?;
+    Ok((report, status_moved))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{
+        bulk::BulkResult, order::OrderForCreate, tax::VatRate, time::DEFAULT_TIMEZONE,
+    };
+    use anyhow::Result;
+
+    fn item(quantity: &str, value: i64) -> ItemForCreate {
+        ItemForCreate {
+            quantity: quantity.to_owned(),
+            name: "biała farba".to_owned(),
+            value: Money::pln(value),
+            additional_info: None,
+            product_id: None,
+            vat_rate: VatRate::Vat23,
+            discount: None,
+        }
+    }
+
+    // order with an item worth the value, or an empty one
+    async fn order(pool: &Db, value: Option<i64>) -> Result<i32> {
+        let order_fc = OrderForCreate {
+            receiver: "tomek".to_owned(),
+            additional_info: None,
+            customer_id: None,
+        };
+        let id =
+            controllers::order::create(Session::BASIC(), order_fc, DEFAULT_TIMEZONE, pool.clone())
+                .await?;
+        if let Some(value) = value {
+            controllers::item::create(Session::BASIC(), item("1", value), id, pool.clone()).await?;
+        }
+        Ok(id)
+    }
+
+    async fn status(pool: &Db, id: i32) -> Result<OrderStatus> {
+        let status: (OrderStatus,) = sqlx::query_as("SELECT status FROM orders WHERE id=$1")
+            .bind(id)
+            .fetch_one(pool)
+            .await?;
+        Ok(status.0)
+    }
+
+    fn ok(id: i32) -> BulkResult {
+        BulkResult {
+            id: Some(id),
+            unchanged: false,
+            error: None,
+        }
+    }
+
+    fn failed(id: Option<i32>, error: Error) -> BulkResult {
+        BulkResult {
+            id,
+            unchanged: false,
+            error: Some(error.variant()),
+        }
+    }
+
+    #[sqlx::test]
+    async fn bulk_pay_and_unpay(pool: Db) -> Result<()> {
+        let partly_paid = order(&pool, Some(5000)).await?;
+        let payment = PaymentForCreate {
+            amount: Money::pln(2000),
+            method: PaymentMethod::Cash,
+            note: None,
+        };
+        controllers::admin::payment::create(Session::FULL(), partly_paid, payment, pool.clone())
+            .await?;
+        let unpaid = order(&pool, Some(1500)).await?;
+        let empty = order(&pool, None).await?;
+        let cancelled = order(&pool, Some(1500)).await?;
+        controllers::order::delete(Session::BASIC(), cancelled, None, pool.clone()).await?;
+
+        let ids = vec![partly_paid, unpaid, empty, cancelled, 99];
+        let report = pay(
+            Session::FULL(),
+            ids.clone(),
+            PaymentMethod::Card,
+            pool.clone(),
+        )
+        .await?;
+        let not_found = Error::SQLEntityNotFound {
+            entity_type: "order",
+            id: 99,
+        };
+        assert_eq!(
+            report,
+            BulkReport {
+                succeeded: 2,
+                unchanged: 0,
+                failed: 3,
+                results: vec![
+                    ok(partly_paid),
+                    ok(unpaid),
+                    failed(Some(empty), Error::PaymentInvalidAmount),
+                    failed(Some(cancelled), Error::PaymentOrderCancelled),
+                    failed(Some(99), not_found),
+                ],
+            }
+        );
+        assert_eq!(status(&pool, partly_paid).await?, OrderStatus::Paid);
+        assert_eq!(status(&pool, unpaid).await?, OrderStatus::Paid);
+        let balance =
+            controllers::admin::payment::balance(Session::FULL(), partly_paid, pool.clone())
+                .await?;
+        assert_eq!(balance.paid, Money::pln(5000));
+
+        let report = unpay(
+            Session::FULL(),
+            vec![partly_paid, empty],
+            PaymentMethod::Card,
+            pool.clone(),
+        )
+        .await?;
+        assert_eq!(report.succeeded, 1);
+        assert_eq!(
+            report.results[1],
+            failed(Some(empty), Error::PaymentInvalidAmount)
+        );
+        assert_eq!(status(&pool, partly_paid).await?, OrderStatus::Submitted);
+        assert_eq!(status(&pool, unpaid).await?, OrderStatus::Paid);
+        let balance =
+            controllers::admin::payment::balance(Session::FULL(), partly_paid, pool.clone())
+                .await?;
+        assert_eq!(balance.paid, Money::pln(0));
+
+        let should_err = pay(Session::BASIC(), ids, PaymentMethod::Card, pool.clone()).await;
+        assert_eq!(should_err, Err(Error::AuthNoAccess));
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn bulk_delete_orders(pool: Db) -> Result<()> {
+        let open = order(&pool, Some(1500)).await?;
+        let paid = order(&pool, Some(1500)).await?;
+        pay(
+            Session::FULL(),
+            vec![paid],
+            PaymentMethod::Cash,
+            pool.clone(),
+        )
+        .await?;
+
+        let report = delete_orders(Session::BASIC(), vec![open, paid], pool.clone()).await?;
+        let invalid = Error::OrderInvalidTransition {
+            from: OrderStatus::Paid,
+            to: OrderStatus::Cancelled,
+        };
+        assert_eq!(report.results, vec![ok(open), failed(Some(paid), invalid)]);
+        assert_eq!(report.succeeded_ids().collect::<Vec<_>>(), vec![open]);
+        assert_eq!(status(&pool, open).await?, OrderStatus::Cancelled);
+        assert_eq!(status(&pool, paid).await?, OrderStatus::Paid);
+
+        let too_many = vec![open; LIMIT as usize + 1];
+        let should_err = delete_orders(Session::BASIC(), too_many, pool.clone()).await;
+        assert_eq!(should_err, Err(Error::BulkTooLarge));
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn bulk_check_items(pool: Db) -> Result<()> {
+        let id = order(&pool, Some(1500)).await?;
+        let report = create_items(
+            Session::BASIC(),
+            id,
+            vec![item("2", 500), item("dużo", 500), item("1kg", 700)],
+            pool.clone(),
+        )
+        .await?
+        .0;
+        assert_eq!(report.succeeded, 2);
+        assert_eq!(report.results[1], failed(None, Error::ItemInvalidQuantity));
+        let order = controllers::order::read(Session::BASIC(), id, pool.clone()).await?;
+        let ids: Vec<Option<i32>> = order
+            .items
+            .iter()
+            .skip(1)
+            .map(|item| Some(item.id))
+            .collect();
+        let created: Vec<Option<i32>> = [0, 2].iter().map(|i| report.results[*i].id).collect();
+        assert_eq!(ids, created);
+        assert_eq!(order.totals.totals.gross, Money::pln(3200));
+
+        let report = check_items(Session::BASIC(), id, true, pool.clone())
+            .await?
+            .0;
+        assert_eq!((report.succeeded, report.failed), (3, 0));
+        assert_eq!(status(&pool, id).await?, OrderStatus::Ready);
+        // checked already, nothing to publish
+        let report = check_items(Session::BASIC(), id, true, pool.clone())
+            .await?
+            .0;
+        assert_eq!((report.succeeded, report.unchanged), (0, 3));
+        assert_eq!(report.succeeded_ids().count(), 0);
+        let report = check_items(Session::BASIC(), id, false, pool.clone())
+            .await?
+            .0;
+        assert_eq!(report.succeeded, 3);
+        assert_eq!(status(&pool, id).await?, OrderStatus::InPreparation);
+
+        let should_err = check_items(Session::BASIC(), 99, true, pool.clone()).await;
+        assert_eq!(
+            should_err,
+            Err(Error::SQLEntityNotFound {
+                entity_type: "order",
+                id: 99
+            })
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,289 @@
+use tracing::trace;
+
+use crate::{
+    controllers::{self, contains_pattern, payment},
+    models::{
+        customer::{
+            Customer, CustomerDetail, CustomerForCreate, CustomerForUpdate, CustomerResponseBasic,
+        },
+        money::{Currency, Money},
+    },
+    session::Session,
+    Db, Error, Result,
+};
+
+const SEARCH_LIMIT: i64 = 20;
+
+pub async fn create(_session: Session, payload: CustomerForCreate, db: Db) -> Result<i32> {
+    trace!(" -- CONTROLLER customer::create");
+    let time_created = chrono::Utc::now();
+    let res: (i32,) = sqlx::query_as(
+        "
+            INSERT INTO customers
+                (name,phone,email,nip,notes,time_created)
+            VALUES
+                ($1,$2,$3,$4,$5,$6)
+            RETURNING id
+        ",
+    )
+    .bind(payload.name.trim())
+    .bind(payload.phone)
+    .bind(payload.email)
+    .bind(payload.nip)
+    .bind(payload.notes)
+    .bind(time_created)
+    .fetch_one(&db)
+    .await?;
+    Ok(res.0)
+}
+
+// orders can only be assigned to customers that weren't merged into others
+pub async fn ensure_active(db: &Db, id: i32) -> Result<()> {
+    let exists: Option<(i32,)> =
+        sqlx::query_as("SELECT id FROM customers WHERE id=$1 AND merged_into IS NULL")
+            .bind(id)
+            .fetch_optional(db)
+            .await?;
+    match exists {
+        Some(_) => Ok(()),
+        None => Err(Error::SQLEntityNotFound {
+            entity_type: "customer",
+            id,
+        }),
+    }
+}
+
+// receiver autocomplete, matches names, phone numbers and NIPs
+pub async fn search(
+    _session: Session,
+    query: String,
+    db: Db,
+) -> Result<Vec<CustomerResponseBasic>> {
+    trace!(" -- CONTROLLER customer::search");
+    let pattern = contains_pattern(&query);
+    let res: Vec<CustomerResponseBasic> = sqlx::query_as(
+        "
+            SELECT id,name,phone,nip FROM customers
+            WHERE merged_into IS NULL AND (name ILIKE $1 ESCAPE '\\' OR phone ILIKE $1 ESCAPE '\\' OR nip ILIKE $1 ESCAPE '\\')
+            ORDER BY lower(name)
+            LIMIT $2
+        ",
+    )
+    .bind(pattern)
+    .bind(SEARCH_LIMIT)
+    .fetch_all(&db)
+    .await?;
+    Ok(res)
+}
+
+pub async fn read(session: Session, id: i32, db: Db) -> Result<CustomerDetail> {
+    trace!(" -- CONTROLLER customer::read");
+    let customer: Customer = sqlx::query_as("SELECT * FROM customers WHERE id=$1")
+        .bind(id)
+        .fetch_optional(&db)
+        .await?
+        .ok_or(Error::SQLEntityNotFound {
+            entity_type: "customer",
+            id,
+        })?;
+    let order_ids: Vec<(i32,)> = sqlx::query_as(
+        "SELECT id FROM orders WHERE customer_id=$1 AND status<>'Cancelled' ORDER BY id DESC",
+    )
+    .bind(id)
+    .fetch_all(&db)
+    .await?;
+
+    let mut orders = vec![];
+    let mut outstanding = Money::zero(Currency::PLN);
+    let mut conn = db.acquire().await?;
+    for (order_id,) in order_ids {
+        let balance = payment::balance(&mut conn, order_id).await?;
+        outstanding = outstanding
+            .checked_add(balance.balance)
+            .ok_or(Error::OrderTotalOverflow)?;
+        orders.push(controllers::order::read(session.clone(), order_id, db.clone()).await?);
+    }
+    Ok(CustomerDetail {
+        customer,
+        orders,
+        outstanding,
+    })
+}
+
+pub async fn update(_session: Session, id: i32, payload: CustomerForUpdate, db: Db) -> Result<()> {
+    trace!(" -- CONTROLLER customer::update");
+    let result = sqlx::query(
+        "
+            UPDATE customers SET
+                name=COALESCE($1,name),
+                phone=COALESCE($2,phone),
+                email=COALESCE($3,email),
+                nip=COALESCE($4,nip),
+                notes=COALESCE($5,notes)
+            WHERE id=$6
+        ",
+    )
+    .bind(payload.name.as_deref().map(str::trim))
+    .bind(payload.phone)
+    .bind(payload.email)
+    .bind(payload.nip)
+    .bind(payload.notes)
+    .bind(id)
+    .execute(&db)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(Error::SQLEntityNotFound {
+            entity_type: "customer",
+            id,
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{
+        item::ItemForCreate,
+        order::{OrderForCreate, OrderForUpdate},
+        payment::{PaymentForCreate, PaymentMethod},
+        tax::VatRate,
+        time::DEFAULT_TIMEZONE,
+    };
+    use anyhow::Result;
+
+    fn customer(name: &str) -> CustomerForCreate {
+        CustomerForCreate {
+            name: name.to_owned(),
+            phone: None,
+            email: None,
+            nip: None,
+            notes: None,
+        }
+    }
+
+    #[sqlx::test]
+    async fn customer_search(pool: Db) -> Result<()> {
+        let mut kowalski = customer(" Jan Kowalski ");
+        kowalski.phone = Some("600100200".to_owned());
+        let id = create(Session::BASIC(), kowalski, pool.clone()).await?;
+        create(Session::BASIC(), customer("Nowak"), pool.clone()).await?;
+
+        let found = search(Session::BASIC(), "kowal".to_owned(), pool.clone()).await?;
+        assert_eq!(
+            found,
+            vec![CustomerResponseBasic {
+                id,
+                name: "Jan Kowalski".to_owned(),
+                phone: Some("600100200".to_owned()),
+                nip: None,
+            }]
+        );
+        let by_phone = search(Session::BASIC(), "100200".to_owned(), pool.clone()).await?;
+        assert_eq!(by_phone.len(), 1);
+        let all = search(Session::BASIC(), "".to_owned(), pool.clone()).await?;
+        assert_eq!(all.len(), 2);
+        // wildcards are matched literally
+        let none = search(Session::BASIC(), "_owa".to_owned(), pool.clone()).await?;
+        assert!(none.is_empty());
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn customer_read(pool: Db) -> Result<()> {
+        let id = create(Session::BASIC(), customer("Kowalski"), pool.clone()).await?;
+        let order_fc = OrderForCreate {
+            receiver: "Kowalski".to_owned(),
+            additional_info: None,
+            customer_id: Some(id),
+        };
+        let first = controllers::order::create(
+            Session::BASIC(),
+            order_fc.clone(),
+            DEFAULT_TIMEZONE,
+            pool.clone(),
+        )
+        .await?;
+        let second =
+            controllers::order::create(Session::BASIC(), order_fc, DEFAULT_TIMEZONE, pool.clone())
+                .await?;
+        let other = OrderForCreate {
+            receiver: "Nowak".to_owned(),
+            additional_info: None,
+            customer_id: None,
+        };
+        controllers::order::create(Session::BASIC(), other, DEFAULT_TIMEZONE, pool.clone()).await?;
+
+        let item = ItemForCreate {
+            quantity: "2".to_owned(),
+            name: "pędzel".to_owned(),
+            value: Money::pln(1500),
+            additional_info: None,
+            product_id: None,
+            vat_rate: VatRate::Vat23,
+            discount: None,
+        };
+        for order_id in [first, second] {
+            controllers::item::create(Session::BASIC(), item.clone(), order_id, pool.clone())
+                .await?;
+        }
+        let payment = PaymentForCreate {
+            amount: Money::pln(1000),
+            method: PaymentMethod::Cash,
+            note: None,
+        };
+        controllers::admin::payment::create(Session::FULL(), first, payment, pool.clone()).await?;
+
+        let detail = read(Session::BASIC(), id, pool.clone()).await?;
+        assert_eq!(detail.customer.name, "Kowalski");
+        let order_ids: Vec<i32> = detail.orders.iter().map(|order| order.id).collect();
+        assert_eq!(order_ids, vec![second, first]);
+        assert_eq!(detail.outstanding, Money::pln(5000));
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn customer_update(pool: Db) -> Result<()> {
+        let id = create(Session::BASIC(), customer("Kowalski"), pool.clone()).await?;
+        let payload = CustomerForUpdate {
+            nip: Some("1234567890".to_owned()),
+            ..Default::default()
+        };
+        update(Session::BASIC(), id, payload, pool.clone()).await?;
+
+        let detail = read(Session::BASIC(), id, pool.clone()).await?;
+        assert_eq!(detail.customer.name, "Kowalski");
+        assert_eq!(detail.customer.nip, Some("1234567890".to_owned()));
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn customer_not_found(pool: Db) -> Result<()> {
+        let err = || Error::SQLEntityNotFound {
+            entity_type: "customer",
+            id: 0,
+        };
+        let should_err = read(Session::BASIC(), 0, pool.clone()).await;
+        assert!(matches!(should_err, Err(e) if e == err()));
+        let should_err = update(Session::BASIC(), 0, Default::default(), pool.clone()).await;
+        assert_eq!(should_err, Err(err()));
+
+        // orders can't point to unknown customers
+        let order_fc = OrderForCreate {
+            receiver: "Kowalski".to_owned(),
+            additional_info: None,
+            customer_id: Some(0),
+        };
+        let should_err =
+            controllers::order::create(Session::BASIC(), order_fc, DEFAULT_TIMEZONE, pool.clone())
+                .await;
+        assert_eq!(should_err, Err(err()));
+        let order_fu = OrderForUpdate {
+            customer_id: Some(0),
+            ..Default::default()
+        };
+        let should_err = controllers::order::update(Session::BASIC(), 1, order_fu, pool).await;
+        assert_eq!(should_err, Err(err()));
+        Ok(())
+    }
+}
@@ -0,0 +1,1021 @@
+// Invoices of paid orders and their corrections (faktura korygująca).
+// Every document keeps a full copy of its lines and totals, a correction states the order
+// after the change and is compared with the document it corrects. Documents are numbered
+// per series and year the same way orders are and can't be changed once issued.
+
+use chrono::Datelike;
+use chrono_tz::Tz;
+use sqlx::PgConnection;
+use tracing::trace;
+
+use crate::{
+    controllers::{
+        order, payment,
+        receipt::{center, justify, render, wrap, Line, Page},
+    },
+    models::{
+        invoice::{
+            normalize_nip, CorrectionForCreate, Invoice, InvoiceDetail, InvoiceForCreate,
+            InvoiceKind, InvoiceLine, InvoiceRate,
+        },
+        item::Item,
+        money::Money,
+        order::OrderStatus,
+        receipt::{ReceiptLayout, ShopInfo},
+        tax::{Discount, LineTotals, OrderTotals, Totals},
+        time,
+        user::Privileges,
+    },
+    session::Session,
+    Db, Error, Result,
+};
+
+// served next to the XML, so documents can be checked without network access
+pub const SCHEMA: &str = include_str!("../../schemas/invoice.xsd");
+const NAMESPACE: &str = "urn:new-order:faktura:1";
+
+struct Party {
+    name: String,
+    address: Option<String>,
+    nip: Option<String>,
+}
+
+// empty optional texts are left out instead of printed empty
+fn non_empty(text: Option<String>) -> Option<String> {
+    text.map(|text| text.trim().to_owned())
+        .filter(|text| !text.is_empty())
+}
+
+fn nip_of(nip: Option<String>) -> Result<Option<String>> {
+    non_empty(nip).map(|nip| normalize_nip(&nip)).transpose()
+}
+
+async fn next_number(conn: &mut PgConnection, kind: InvoiceKind, year: i32) -> Result<String> {
+    let res: (i32,) = sqlx::query_as(
+        "
+            INSERT INTO invoice_number_counters (series,year,last_number) VALUES ($1,$2,1)
+            ON CONFLICT (series,year) DO UPDATE SET last_number=invoice_number_counters.last_number+1
+            RETURNING last_number
+        ",
+    )
+    .bind(kind.series())
+    .bind(year)
+    .fetch_one(&mut *conn)
+    .await?;
+    Ok(format!("{}/{year}/{:05}", kind.series(), res.0))
+}
+
+// the items the order has now, cancelled orders have none left
+async fn order_lines(
+    conn: &mut PgConnection,
+    order_id: i32,
+    status: OrderStatus,
+) -> Result<(Vec<(Item, LineTotals)>, OrderTotals)> {
+    let items: Vec<Item> = match status {
+        OrderStatus::Cancelled => vec![],
+        _ => {
+            sqlx::query_as(
+                "SELECT * FROM items WHERE order_id=$1 AND deleted=false ORDER BY position, id",
+            )
+            .bind(order_id)
+            .fetch_all(&mut *conn)
+            .await?
+        }
+    };
+    let discount: (Option<i64>, Option<Money>) =
+        sqlx::query_as("SELECT discount_percent, discount_amount FROM orders WHERE id=$1")
+            .bind(order_id)
+            .fetch_one(&mut *conn)
+            .await?;
+    let discount = Discount::from_columns(discount.0, discount.1);
+    let (line_totals, totals) = payment::totals(&items, discount)?;
+    Ok((items.into_iter().zip(line_totals).collect(), totals))
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn insert(
+    conn: &mut PgConnection,
+    session: &Session,
+    kind: InvoiceKind,
+    order_id: i32,
+    corrected: Option<(&Invoice, String)>,
+    issue_date: chrono::NaiveDate,
+    sale_date: chrono::NaiveDate,
+    seller: Party,
+    buyer: Party,
+) -> Result<i32> {
+    let status = order::lock_status(conn, order_id).await?;
+    let (lines, totals) = order_lines(conn, order_id, status).await?;
+    let time_created = chrono::Utc::now();
+    // numbered within the year of the issue date
+    let number = next_number(conn, kind, issue_date.year()).await?;
+    let (corrected_id, reason) = match corrected {
+        Some((invoice, reason)) => (Some(invoice.id), Some(reason)),
+        None => (None, None),
+    };
+    let res: (i32,) = sqlx::query_as(
+        "
+            INSERT INTO invoices
+                (number,kind,order_id,corrected_id,correction_reason,
+                issue_date,sale_date,
+                seller_name,seller_address,seller_nip,
+                buyer_name,buyer_address,buyer_nip,
+                net,vat,gross,creator_id,time_created)
+            VALUES
+                ($1,$2,$3,$4,$5,$6,$7,$8,$9,$10,$11,$12,$13,$14,$15,$16,$17,$18)
+            RETURNING id
+        ",
+    )
+    .bind(number)
+    .bind(kind)
+    .bind(order_id)
+    .bind(corrected_id)
+    .bind(reason)
+    .bind(issue_date)
+    .bind(sale_date)
+    .bind(seller.name)
+    .bind(seller.address)
+    .bind(seller.nip)
+    .bind(buyer.name)
+    .bind(buyer.address)
+    .bind(buyer.nip)
+    .bind(totals.totals.net)
+    .bind(totals.totals.vat)
+    .bind(totals.totals.gross)
+    .bind(session.id())
+    .bind(time_created)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    for (position, (item, line)) in lines.into_iter().enumerate() {
+        sqlx::query(
+            "
+                INSERT INTO invoice_lines
+                    (invoice_id,position,name,quantity,unit_price,vat_rate,discount,net,vat,gross)
+                VALUES
+                    ($1,$2,$3,$4,$5,$6,$7,$8,$9,$10)
+            ",
+        )
+        .bind(res.0)
+        .bind(position as i32 + 1)
+        .bind(item.name)
+        .bind(item.quantity)
+        .bind(item.value)
+        .bind(item.vat_rate)
+        .bind(line.discount)
+        .bind(line.totals.net)
+        .bind(line.totals.vat)
+        .bind(line.totals.gross)
+        .execute(&mut *conn)
+        .await?;
+    }
+    for rate in totals.by_rate {
+        sqlx::query(
+            "INSERT INTO invoice_rates (invoice_id,vat_rate,net,vat,gross) VALUES ($1,$2,$3,$4,$5)",
+        )
+        .bind(res.0)
+        .bind(rate.rate)
+        .bind(rate.totals.net)
+        .bind(rate.totals.vat)
+        .bind(rate.totals.gross)
+        .execute(&mut *conn)
+        .await?;
+    }
+    Ok(res.0)
+}
+
+pub async fn issue(
+    session: Session,
+    order_id: i32,
+    shop: ShopInfo,
+    payload: InvoiceForCreate,
+    db: Db,
+) -> Result<i32> {
+    trace!(" -- CONTROLLER invoice::issue");
+    if matches!(session.privileges(), Privileges::Basic) {
+        return Err(Error::AuthNoAccess);
+    }
+    if [&shop.name, &shop.address, &shop.nip]
+        .iter()
+        .any(|field| field.trim().is_empty())
+    {
+        return Err(Error::InvoiceMissingSellerData);
+    }
+    let timezone = shop.timezone;
+    let seller = Party {
+        name: shop.name,
+        address: Some(shop.address),
+        nip: Some(normalize_nip(&shop.nip)?),
+    };
+
+    let mut tx = db.begin().await?;
+    if order::lock_status(&mut tx, order_id).await? != OrderStatus::Paid {
+        return Err(Error::InvoiceOrderNotPaid);
+    }
+    let issued: Option<(i32,)> =
+        sqlx::query_as("SELECT id FROM invoices WHERE order_id=$1 AND kind='Invoice'")
+            .bind(order_id)
+            .fetch_optional(&mut *tx)
+            .await?;
+    if issued.is_some() {
+        return Err(Error::InvoiceAlreadyIssued);
+    }
+
+    let (receiver, customer_name, customer_nip): (String, Option<String>, Option<String>) =
+        sqlx::query_as(
+            "
+                SELECT orders.receiver, customers.name, customers.nip FROM orders
+                LEFT JOIN customers ON customers.id = orders.customer_id
+                WHERE orders.id=$1
+            ",
+        )
+        .bind(order_id)
+        .fetch_one(&mut *tx)
+        .await?;
+    let buyer = Party {
+        name: non_empty(payload.buyer_name)
+            .or(non_empty(customer_name))
+            .unwrap_or(receiver),
+        address: non_empty(payload.buyer_address),
+        nip: nip_of(payload.buyer_nip.or(customer_nip))?,
+    };
+    // sold when it was paid for
+    let paid: Option<(chrono::DateTime<chrono::Utc>,)> = sqlx::query_as(
+        "SELECT time_created FROM status_history WHERE order_id=$1 AND status='Paid' ORDER BY time_created DESC, id DESC LIMIT 1",
+    )
+    .bind(order_id)
+    .fetch_optional(&mut *tx)
+    .await?;
+    let issue_date = time::date_of(timezone, chrono::Utc::now());
+    let sale_date = paid.map_or(issue_date, |paid| time::date_of(timezone, paid.0));
+
+    let id = insert(
+        &mut tx,
+        &session,
+        InvoiceKind::Invoice,
+        order_id,
+        None,
+        issue_date,
+        sale_date,
+        seller,
+        buyer,
+    )
+    .await?;
+    tx.commit().await?;
+    Ok(id)
+}
+
+// corrects the latest document of an invoice to the current state of its order
+pub async fn correct(
+    session: Session,
+    invoice_id: i32,
+    timezone: Tz,
+    payload: CorrectionForCreate,
+    db: Db,
+) -> Result<i32> {
+    trace!(" -- CONTROLLER invoice::correct");
+    if matches!(session.privileges(), Privileges::Basic) {
+        return Err(Error::AuthNoAccess);
+    }
+    let reason = payload.reason.trim().to_owned();
+    if reason.is_empty() {
+        return Err(Error::InvoiceInvalidCorrection);
+    }
+
+    let mut tx = db.begin().await?;
+    let corrected: Invoice = sqlx::query_as("SELECT * FROM invoices WHERE id=$1 FOR UPDATE")
+        .bind(invoice_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or(Error::SQLEntityNotFound {
+            entity_type: "invoice",
+            id: invoice_id,
+        })?;
+    let corrected_already: Option<(i32,)> =
+        sqlx::query_as("SELECT id FROM invoices WHERE corrected_id=$1")
+            .bind(invoice_id)
+            .fetch_optional(&mut *tx)
+            .await?;
+    if corrected_already.is_some() {
+        return Err(Error::InvoiceInvalidCorrection);
+    }
+
+    let seller = Party {
+        name: corrected.seller_name.clone(),
+        address: Some(corrected.seller_address.clone()),
+        nip: Some(corrected.seller_nip.clone()),
+    };
+    let buyer = Party {
+        name: non_empty(payload.buyer_name).unwrap_or(corrected.buyer_name.clone()),
+        address: non_empty(payload.buyer_address).or(corrected.buyer_address.clone()),
+        nip: match payload.buyer_nip {
+            Some(nip) => nip_of(Some(nip))?,
+            None => corrected.buyer_nip.clone(),
+        },
+    };
+    let id = insert(
+        &mut tx,
+        &session,
+        InvoiceKind::Correction,
+        corrected.order_id,
+        Some((&corrected, reason)),
+        time::date_of(timezone, chrono::Utc::now()),
+        corrected.sale_date,
+        seller,
+        buyer,
+    )
+    .await?;
+    tx.commit().await?;
+    Ok(id)
+}
+
+pub async fn list(session: Session, db: Db) -> Result<Vec<Invoice>> {
+    trace!(" -- CONTROLLER invoice::list");
+    if matches!(session.privileges(), Privileges::Basic) {
+        return Err(Error::AuthNoAccess);
+    }
+    let res: Vec<Invoice> = sqlx::query_as("SELECT * FROM invoices ORDER BY id")
+        .fetch_all(&db)
+        .await?;
+    Ok(res)
+}
+
+pub async fn read(session: Session, invoice_id: i32, db: Db) -> Result<InvoiceDetail> {
+    trace!(" -- CONTROLLER invoice::read");
+    if matches!(session.privileges(), Privileges::Basic) {
+        return Err(Error::AuthNoAccess);
+    }
+    let invoice: Invoice = sqlx::query_as("SELECT * FROM invoices WHERE id=$1")
+        .bind(invoice_id)
+        .fetch_optional(&db)
+        .await?
+        .ok_or(Error::SQLEntityNotFound {
+            entity_type: "invoice",
+            id: invoice_id,
+        })?;
+    let lines: Vec<InvoiceLine> =
+        sqlx::query_as("SELECT * FROM invoice_lines WHERE invoice_id=$1 ORDER BY position")
+            .bind(invoice_id)
+            .fetch_all(&db)
+            .await?;
+    let rates: Vec<InvoiceRate> =
+        sqlx::query_as("SELECT * FROM invoice_rates WHERE invoice_id=$1 ORDER BY vat_rate")
+            .bind(invoice_id)
+            .fetch_all(&db)
+            .await?;
+    let corrected: Option<Invoice> = match invoice.corrected_id {
+        Some(corrected_id) => Some(
+            sqlx::query_as("SELECT * FROM invoices WHERE id=$1")
+                .bind(corrected_id)
+                .fetch_one(&db)
+                .await?,
+        ),
+        None => None,
+    };
+    let difference = match &corrected {
+        Some(corrected) => Some(difference(&corrected.totals(), &invoice.totals())?),
+        None => None,
+    };
+    let order_number: (String,) = sqlx::query_as("SELECT number FROM orders WHERE id=$1")
+        .bind(invoice.order_id)
+        .fetch_one(&db)
+        .await?;
+    Ok(InvoiceDetail {
+        invoice,
+        order_number: order_number.0,
+        lines,
+        rates,
+        corrected,
+        difference,
+    })
+}
+
+fn difference(before: &Totals, after: &Totals) -> Result<Totals> {
+    let sub =
+        |after: Money, before: Money| after.checked_sub(before).ok_or(Error::OrderTotalOverflow);
+    Ok(Totals {
+        net: sub(after.net, before.net)?,
+        vat: sub(after.vat, before.vat)?,
+        gross: sub(after.gross, before.gross)?,
+    })
+}
+
+pub async fn pdf(session: Session, invoice_id: i32, db: Db) -> Result<Vec<u8>> {
+    trace!(" -- CONTROLLER invoice::pdf");
+    let detail = read(session, invoice_id, db).await?;
+    let page = Page::of(ReceiptLayout::A4);
+    Ok(render(&invoice_text(&detail, page.columns()), &page))
+}
+
+fn invoice_text(detail: &InvoiceDetail, columns: usize) -> Vec<Line> {
+    let invoice = &detail.invoice;
+    let mut lines = vec![];
+    let title = match invoice.kind {
+        InvoiceKind::Invoice => "FAKTURA VAT",
+        InvoiceKind::Correction => "FAKTURA KORYGUJĄCA",
+    };
+    lines.push(Line::bold(center(
+        &format!("{title} nr {}", invoice.number),
+        columns,
+    )));
+    lines.push(Line::new("".to_owned()));
+    let dates = [
+        ("Data wystawienia:", invoice.issue_date),
+        ("Data sprzedaży:", invoice.sale_date),
+    ];
+    for (label, date) in dates {
+        lines.push(Line::new(format!("{label} {}", date.format("%d.%m.%Y"))));
+    }
+    lines.push(Line::new(format!("Zamówienie: {}", detail.order_number)));
+    if let Some(corrected) = &detail.corrected {
+        lines.push(Line::new(format!(
+            "Korekta dokumentu nr {} z dnia {}",
+            corrected.number,
+            corrected.issue_date.format("%d.%m.%Y")
+        )));
+        let reason = invoice.correction_reason.as_deref().unwrap_or_default();
+        for text in wrap(&format!("Przyczyna korekty: {reason}"), columns) {
+            lines.push(Line::new(text));
+        }
+    }
+    lines.push(Line::new("".to_owned()));
+
+    let parties = [
+        (
+            "Sprzedawca:",
+            &invoice.seller_name,
+            Some(&invoice.seller_address),
+            Some(&invoice.seller_nip),
+        ),
+        (
+            "Nabywca:",
+            &invoice.buyer_name,
+            invoice.buyer_address.as_ref(),
+            invoice.buyer_nip.as_ref(),
+        ),
+    ];
+    for (label, name, address, nip) in parties {
+        lines.push(Line::bold(label.to_owned()));
+        for text in [
+            Some(name.to_string()),
+            address.cloned(),
+            nip.map(|nip| format!("NIP: {nip}")),
+        ]
+        .into_iter()
+        .flatten()
+        {
+            for text in wrap(&text, columns) {
+                lines.push(Line::new(text));
+            }
+        }
+    }
+    let separator = "-".repeat(columns);
+    lines.push(Line::new(separator.clone()));
+
+    for line in &detail.lines {
+        for text in wrap(&format!("{}. {}", line.position, line.name), columns) {
+            lines.push(Line::new(text));
+        }
+        let mut left = format!(
+            "   {} x {}  VAT {}%",
+            line.quantity,
+            line.unit_price,
+            line.vat_rate.percent()
+        );
+        if line.discount.amount != 0 {
+            left.push_str(&format!("  rabat {}", line.discount));
+        }
+        for text in justify(&left, &line.gross.to_string(), columns) {
+            lines.push(Line::new(text));
+        }
+    }
+    lines.push(Line::new(separator));
+
+    let row = |label: &str, totals: &Totals| {
+        format!(
+            "{label:<20}{:>20}{:>20}{:>20}",
+            totals.net.to_string(),
+            totals.vat.to_string(),
+            totals.gross.to_string()
+        )
+    };
+    lines.push(Line::bold(format!(
+        "{:<20}{:>20}{:>20}{:>20}",
+        "Stawka", "Netto", "VAT", "Brutto"
+    )));
+    for rate in &detail.rates {
+        let totals = Totals {
+            net: rate.net,
+            vat: rate.vat,
+            gross: rate.gross,
+        };
+        lines.push(Line::new(row(
+            &format!("{}%", rate.vat_rate.percent()),
+            &totals,
+        )));
+    }
+    match (&detail.corrected, &detail.difference) {
+        (Some(corrected), Some(difference)) => {
+            lines.push(Line::new(row("Przed korektą", &corrected.totals())));
+            lines.push(Line::new(row("Po korekcie", &invoice.totals())));
+            lines.push(Line::bold(row("Różnica", difference)));
+        }
+        _ => lines.push(Line::bold(row("Razem", &invoice.totals()))),
+    }
+    lines
+}
+
+pub async fn xml(session: Session, invoice_id: i32, db: Db) -> Result<String> {
+    trace!(" -- CONTROLLER invoice::xml");
+    let detail = read(session, invoice_id, db).await?;
+    Ok(invoice_xml(&detail))
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// xs:decimal with two fraction digits, for example -12.05
+fn decimal(money: Money) -> String {
+    let sign = if money.is_negative() { "-" } else { "" };
+    let abs = money.amount.unsigned_abs();
+    format!("{sign}{}.{:02}", abs / 100, abs % 100)
+}
+
+struct XmlWriter {
+    out: String,
+    depth: usize,
+}
+
+impl XmlWriter {
+    fn open(&mut self, tag: &str) {
+        self.out
+            .push_str(&format!("{}<{tag}>\n", "    ".repeat(self.depth)));
+        self.depth += 1;
+    }
+    fn close(&mut self, tag: &str) {
+        self.depth -= 1;
+        self.out
+            .push_str(&format!("{}</{tag}>\n", "    ".repeat(self.depth)));
+    }
+    fn leaf(&mut self, tag: &str, text: &str) {
+        self.out.push_str(&format!(
+            "{}<{tag}>{}</{tag}>\n",
+            "    ".repeat(self.depth),
+            escape(text)
+        ));
+    }
+    fn totals(&mut self, totals: &Totals) {
+        self.leaf("Netto", &decimal(totals.net));
+        self.leaf("VAT", &decimal(totals.vat));
+        self.leaf("Brutto", &decimal(totals.gross));
+    }
+    fn party(&mut self, tag: &str, name: &str, address: Option<&str>, nip: Option<&str>) {
+        self.open(tag);
+        self.leaf("Nazwa", name);
+        if let Some(address) = address {
+            self.leaf("Adres", address);
+        }
+        if let Some(nip) = nip {
+            self.leaf("NIP", nip);
+        }
+        self.close(tag);
+    }
+}
+
+fn invoice_xml(detail: &InvoiceDetail) -> String {
+    let invoice = &detail.invoice;
+    let mut xml = XmlWriter {
+        out: format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<Faktura xmlns=\"{NAMESPACE}\">\n"
+        ),
+        depth: 1,
+    };
+    let kind = match invoice.kind {
+        InvoiceKind::Invoice => "VAT",
+        InvoiceKind::Correction => "KOR",
+    };
+    xml.leaf("Rodzaj", kind);
+    xml.leaf("Numer", &invoice.number);
+    xml.leaf("DataWystawienia", &invoice.issue_date.to_string());
+    xml.leaf("DataSprzedazy", &invoice.sale_date.to_string());
+    xml.leaf("Zamowienie", &detail.order_number);
+    xml.leaf("Waluta", invoice.gross.currency.code());
+    xml.party(
+        "Sprzedawca",
+        &invoice.seller_name,
+        Some(&invoice.seller_address),
+        Some(&invoice.seller_nip),
+    );
+    xml.party(
+        "Nabywca",
+        &invoice.buyer_name,
+        invoice.buyer_address.as_deref(),
+        invoice.buyer_nip.as_deref(),
+    );
+    if let (Some(corrected), Some(difference)) = (&detail.corrected, &detail.difference) {
+        xml.open("Korekta");
+        xml.leaf("NumerKorygowanej", &corrected.number);
+        xml.leaf("DataKorygowanej", &corrected.issue_date.to_string());
+        xml.leaf(
+            "Przyczyna",
+            invoice.correction_reason.as_deref().unwrap_or_default(),
+        );
+        xml.open("PrzedKorekta");
+        xml.totals(&corrected.totals());
+        xml.close("PrzedKorekta");
+        xml.open("Roznica");
+        xml.totals(difference);
+        xml.close("Roznica");
+        xml.close("Korekta");
+    }
+    xml.open("Wiersze");
+    for line in &detail.lines {
+        xml.out.push_str(&format!(
+            "{}<Wiersz lp=\"{}\">\n",
+            "    ".repeat(xml.depth),
+            line.position
+        ));
+        xml.depth += 1;
+        xml.leaf("Nazwa", &line.name);
+        xml.leaf("Ilosc", &line.quantity);
+        xml.leaf("CenaBrutto", &decimal(line.unit_price));
+        xml.leaf("StawkaVAT", &line.vat_rate.percent().to_string());
+        xml.leaf("Rabat", &decimal(line.discount));
+        xml.totals(&Totals {
+            net: line.net,
+            vat: line.vat,
+            gross: line.gross,
+        });
+        xml.close("Wiersz");
+    }
+    xml.close("Wiersze");
+    xml.open("PodsumowanieVAT");
+    for rate in &detail.rates {
+        xml.out.push_str(&format!(
+            "{}<Stawka stawka=\"{}\">\n",
+            "    ".repeat(xml.depth),
+            rate.vat_rate.percent()
+        ));
+        xml.depth += 1;
+        xml.totals(&Totals {
+            net: rate.net,
+            vat: rate.vat,
+            gross: rate.gross,
+        });
+        xml.close("Stawka");
+    }
+    xml.close("PodsumowanieVAT");
+    xml.open("Razem");
+    xml.totals(&invoice.totals());
+    xml.close("Razem");
+    xml.out.push_str("</Faktura>\n");
+    xml.out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        controllers,
+        models::{
+            item::ItemForCreate,
+            order::OrderForCreate,
+            payment::{PaymentForCreate, PaymentMethod},
+            tax::VatRate,
+            time::DEFAULT_TIMEZONE,
+        },
+    };
+    use anyhow::{Context, Result};
+
+    fn shop() -> ShopInfo {
+        ShopInfo {
+            name: "Farby Kowalski".to_owned(),
+            address: "ul. Kolorowa 1, Kraków".to_owned(),
+            nip: "123-456-32-18".to_owned(),
+            receipt_layout: ReceiptLayout::A4,
+            timezone: DEFAULT_TIMEZONE,
+        }
+    }
+
+    // order with 123zł at 23% and 108zł at 8%, returns (order_id, item_ids)
+    async fn setup(pool: &Db) -> Result<(i32, Vec<i32>)> {
+        let order_fc = OrderForCreate {
+            receiver: "tomek".to_owned(),
+            additional_info: None,
+            customer_id: None,
+        };
+        let order_id =
+            controllers::order::create(Session::BASIC(), order_fc, DEFAULT_TIMEZONE, pool.clone())
+                .await?;
+        let mut item_ids = vec![];
+        for (name, value, vat_rate) in [
+            ("biała farba", 12300, VatRate::Vat23),
+            ("poradnik malarza", 10800, VatRate::Vat8),
+        ] {
+            let item_fc = ItemForCreate {
+                quantity: "1".to_owned(),
+                name: name.to_owned(),
+                value: Money::pln(value),
+                additional_info: None,
+                product_id: None,
+                vat_rate,
+                discount: None,
+            };
+            item_ids.push(
+                controllers::item::create(Session::BASIC(), item_fc, order_id, pool.clone())
+                    .await?
+                    .0,
+            );
+        }
+        Ok((order_id, item_ids))
+    }
+
+    async fn pay(pool: &Db, order_id: i32, amount: i64) -> Result<()> {
+        let payment = PaymentForCreate {
+            amount: Money::pln(amount),
+            method: PaymentMethod::Card,
+            note: None,
+        };
+        controllers::admin::payment::create(Session::FULL(), order_id, payment, pool.clone())
+            .await?;
+        Ok(())
+    }
+
+    fn totals(net: i64, vat: i64, gross: i64) -> Totals {
+        Totals {
+            net: Money::pln(net),
+            vat: Money::pln(vat),
+            gross: Money::pln(gross),
+        }
+    }
+
+    #[sqlx::test]
+    async fn invoice_issue(pool: Db) -> Result<()> {
+        let (order_id, _) = setup(&pool).await?;
+        pay(&pool, order_id, 23100).await?;
+        let payload = InvoiceForCreate {
+            buyer_nip: Some("DE 123456789".to_owned()),
+            ..Default::default()
+        };
+        let id = issue(Session::FULL(), order_id, shop(), payload, pool.clone()).await?;
+
+        let detail = read(Session::FULL(), id, pool.clone()).await?;
+        let year = time::date_of(DEFAULT_TIMEZONE, chrono::Utc::now()).year();
+        assert_eq!(detail.invoice.number, format!("FV/{year}/00001"));
+        assert_eq!(detail.invoice.kind, InvoiceKind::Invoice);
+        assert_eq!(detail.invoice.seller_nip, "1234563218");
+        assert_eq!(detail.invoice.buyer_name, "tomek");
+        assert_eq!(detail.invoice.buyer_nip.as_deref(), Some("DE123456789"));
+        assert_eq!(detail.invoice.totals(), totals(20000, 3100, 23100));
+        let names: Vec<&str> = detail.lines.iter().map(|line| line.name.as_str()).collect();
+        assert_eq!(names, vec!["biała farba", "poradnik malarza"]);
+        let rates: Vec<(VatRate, Money)> = detail
+            .rates
+            .iter()
+            .map(|rate| (rate.vat_rate, rate.vat))
+            .collect();
+        assert_eq!(
+            rates,
+            vec![
+                (VatRate::Vat23, Money::pln(2300)),
+                (VatRate::Vat8, Money::pln(800))
+            ]
+        );
+        assert_eq!(detail.corrected, None);
+
+        let list = list(Session::FULL(), pool.clone()).await?;
+        assert_eq!(list.len(), 1);
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn invoice_issue_invalid(pool: Db) -> Result<()> {
+        let (order_id, _) = setup(&pool).await?;
+        let should_err = issue(
+            Session::FULL(),
+            order_id,
+            shop(),
+            Default::default(),
+            pool.clone(),
+        )
+        .await;
+        assert_eq!(should_err, Err(Error::InvoiceOrderNotPaid));
+
+        pay(&pool, order_id, 23100).await?;
+        let should_err = issue(
+            Session::BASIC(),
+            order_id,
+            shop(),
+            Default::default(),
+            pool.clone(),
+        )
+        .await;
+        assert_eq!(should_err, Err(Error::AuthNoAccess));
+        let no_nip = ShopInfo {
+            nip: "".to_owned(),
+            ..shop()
+        };
+        let should_err = issue(
+            Session::FULL(),
+            order_id,
+            no_nip,
+            Default::default(),
+            pool.clone(),
+        )
+        .await;
+        assert_eq!(should_err, Err(Error::InvoiceMissingSellerData));
+        let payload = InvoiceForCreate {
+            buyer_nip: Some("1234563219".to_owned()),
+            ..Default::default()
+        };
+        let should_err = issue(Session::FULL(), order_id, shop(), payload, pool.clone()).await;
+        assert_eq!(should_err, Err(Error::InvoiceInvalidNip));
+
+        issue(
+            Session::FULL(),
+            order_id,
+            shop(),
+            Default::default(),
+            pool.clone(),
+        )
+        .await?;
+        let should_err = issue(
+            Session::FULL(),
+            order_id,
+            shop(),
+            Default::default(),
+            pool.clone(),
+        )
+        .await;
+        assert_eq!(should_err, Err(Error::InvoiceAlreadyIssued));
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn invoice_correct(pool: Db) -> Result<()> {
+        let (order_id, item_ids) = setup(&pool).await?;
+        pay(&pool, order_id, 23100).await?;
+        let invoice_id = issue(
+            Session::FULL(),
+            order_id,
+            shop(),
+            Default::default(),
+            pool.clone(),
+        )
+        .await?;
+
+        // the book is returned and refunded
+        controllers::item::delete(Session::BASIC(), item_ids[1], None, pool.clone()).await?;
+        pay(&pool, order_id, -10800).await?;
+        let correction = CorrectionForCreate {
+            reason: "zwrot towaru".to_owned(),
+            ..Default::default()
+        };
+        let id = correct(
+            Session::FULL(),
+            invoice_id,
+            DEFAULT_TIMEZONE,
+            correction.clone(),
+            pool.clone(),
+        )
+        .await?;
+
+        let detail = read(Session::FULL(), id, pool.clone()).await?;
+        let year = time::date_of(DEFAULT_TIMEZONE, chrono::Utc::now()).year();
+        assert_eq!(detail.invoice.number, format!("FK/{year}/00001"));
+        assert_eq!(detail.invoice.kind, InvoiceKind::Correction);
+        assert_eq!(
+            detail.invoice.correction_reason.as_deref(),
+            Some("zwrot towaru")
+        );
+        assert_eq!(detail.invoice.seller_nip, "1234563218");
+        assert_eq!(detail.lines.len(), 1);
+        assert_eq!(detail.invoice.totals(), totals(10000, 2300, 12300));
+        assert_eq!(detail.corrected.map(|invoice| invoice.id), Some(invoice_id));
+        assert_eq!(detail.difference, Some(totals(-10000, -800, -10800)));
+
+        // only the latest document can be corrected
+        let should_err = correct(
+            Session::FULL(),
+            invoice_id,
+            DEFAULT_TIMEZONE,
+            correction.clone(),
+            pool.clone(),
+        )
+        .await;
+        assert_eq!(should_err, Err(Error::InvoiceInvalidCorrection));
+        let empty = CorrectionForCreate {
+            reason: " ".to_owned(),
+            ..Default::default()
+        };
+        let should_err = correct(Session::FULL(), id, DEFAULT_TIMEZONE, empty, pool.clone()).await;
+        assert_eq!(should_err, Err(Error::InvoiceInvalidCorrection));
+        correct(
+            Session::FULL(),
+            id,
+            DEFAULT_TIMEZONE,
+            correction,
+            pool.clone(),
+        )
+        .await?;
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn invoice_immutable(pool: Db) -> Result<()> {
+        let (order_id, _) = setup(&pool).await?;
+        pay(&pool, order_id, 23100).await?;
+        let id = issue(
+            Session::FULL(),
+            order_id,
+            shop(),
+            Default::default(),
+            pool.clone(),
+        )
+        .await?;
+
+        for query in [
+            "UPDATE invoices SET buyer_name='ktoś' WHERE id=$1",
+            "DELETE FROM invoices WHERE id=$1",
+            "UPDATE invoice_lines SET name='coś' WHERE invoice_id=$1",
+            "DELETE FROM invoice_rates WHERE invoice_id=$1",
+        ] {
+            let result = sqlx::query(query).bind(id).execute(&pool).await;
+            assert!(result.is_err(), "{query}");
+        }
+        let detail = read(Session::FULL(), id, pool.clone()).await?;
+        assert_eq!(detail.invoice.buyer_name, "tomek");
+        assert_eq!(detail.lines.len(), 2);
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn invoice_pdf_and_xml(pool: Db) -> Result<()> {
+        let (order_id, item_ids) = setup(&pool).await?;
+        pay(&pool, order_id, 23100).await?;
+        let payload = InvoiceForCreate {
+            buyer_name: Some("Malarze & Synowie <sp. j.>".to_owned()),
+            buyer_address: Some("ul. Szeroka 2, Kraków".to_owned()),
+            buyer_nip: Some("1234563218".to_owned()),
+        };
+        let invoice_id = issue(Session::FULL(), order_id, shop(), payload, pool.clone()).await?;
+        controllers::item::delete(Session::BASIC(), item_ids[0], None, pool.clone()).await?;
+        let correction = CorrectionForCreate {
+            reason: "zwrot farby".to_owned(),
+            ..Default::default()
+        };
+        let id = correct(
+            Session::FULL(),
+            invoice_id,
+            DEFAULT_TIMEZONE,
+            correction,
+            pool.clone(),
+        )
+        .await?;
+
+        for id in [invoice_id, id] {
+            let pdf = pdf(Session::FULL(), id, pool.clone()).await?;
+            assert!(pdf.starts_with(b"%PDF"));
+
+            let xml = xml(Session::FULL(), id, pool.clone()).await?;
+            assert!(xml.contains("<Nazwa>Malarze &amp; Synowie &lt;sp. j.&gt;</Nazwa>"));
+            validate(&xml)?;
+        }
+        let should_err = xml(Session::BASIC(), id, pool.clone()).await;
+        assert_eq!(should_err, Err(Error::AuthNoAccess));
+        Ok(())
+    }
+
+    // checks the document against the bundled schema, xmllint has to be installed
+    fn validate(xml: &str) -> Result<()> {
+        use std::{io::Write, process::Stdio};
+
+        let schema = concat!(env!("CARGO_MANIFEST_DIR"), "/schemas/invoice.xsd");
+        let mut xmllint = std::process::Command::new("xmllint")
+            .args(["--noout", "--schema", schema, "-"])
+            .stdin(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .context("xmllint (libxml2-utils) is needed to check invoices against the schema")?;
+        xmllint.stdin.take().unwrap().write_all(xml.as_bytes())?;
+        let output = xmllint.wait_with_output()?;
+        assert!(
+            output.status.success(),
+            "{}\n{xml}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn invoice_decimal_and_escape() {
+        assert_eq!(decimal(Money::pln(-1205)), "-12.05");
+        assert_eq!(decimal(Money::pln(-5)), "-0.05");
+        assert_eq!(decimal(Money::pln(100000)), "1000.00");
+        assert_eq!(escape("\"a\" & <b>"), "&quot;a&quot; &amp; &lt;b&gt;");
+    }
+}
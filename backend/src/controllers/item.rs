@@ -1,30 +1,75 @@
+use sqlx::PgConnection;
 use tracing::trace;
 
 use crate::{
-    models::item::{Item, ItemForCreate},
+    controllers::{order, stock},
+    models::{
+        item::{Item, ItemForCreate, ItemForUpdate},
+        order::OrderStatus,
+        quantity::Quantity,
+        tax::Discount,
+    },
     session::Session,
     Db, Error, Result,
 };
 
+// the id of the new item and whether the order status moved with it
 pub async fn create(
     session: Session,
     item_fc: ItemForCreate,
     order_id: i32,
     db: Db, /**/
-) -> Result<i32> {
+) -> Result<(i32, bool)> {
     trace!(" -- CONTROLLER item::create");
     let creator_id = session.id();
-    let time_created = chrono::Local::now().naive_local();
+    let mut tx = db.begin().await?;
+    let id = insert(&mut tx, creator_id, order_id, item_fc).await?;
+    let status_moved = order::sync(&mut tx, creator_id, order_id).await?;
+    tx.commit().await?;
+
+    Ok((id, status_moved))
+}
 
+// leaves syncing the order status to the caller
+pub async fn insert(
+    conn: &mut PgConnection,
+    creator_id: i32,
+    order_id: i32,
+    item_fc: ItemForCreate,
+) -> Result<i32> {
+    let time_created = chrono::Utc::now();
+    let quantity_parsed: Quantity = item_fc.quantity.parse()?;
+    let discount = item_fc.discount.map(Discount::validate).transpose()?;
+    let (discount_percent, discount_amount) = Discount::into_columns(discount);
+    if let Some(product_id) = item_fc.product_id {
+        let exists: Option<(i32,)> =
+            sqlx::query_as("SELECT id FROM products WHERE id=$1 AND active=true")
+                .bind(product_id)
+                .fetch_optional(&mut *conn)
+                .await?;
+        if exists.is_none() {
+            return Err(Error::SQLEntityNotFound {
+                entity_type: "product",
+                id: product_id,
+            });
+        }
+    }
+
+    // new items go last, the order row makes concurrent ones wait for each other
+    ensure_not_cancelled(order::lock_status(conn, order_id).await?)?;
     let res: (i32,) = sqlx::query_as(
         "
             INSERT INTO items
                 (order_id,creator_id,
                 time_created,quantity,
+                quantity_parsed,product_id,
                 name,value,
-                additional_info,deleted)
+                additional_info,deleted,
+                vat_rate,discount_percent,
+                discount_amount,position)
             VALUES
-                ($1,$2,$3,$4,$5,$6,$7,false)
+                ($1,$2,$3,$4,$5,$6,$7,$8,$9,false,$10,$11,$12,
+                (SELECT COALESCE(MAX(position),0)+1 FROM items WHERE order_id=$1))
             RETURNING id
         ",
     )
@@ -32,12 +77,16 @@ pub async fn create(
     .bind(creator_id)
     .bind(time_created)
     .bind(item_fc.quantity)
+    .bind(quantity_parsed)
+    .bind(item_fc.product_id)
     .bind(item_fc.name)
     .bind(item_fc.value)
     .bind(item_fc.additional_info)
-    .fetch_one(&db)
+    .bind(item_fc.vat_rate)
+    .bind(discount_percent)
+    .bind(discount_amount)
+    .fetch_one(&mut *conn)
     .await?;
-
     Ok(res.0)
 }
 
@@ -45,7 +94,7 @@ pub async fn read_where_order_id(_session: Session, order_id: i32, db: Db) -> Re
     trace!(" -- CONTROLLER item::read_where_order_id");
     let result: Vec<Item> = sqlx::query_as(
         "
-            SELECT * FROM items WHERE deleted=false AND order_id=$1 ORDER BY id
+            SELECT * FROM items WHERE deleted=false AND order_id=$1 ORDER BY position, id
         ",
     )
     .bind(order_id)
@@ -54,46 +103,283 @@ pub async fn read_where_order_id(_session: Session, order_id: i32, db: Db) -> Re
     Ok(result)
 }
 
-pub async fn delete(_session: Session, item_id: i32, db: Db) -> Result<()> {
+// items are addressed by their id under the order they belong to
+pub async fn ensure_in_order(db: &Db, order_id: i32, item_id: i32) -> Result<()> {
+    let exists: Option<(i32,)> =
+        sqlx::query_as("SELECT id FROM items WHERE id=$1 AND order_id=$2 AND deleted=false")
+            .bind(item_id)
+            .bind(order_id)
+            .fetch_optional(db)
+            .await?;
+    if exists.is_none() {
+        return Err(Error::SQLEntityNotFound {
+            entity_type: "item",
+            id: item_id,
+        });
+    }
+    Ok(())
+}
+
+// items of cancelled orders stay as they were, the cancellation already settled their stock
+pub fn ensure_not_cancelled(status: OrderStatus) -> Result<()> {
+    match status {
+        OrderStatus::Cancelled => Err(Error::ItemOrderCancelled),
+        _ => Ok(()),
+    }
+}
+
+// locks the order of the item before the item itself, the same order order::transition locks them in.
+// Missing items are left to the caller to report
+async fn lock_order(conn: &mut PgConnection, item_id: i32) -> Result<()> {
+    let status: Option<(OrderStatus,)> = sqlx::query_as(
+        "SELECT status FROM orders WHERE id=(SELECT order_id FROM items WHERE id=$1) FOR UPDATE",
+    )
+    .bind(item_id)
+    .fetch_optional(&mut *conn)
+    .await?;
+    match status {
+        Some((status,)) => ensure_not_cancelled(status),
+        None => Ok(()),
+    }
+}
+
+// true when the order status moved with the item
+pub async fn update(
+    session: Session,
+    item_id: i32,
+    item_fu: ItemForUpdate,
+    db: Db,
+) -> Result<bool> {
+    trace!(" -- CONTROLLER item::update");
+    let quantity_parsed = match &item_fu.quantity {
+        Some(quantity) => Some(quantity.parse::<Quantity>()?),
+        None => None,
+    };
+    let discount = item_fu
+        .discount
+        .map(|discount| discount.map(Discount::validate).transpose())
+        .transpose()?;
+    let (discount_percent, discount_amount) = Discount::into_columns(discount.flatten());
+
+    let requantified = quantity_parsed.is_some();
+    let mut tx = db.begin().await?;
+    lock_order(&mut tx, item_id).await?;
+    let item: Item = sqlx::query_as("SELECT * FROM items WHERE id=$1 AND deleted=false FOR UPDATE")
+        .bind(item_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or(Error::SQLEntityNotFound {
+            entity_type: "item",
+            id: item_id,
+        })?;
+    if item_fu
+        .version
+        .is_some_and(|version| version != item.version)
+    {
+        return Err(Error::VersionConflict {
+            entity_type: "item",
+            id: item_id,
+        });
+    }
+
+    sqlx::query(
+        "
+            UPDATE items SET
+                quantity=COALESCE($1,quantity),
+                quantity_parsed=COALESCE($2,quantity_parsed),
+                name=COALESCE($3,name),
+                value=COALESCE($4,value),
+                additional_info=COALESCE($5,additional_info),
+                checked=COALESCE($6,checked),
+                vat_rate=COALESCE($7,vat_rate),
+                discount_percent=CASE WHEN $8 THEN $9 ELSE discount_percent END,
+                discount_amount=CASE WHEN $8 THEN $10 ELSE discount_amount END,
+                updated_by=$11
+            WHERE id=$12
+        ",
+    )
+    .bind(item_fu.quantity)
+    .bind(quantity_parsed)
+    .bind(item_fu.name)
+    .bind(item_fu.value)
+    .bind(item_fu.additional_info)
+    .bind(item_fu.checked)
+    .bind(item_fu.vat_rate)
+    .bind(discount.is_some())
+    .bind(discount_percent)
+    .bind(discount_amount)
+    .bind(session.id())
+    .bind(item_id)
+    .execute(&mut *tx)
+    .await?;
+
+    if requantified {
+        stock::reissue_for_item(&mut tx, session.id(), item_id).await?;
+    }
+    if let Some(checked) = item_fu.checked {
+        follow_check(&mut tx, session.id(), &item, checked).await?;
+    }
+    let status_moved = order::sync(&mut tx, session.id(), item.order_id).await?;
+    tx.commit().await?;
+    Ok(status_moved)
+}
+
+// checked items leave the stock, unchecked ones come back unless the order was picked up or paid
+async fn follow_check(
+    conn: &mut PgConnection,
+    user_id: i32,
+    item: &Item,
+    checked: bool,
+) -> Result<()> {
+    match (item.checked, checked) {
+        (false, true) => stock::issue_for_item(conn, user_id, item.id).await,
+        (true, false) if !order::goods_out(conn, item.order_id).await? => {
+            stock::return_for_item(conn, user_id, item.id).await
+        }
+        _ => Ok(()),
+    }
+}
+
+// checks or unchecks a single item of an order locked by the caller, leaves syncing the order
+// status to the caller
+pub async fn set_checked(
+    conn: &mut PgConnection,
+    user_id: i32,
+    item: &Item,
+    checked: bool,
+) -> Result<()> {
+    sqlx::query("UPDATE items SET checked=$1, updated_by=$2 WHERE id=$3")
+        .bind(checked)
+        .bind(user_id)
+        .bind(item.id)
+        .execute(&mut *conn)
+        .await?;
+    follow_check(conn, user_id, item, checked).await
+}
+
+// item_ids lists every item of the order that isn't deleted, in the new order
+pub async fn reorder(session: Session, order_id: i32, item_ids: Vec<i32>, db: Db) -> Result<()> {
+    trace!(" -- CONTROLLER item::reorder");
+    let mut tx = db.begin().await?;
+    ensure_not_cancelled(order::lock_status(&mut tx, order_id).await?)?;
+    let current: Vec<(i32,)> = sqlx::query_as(
+        "SELECT id FROM items WHERE order_id=$1 AND deleted=false ORDER BY id FOR UPDATE",
+    )
+    .bind(order_id)
+    .fetch_all(&mut *tx)
+    .await?;
+    let current: Vec<i32> = current.into_iter().map(|item| item.0).collect();
+    let mut requested = item_ids.clone();
+    requested.sort_unstable();
+    if requested != current {
+        return Err(Error::ItemInvalidReorder);
+    }
+    sqlx::query(
+        "
+            UPDATE items SET position=new.position::INT, updated_by=$2
+            FROM UNNEST($1::INT[]) WITH ORDINALITY AS new(id,position)
+            WHERE items.id=new.id
+        ",
+    )
+    .bind(item_ids)
+    .bind(session.id())
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+// true when the order status moved with the item
+pub async fn delete(session: Session, item_id: i32, version: Option<i32>, db: Db) -> Result<bool> {
     trace!(" -- CONTROLLER item::delete");
-    let result = sqlx::query(
+    let mut tx = db.begin().await?;
+    lock_order(&mut tx, item_id).await?;
+    if let Some(version) = version {
+        let current: Option<(i32,)> = sqlx::query_as("SELECT version FROM items WHERE id=$1")
+            .bind(item_id)
+            .fetch_optional(&mut *tx)
+            .await?;
+        if current.is_some_and(|current| current.0 != version) {
+            return Err(Error::VersionConflict {
+                entity_type: "item",
+                id: item_id,
+            });
+        }
+    }
+    let result: Option<(i32,)> = sqlx::query_as(
         "
             UPDATE items
-            SET deleted = true
+            SET deleted = true, updated_by = $2
             WHERE id=$1
+            RETURNING order_id
         ",
     )
     .bind(item_id)
-    .execute(&db)
+    .bind(session.id())
+    .fetch_optional(&mut *tx)
     .await?;
 
-    if result.rows_affected() == 0 {
+    let Some((order_id,)) = result else {
         let err = Error::SQLEntityNotFound {
             entity_type: "item",
             id: item_id,
         };
         return Err(err);
+    };
+    // goods picked up or paid for have left the shop
+    if !order::goods_out(&mut tx, order_id).await? {
+        stock::return_for_item(&mut tx, session.id(), item_id).await?;
     }
-    Ok(())
+    let status_moved = order::sync(&mut tx, session.id(), order_id).await?;
+    tx.commit().await?;
+    Ok(status_moved)
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::models::item::{Item, ItemForCreate};
+    use crate::{
+        controllers,
+        models::{
+            item::{Item, ItemForCreate, ItemForUpdate},
+            money::Money,
+            order::OrderForCreate,
+            product::{ProductForCreate, ProductForUpdate},
+            quantity::Unit,
+            tax::VatRate,
+            time::DEFAULT_TIMEZONE,
+        },
+    };
     use anyhow::Result;
 
     use super::*;
 
+    async fn order(pool: &Db) -> Result<i32> {
+        let order_fc = OrderForCreate {
+            receiver: "tomek".to_owned(),
+            additional_info: None,
+            customer_id: None,
+        };
+        let id =
+            controllers::order::create(Session::BASIC(), order_fc, DEFAULT_TIMEZONE, pool.clone())
+                .await?;
+        Ok(id)
+    }
+
     #[sqlx::test]
     async fn item_create(pool: Db) -> Result<()> {
         // create new item
         let item_fc = ItemForCreate {
             quantity: "15kg".to_owned(),
             name: "bejca".to_owned(),
-            value: 13000,
+            value: Money::pln(13000),
             additional_info: None,
+            product_id: None,
+            vat_rate: VatRate::Vat23,
+            discount: None,
         };
-        let id = create(Session::BASIC(), item_fc, 0, pool.clone()).await?;
+        let id = create(Session::BASIC(), item_fc, order(&pool).await?, pool.clone())
+            .await?
+            .0;
         assert_eq!(id, 1); // on empty db first item should have 1 id
 
         // fetch item
@@ -112,16 +398,217 @@ mod tests {
         Ok(())
     }
 
+    #[sqlx::test]
+    async fn item_create_parses_quantity(pool: Db) -> Result<()> {
+        let item_fc = ItemForCreate {
+            quantity: "1,5 kg".to_owned(),
+            name: "bejca".to_owned(),
+            value: Money::pln(13000),
+            additional_info: None,
+            product_id: None,
+            vat_rate: VatRate::Vat23,
+            discount: None,
+        };
+        let id = create(Session::BASIC(), item_fc, order(&pool).await?, pool.clone())
+            .await?
+            .0;
+
+        let item: Item = sqlx::query_as("SELECT * FROM items WHERE id = $1")
+            .bind(id)
+            .fetch_one(&pool)
+            .await?;
+
+        // raw string is kept next to the parsed one
+        assert_eq!(item.quantity, "1,5 kg");
+        assert_eq!(item.quantity_parsed, Some(Quantity::new(1500, Unit::Kg)));
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn item_create_from_product(pool: Db) -> Result<()> {
+        let product = ProductForCreate {
+            name: "czerwona farba".to_owned(),
+            default_unit: Unit::L,
+            unit_price: Money::pln(3000),
+            category: None,
+            low_stock_threshold: None,
+        };
+        let product_id =
+            controllers::admin::product::create(Session::FULL(), product, pool.clone()).await?;
+        let item_fc = ItemForCreate {
+            quantity: "2l".to_owned(),
+            name: "czerwona farba".to_owned(),
+            value: Money::pln(3000),
+            additional_info: None,
+            product_id: Some(product_id),
+            vat_rate: VatRate::Vat23,
+            discount: None,
+        };
+        let id = create(Session::BASIC(), item_fc, order(&pool).await?, pool.clone())
+            .await?
+            .0;
+
+        // changing the catalog price doesn't change already ordered items
+        let update = ProductForUpdate {
+            name: Some("farba czerwona".to_owned()),
+            unit_price: Some(Money::pln(4000)),
+            ..Default::default()
+        };
+        controllers::admin::product::update(Session::FULL(), product_id, update, pool.clone())
+            .await?;
+
+        let item: Item = sqlx::query_as("SELECT * FROM items WHERE id = $1")
+            .bind(id)
+            .fetch_one(&pool)
+            .await?;
+        assert_eq!(item.product_id, Some(product_id));
+        assert_eq!(item.name, "czerwona farba");
+        assert_eq!(item.value, Money::pln(3000));
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn item_create_product_not_found(pool: Db) -> Result<()> {
+        let item_fc = ItemForCreate {
+            quantity: "2l".to_owned(),
+            name: "czerwona farba".to_owned(),
+            value: Money::pln(3000),
+            additional_info: None,
+            product_id: Some(7),
+            vat_rate: VatRate::Vat23,
+            discount: None,
+        };
+        let result = create(Session::BASIC(), item_fc, order(&pool).await?, pool.clone()).await;
+        assert_eq!(
+            result,
+            Err(crate::Error::SQLEntityNotFound {
+                entity_type: "product",
+                id: 7
+            })
+        );
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn item_create_order_not_found(pool: Db) -> Result<()> {
+        let item_fc = ItemForCreate {
+            quantity: "2l".to_owned(),
+            name: "czerwona farba".to_owned(),
+            value: Money::pln(3000),
+            additional_info: None,
+            product_id: None,
+            vat_rate: VatRate::Vat23,
+            discount: None,
+        };
+        let result = create(Session::BASIC(), item_fc, 7, pool.clone()).await;
+        assert_eq!(
+            result,
+            Err(crate::Error::SQLEntityNotFound {
+                entity_type: "order",
+                id: 7
+            })
+        );
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn item_update(pool: Db) -> Result<()> {
+        let item_fc = ItemForCreate {
+            quantity: "15kg".to_owned(),
+            name: "bejca".to_owned(),
+            value: Money::pln(13000),
+            additional_info: None,
+            product_id: None,
+            vat_rate: VatRate::Vat23,
+            discount: None,
+        };
+        let id = create(Session::BASIC(), item_fc, order(&pool).await?, pool.clone())
+            .await?
+            .0;
+
+        let item_fu = ItemForUpdate {
+            quantity: Some("2l".to_owned()),
+            name: None,
+            value: None,
+            additional_info: Some("ciemna".to_owned()),
+            checked: Some(true),
+            vat_rate: None,
+            discount: None,
+            version: None,
+        };
+        update(Session::BASIC(), id, item_fu, pool.clone()).await?;
+
+        let item: Item = sqlx::query_as("SELECT * FROM items WHERE id = $1")
+            .bind(id)
+            .fetch_one(&pool)
+            .await?;
+        assert_eq!(item.quantity, "2l");
+        assert_eq!(item.quantity_parsed, Some(Quantity::new(2000, Unit::L)));
+        assert_eq!(item.name, "bejca");
+        assert_eq!(item.additional_info, Some("ciemna".to_owned()));
+        assert!(item.checked);
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn item_update_entity_not_found(pool: Db) -> Result<()> {
+        let item_fu = ItemForUpdate {
+            quantity: None,
+            name: None,
+            value: None,
+            additional_info: None,
+            checked: Some(true),
+            vat_rate: None,
+            discount: None,
+            version: None,
+        };
+        let result = update(Session::BASIC(), 4, item_fu, pool.clone()).await;
+        assert_eq!(
+            result,
+            Err(crate::Error::SQLEntityNotFound {
+                entity_type: "item",
+                id: 4
+            })
+        );
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn item_create_invalid_quantity(pool: Db) -> Result<()> {
+        let item_fc = ItemForCreate {
+            quantity: "dużo".to_owned(),
+            name: "bejca".to_owned(),
+            value: Money::pln(13000),
+            additional_info: None,
+            product_id: None,
+            vat_rate: VatRate::Vat23,
+            discount: None,
+        };
+        let result = create(Session::BASIC(), item_fc, order(&pool).await?, pool.clone()).await;
+        assert_eq!(result, Err(crate::Error::ItemInvalidQuantity));
+
+        let count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM items")
+            .fetch_one(&pool)
+            .await?;
+        assert_eq!(count.0, 0);
+        Ok(())
+    }
+
     #[sqlx::test]
     async fn item_delete(pool: Db) -> Result<()> {
         // create new item
         let item_fc = ItemForCreate {
             quantity: "15kg".to_owned(),
             name: "bejca".to_owned(),
-            value: 13000,
+            value: Money::pln(13000),
             additional_info: None,
+            product_id: None,
+            vat_rate: VatRate::Vat23,
+            discount: None,
         };
-        let id = create(Session::BASIC(), item_fc, 0, pool.clone()).await?;
+        let id = create(Session::BASIC(), item_fc, order(&pool).await?, pool.clone())
+            .await?
+            .0;
 
         // fetch it
         let item: Item = sqlx::query_as("SELECT * FROM items WHERE id = $1")
@@ -133,7 +620,7 @@ mod tests {
         assert!(!item.deleted);
 
         // delete it
-        delete(Session::BASIC(), id, pool.clone()).await?;
+        delete(Session::BASIC(), id, None, pool.clone()).await?;
 
         // fetch it
         let item: Item = sqlx::query_as("SELECT * FROM items WHERE id = $1")
@@ -149,7 +636,7 @@ mod tests {
     #[sqlx::test]
     async fn item_delete_entity_not_found(pool: Db) -> Result<()> {
         let id = 4;
-        let result = delete(Session::BASIC(), id, pool.clone()).await;
+        let result = delete(Session::BASIC(), id, None, pool.clone()).await;
         assert_eq!(
             result,
             Err(crate::Error::SQLEntityNotFound {
@@ -169,24 +656,120 @@ mod tests {
             .map(|name| ItemForCreate {
                 name: name.to_string(),
                 quantity: "15kg".to_owned(),
-                value: 13000,
+                value: Money::pln(13000),
                 additional_info: None,
+                product_id: None,
+                vat_rate: VatRate::Vat23,
+                discount: None,
             })
             .collect();
         // push them to db with different order_id
-        let id1_order1 = create(Session::BASIC(), objects[0].clone(), 1, pool.clone()).await?;
-        let id2_order1 = create(Session::BASIC(), objects[1].clone(), 1, pool.clone()).await?;
-        let id3_order2 = create(Session::BASIC(), objects[2].clone(), 2, pool.clone()).await?;
+        let (order1, order2) = (order(&pool).await?, order(&pool).await?);
+        let id1_order1 = create(Session::BASIC(), objects[0].clone(), order1, pool.clone())
+            .await?
+            .0;
+        let id2_order1 = create(Session::BASIC(), objects[1].clone(), order1, pool.clone())
+            .await?
+            .0;
+        let id3_order2 = create(Session::BASIC(), objects[2].clone(), order2, pool.clone())
+            .await?
+            .0;
 
         // assert if filter works
-        let res = read_where_order_id(Session::BASIC(), 1, pool.clone()).await?;
+        let res = read_where_order_id(Session::BASIC(), order1, pool.clone()).await?;
         assert_eq!(res.len(), 2);
         assert_eq!(res[0].id, id1_order1);
         assert_eq!(res[1].id, id2_order1);
 
-        let res = read_where_order_id(Session::BASIC(), 2, pool.clone()).await?;
+        let res = read_where_order_id(Session::BASIC(), order2, pool.clone()).await?;
         assert_eq!(res.len(), 1);
         assert_eq!(res[0].id, id3_order2);
         Ok(())
     }
+
+    #[sqlx::test]
+    async fn item_positions_and_reorder(pool: Db) -> Result<()> {
+        let order_id = order(&pool).await?;
+        let item = |name: &str| ItemForCreate {
+            name: name.to_owned(),
+            quantity: "1".to_owned(),
+            value: Money::pln(1000),
+            additional_info: None,
+            product_id: None,
+            vat_rate: VatRate::Vat23,
+            discount: None,
+        };
+        let mut ids = vec![];
+        for name in ["bejca", "lakier", "klej"] {
+            ids.push(
+                create(Session::BASIC(), item(name), order_id, pool.clone())
+                    .await?
+                    .0,
+            );
+        }
+        // positions aren't reused after a delete
+        delete(Session::BASIC(), ids[1], None, pool.clone()).await?;
+        let brush = create(Session::BASIC(), item("pędzel"), order_id, pool.clone())
+            .await?
+            .0;
+        let positions = |items: Vec<Item>| -> Vec<(String, i32)> {
+            items
+                .into_iter()
+                .map(|item| (item.name, item.position))
+                .collect()
+        };
+        let items = read_where_order_id(Session::BASIC(), order_id, pool.clone()).await?;
+        assert_eq!(
+            positions(items),
+            vec![
+                ("bejca".to_owned(), 1),
+                ("klej".to_owned(), 3),
+                ("pędzel".to_owned(), 4)
+            ]
+        );
+
+        reorder(
+            Session::BASIC(),
+            order_id,
+            vec![brush, ids[0], ids[2]],
+            pool.clone(),
+        )
+        .await?;
+        let items = read_where_order_id(Session::BASIC(), order_id, pool.clone()).await?;
+        assert_eq!(
+            positions(items),
+            vec![
+                ("pędzel".to_owned(), 1),
+                ("bejca".to_owned(), 2),
+                ("klej".to_owned(), 3)
+            ]
+        );
+        let order = controllers::order::read(Session::BASIC(), order_id, pool.clone()).await?;
+        let names: Vec<&str> = order.items.iter().map(|item| item.name.as_str()).collect();
+        assert_eq!(names, vec!["pędzel", "bejca", "klej"]);
+
+        // every item that isn't deleted, exactly once
+        for item_ids in [
+            vec![brush, ids[0]],
+            vec![brush, ids[0], ids[2], ids[1]],
+            vec![brush, ids[0], ids[0]],
+            vec![brush, ids[0], ids[2], 99],
+        ] {
+            let should_err = reorder(Session::BASIC(), order_id, item_ids, pool.clone()).await;
+            assert_eq!(should_err, Err(Error::ItemInvalidReorder));
+        }
+
+        ensure_in_order(&pool, order_id, brush).await?;
+        for (order_id, item_id) in [(order_id + 1, brush), (order_id, ids[1])] {
+            let should_err = ensure_in_order(&pool, order_id, item_id).await;
+            assert_eq!(
+                should_err,
+                Err(Error::SQLEntityNotFound {
+                    entity_type: "item",
+                    id: item_id
+                })
+            );
+        }
+        Ok(())
+    }
 }
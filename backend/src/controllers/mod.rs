@@ -1,3 +1,24 @@
 pub mod admin;
+pub mod bulk;
+pub mod customer;
+pub mod invoice;
 pub mod item;
 pub mod order;
+pub mod payment;
+pub mod product;
+pub mod receipt;
+pub mod stock;
+pub mod template;
+
+// pattern matching the query anywhere in the text, for ILIKE ... ESCAPE '\'
+pub fn contains_pattern(query: &str) -> String {
+    let mut pattern = String::from("%");
+    for c in query.trim().chars() {
+        if matches!(c, '%' | '_' | '\\') {
+            pattern.push('\\');
+        }
+        pattern.push(c);
+    }
+    pattern.push('%');
+    pattern
+}
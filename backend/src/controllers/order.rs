@@ -1,59 +1,445 @@
+use chrono::Datelike;
+use chrono_tz::Tz;
+use sqlx::PgConnection;
 use tracing::trace;
 
 use crate::{
-    controllers,
+    controllers::{self, payment, stock},
     models::{
-        item::{Item, ItemResponseBasic},
-        order::{Order, OrderForCreate, OrderListParams, OrderResponseBasic},
+        item::{Item, ItemForCreate, ItemResponseBasic},
+        order::{
+            Order, OrderForCreate, OrderForUpdate, OrderListParams, OrderResponseBasic,
+            OrderStatus, StatusChange,
+        },
+        tax::Discount,
+        time,
     },
     session::Session,
     Db, Error, Result,
 };
 
-pub async fn create(session: Session, payload: OrderForCreate, db: Db) -> Result<i32> {
+// numbers restart with the year in the shop timezone
+pub async fn create(
+    session: Session,
+    payload: OrderForCreate,
+    timezone: Tz,
+    db: Db,
+) -> Result<i32> {
     trace!(" -- CONTROLLER order::create");
-    let creator_id = session.id();
-    let time_created = chrono::Local::now().naive_local();
-    let res: (i32, ) = sqlx::query_as("INSERT INTO orders (creator_id,time_created,receiver,additional_info,deleted,paid) VALUES ($1,$2,$3,$4, false,false) RETURNING id")
+    if let Some(customer_id) = payload.customer_id {
+        controllers::customer::ensure_active(&db, customer_id).await?;
+    }
+    let mut tx = db.begin().await?;
+    let id = insert(&mut tx, session.id(), timezone, payload).await?;
+    tx.commit().await?;
+
+    Ok(id)
+}
+
+pub async fn insert(
+    conn: &mut PgConnection,
+    creator_id: i32,
+    timezone: Tz,
+    payload: OrderForCreate,
+) -> Result<i32> {
+    let time_created = chrono::Utc::now();
+    let number = next_number(conn, time::date_of(timezone, time_created).year()).await?;
+    let res: (i32, ) = sqlx::query_as("INSERT INTO orders (number,creator_id,time_created,receiver,additional_info,status,customer_id) VALUES ($1,$2,$3,$4,$5,$6,$7) RETURNING id")
+        .bind(number)
         .bind(creator_id)
         .bind(time_created)
         .bind(payload.receiver)
         .bind(payload.additional_info)
-        .fetch_one(&db).await?;
-
+        .bind(OrderStatus::Submitted)
+        .bind(payload.customer_id)
+        .fetch_one(&mut *conn).await?;
+    insert_status_change(conn, creator_id, res.0, None, OrderStatus::Submitted).await?;
     Ok(res.0)
 }
 
-fn order_and_items_into_response(res: Order, items: Vec<Item>) -> OrderResponseBasic {
+// new order for the same receiver with copies of the items that weren't deleted,
+// cancelled orders can be repeated too
+pub async fn duplicate(session: Session, order_id: i32, timezone: Tz, db: Db) -> Result<i32> {
+    trace!(" -- CONTROLLER order::duplicate");
+    let order: Order = sqlx::query_as("SELECT * FROM orders WHERE id=$1")
+        .bind(order_id)
+        .fetch_optional(&db)
+        .await?
+        .ok_or(Error::SQLEntityNotFound {
+            entity_type: "order",
+            id: order_id,
+        })?;
+    let items: Vec<Item> = sqlx::query_as(
+        "SELECT * FROM items WHERE order_id=$1 AND deleted=false ORDER BY position, id",
+    )
+    .bind(order_id)
+    .fetch_all(&db)
+    .await?;
+    let order_fc = OrderForCreate {
+        receiver: order.receiver,
+        additional_info: order.additional_info,
+        customer_id: order.customer_id,
+    };
+    let items = items.into_iter().map(ItemForCreate::from).collect();
+
+    let mut tx = db.begin().await?;
+    let id = insert_copy(&mut tx, session.id(), timezone, order_fc, items).await?;
+    tx.commit().await?;
+    Ok(id)
+}
+
+// customers merged in the meantime are replaced by the one they were merged into and
+// items of products taken off sale lose the link to them
+pub async fn insert_copy(
+    conn: &mut PgConnection,
+    creator_id: i32,
+    timezone: Tz,
+    mut order_fc: OrderForCreate,
+    items: Vec<ItemForCreate>,
+) -> Result<i32> {
+    if let Some(customer_id) = order_fc.customer_id {
+        let customer: Option<(i32,)> =
+            sqlx::query_as("SELECT COALESCE(merged_into,id) FROM customers WHERE id=$1")
+                .bind(customer_id)
+                .fetch_optional(&mut *conn)
+                .await?;
+        order_fc.customer_id = customer.map(|customer| customer.0);
+    }
+    let order_id = insert(conn, creator_id, timezone, order_fc).await?;
+    for mut item_fc in items {
+        if let Some(product_id) = item_fc.product_id {
+            let active: Option<(i32,)> =
+                sqlx::query_as("SELECT id FROM products WHERE id=$1 AND active=true")
+                    .bind(product_id)
+                    .fetch_optional(&mut *conn)
+                    .await?;
+            item_fc.product_id = active.map(|product| product.0);
+        }
+        controllers::item::insert(conn, creator_id, order_id, item_fc).await?;
+    }
+    Ok(order_id)
+}
+
+// the counter row stays locked until the transaction ends, so concurrent orders wait
+// for each other and a rolled back order gives its number back
+async fn next_number(conn: &mut PgConnection, year: i32) -> Result<String> {
+    let res: (i32,) = sqlx::query_as(
+        "
+            INSERT INTO order_number_counters (year,last_number) VALUES ($1,1)
+            ON CONFLICT (year) DO UPDATE SET last_number=order_number_counters.last_number+1
+            RETURNING last_number
+        ",
+    )
+    .bind(year)
+    .fetch_one(&mut *conn)
+    .await?;
+    Ok(format_number(year, res.0))
+}
+
+fn format_number(year: i32, number: i32) -> String {
+    format!("ZAM/{year}/{number:05}")
+}
+
+async fn insert_status_change(
+    conn: &mut PgConnection,
+    user_id: i32,
+    order_id: i32,
+    previous_status: Option<OrderStatus>,
+    status: OrderStatus,
+) -> Result<()> {
+    let time_created = chrono::Utc::now();
+    sqlx::query(
+        "
+            INSERT INTO status_history
+                (order_id,previous_status,status,user_id,time_created)
+            VALUES
+                ($1,$2,$3,$4,$5)
+        ",
+    )
+    .bind(order_id)
+    .bind(previous_status)
+    .bind(status)
+    .bind(user_id)
+    .bind(time_created)
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+// stock follows the goods: they leave with picking up or paying and come back
+// when that is reverted (checked items stay out) or the order is cancelled
+async fn set_status(
+    conn: &mut PgConnection,
+    user_id: i32,
+    order_id: i32,
+    from: OrderStatus,
+    to: OrderStatus,
+) -> Result<()> {
+    let was_picked_up = picked_up(conn, order_id).await?;
+    let is_picked_up = to == OrderStatus::PickedUp || (to == OrderStatus::Paid && was_picked_up);
+    let was_out = from.is_goods_out() || was_picked_up;
+    let is_out = to.is_goods_out() || is_picked_up;
+    sqlx::query("UPDATE orders SET status=$1, picked_up=$2, updated_by=$3 WHERE id=$4")
+        .bind(to)
+        .bind(is_picked_up)
+        .bind(user_id)
+        .bind(order_id)
+        .execute(&mut *conn)
+        .await?;
+    insert_status_change(conn, user_id, order_id, Some(from), to).await?;
+
+    let items: Vec<(i32, bool)> =
+        sqlx::query_as("SELECT id, checked FROM items WHERE order_id=$1 AND deleted=false")
+            .bind(order_id)
+            .fetch_all(&mut *conn)
+            .await?;
+    for (item_id, checked) in items {
+        if is_out && !was_out {
+            stock::issue_for_item(conn, user_id, item_id).await?;
+        } else if to == OrderStatus::Cancelled || (was_out && !is_out && !checked) {
+            stock::return_for_item(conn, user_id, item_id).await?;
+        }
+    }
+    Ok(())
+}
+
+// paid or picked up, its goods have left the stock
+pub async fn goods_out(conn: &mut PgConnection, order_id: i32) -> Result<bool> {
+    let status = lock_status(conn, order_id).await?;
+    Ok(status.is_goods_out() || picked_up(conn, order_id).await?)
+}
+
+pub async fn picked_up(conn: &mut PgConnection, order_id: i32) -> Result<bool> {
+    let picked_up: (bool,) = sqlx::query_as("SELECT picked_up FROM orders WHERE id=$1")
+        .bind(order_id)
+        .fetch_optional(&mut *conn)
+        .await?
+        .ok_or(Error::SQLEntityNotFound {
+            entity_type: "order",
+            id: order_id,
+        })?;
+    Ok(picked_up.0)
+}
+
+// locks the order for the rest of the transaction
+pub async fn lock_status(conn: &mut PgConnection, order_id: i32) -> Result<OrderStatus> {
+    let status: (OrderStatus,) = sqlx::query_as("SELECT status FROM orders WHERE id=$1 FOR UPDATE")
+        .bind(order_id)
+        .fetch_optional(&mut *conn)
+        .await?
+        .ok_or(Error::SQLEntityNotFound {
+            entity_type: "order",
+            id: order_id,
+        })?;
+    Ok(status.0)
+}
+
+// locks the order like lock_status, fails when it changed since the client read the version
+pub async fn lock_version(
+    conn: &mut PgConnection,
+    order_id: i32,
+    version: Option<i32>,
+) -> Result<()> {
+    let current: (i32,) = sqlx::query_as("SELECT version FROM orders WHERE id=$1 FOR UPDATE")
+        .bind(order_id)
+        .fetch_optional(&mut *conn)
+        .await?
+        .ok_or(Error::SQLEntityNotFound {
+            entity_type: "order",
+            id: order_id,
+        })?;
+    if version.is_some_and(|version| version != current.0) {
+        return Err(Error::VersionConflict {
+            entity_type: "order",
+            id: order_id,
+        });
+    }
+    Ok(())
+}
+
+// paid is derived from the payments, see sync_with_payments. A paid order can still be picked
+// up, it is paid again right after
+pub async fn transition(
+    session: Session,
+    order_id: i32,
+    status: OrderStatus,
+    db: Db,
+) -> Result<()> {
+    trace!(" -- CONTROLLER order::transition");
+    let mut tx = db.begin().await?;
+    change_status(&mut tx, session.id(), order_id, status).await?;
+    sync_with_payments(&mut tx, session.id(), order_id).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+pub async fn change_status(
+    conn: &mut PgConnection,
+    user_id: i32,
+    order_id: i32,
+    status: OrderStatus,
+) -> Result<()> {
+    let from = lock_status(conn, order_id).await?;
+    let allowed = match (from, status) {
+        (_, OrderStatus::Paid) => false,
+        (OrderStatus::Paid, OrderStatus::PickedUp) => !picked_up(conn, order_id).await?,
+        (OrderStatus::Paid, _) => false,
+        _ => from.can_transition_to(status),
+    };
+    if !allowed {
+        return Err(Error::OrderInvalidTransition { from, to: status });
+    }
+    set_status(conn, user_id, order_id, from, status).await
+}
+
+// orders on the shop floor follow their items: in preparation once something is checked,
+// ready once everything is
+pub async fn sync_with_items(conn: &mut PgConnection, user_id: i32, order_id: i32) -> Result<bool> {
+    let status: Option<(OrderStatus,)> =
+        sqlx::query_as("SELECT status FROM orders WHERE id=$1 FOR UPDATE")
+            .bind(order_id)
+            .fetch_optional(&mut *conn)
+            .await?;
+    let Some((from,)) = status else {
+        return Ok(false);
+    };
+    let (total, checked): (i64, i64) = sqlx::query_as(
+        "SELECT COUNT(*), COUNT(*) FILTER (WHERE checked) FROM items WHERE order_id=$1 AND deleted=false",
+    )
+    .bind(order_id)
+    .fetch_one(&mut *conn)
+    .await?;
+    let to = match from {
+        OrderStatus::Submitted | OrderStatus::InPreparation if total > 0 && checked == total => {
+            OrderStatus::Ready
+        }
+        OrderStatus::Submitted if checked > 0 => OrderStatus::InPreparation,
+        OrderStatus::Ready if checked < total => OrderStatus::InPreparation,
+        _ => return Ok(false),
+    };
+    set_status(conn, user_id, order_id, from, to).await?;
+    Ok(true)
+}
+
+// paid once payments cover a non empty order, back to the status from before the payment
+// when a refund or a new item leaves something to pay
+pub async fn sync_with_payments(
+    conn: &mut PgConnection,
+    user_id: i32,
+    order_id: i32,
+) -> Result<bool> {
+    let status: Option<(OrderStatus,)> =
+        sqlx::query_as("SELECT status FROM orders WHERE id=$1 FOR UPDATE")
+            .bind(order_id)
+            .fetch_optional(&mut *conn)
+            .await?;
+    let Some((from,)) = status else {
+        return Ok(false);
+    };
+    let balance = payment::balance(conn, order_id).await?;
+    let covered = balance.total.amount > 0 && balance.balance.amount <= 0;
+    let to = if covered && from != OrderStatus::Paid && from.can_transition_to(OrderStatus::Paid) {
+        OrderStatus::Paid
+    } else if !covered && from == OrderStatus::Paid {
+        // orders migrated as paid have no history
+        let previous: Option<(Option<OrderStatus>,)> = sqlx::query_as(
+            "
+                SELECT previous_status FROM status_history
+                WHERE order_id=$1 AND status='Paid'
+                ORDER BY time_created DESC, id DESC LIMIT 1
+            ",
+        )
+        .bind(order_id)
+        .fetch_optional(&mut *conn)
+        .await?;
+        previous
+            .and_then(|previous| previous.0)
+            .unwrap_or(OrderStatus::Ready)
+    } else {
+        return Ok(false);
+    };
+    set_status(conn, user_id, order_id, from, to).await?;
+    Ok(true)
+}
+
+// both of the above after the items of the order changed, true when the status moved
+pub async fn sync(conn: &mut PgConnection, user_id: i32, order_id: i32) -> Result<bool> {
+    let followed_items = sync_with_items(conn, user_id, order_id).await?;
+    let followed_payments = sync_with_payments(conn, user_id, order_id).await?;
+    Ok(followed_items || followed_payments)
+}
+
+pub async fn history(_session: Session, order_id: i32, db: Db) -> Result<Vec<StatusChange>> {
+    trace!(" -- CONTROLLER order::history");
+    let exists: Option<(i32,)> = sqlx::query_as("SELECT id FROM orders WHERE id=$1")
+        .bind(order_id)
+        .fetch_optional(&db)
+        .await?;
+    if exists.is_none() {
+        return Err(Error::SQLEntityNotFound {
+            entity_type: "order",
+            id: order_id,
+        });
+    }
+    let res: Vec<StatusChange> =
+        sqlx::query_as("SELECT * FROM status_history WHERE order_id=$1 ORDER BY time_created, id")
+            .bind(order_id)
+            .fetch_all(&db)
+            .await?;
+    Ok(res)
+}
+
+fn order_and_items_into_response(res: Order, items: Vec<Item>) -> Result<OrderResponseBasic> {
+    let discount = res.discount();
+    let (line_totals, totals) = payment::totals(&items, discount)?;
     let mapped_items = items
         .iter()
-        .map(|item| ItemResponseBasic {
+        .zip(line_totals)
+        .map(|(item, totals)| ItemResponseBasic {
             id: item.id,
             order_id: item.order_id,
             time_created: item.time_created,
             quantity: item.quantity.to_string(),
+            quantity_parsed: item.quantity_parsed,
+            product_id: item.product_id,
             name: item.name.to_string(),
             value: item.value,
             additional_info: item.additional_info.to_owned(),
             checked: item.checked,
+            vat_rate: item.vat_rate,
+            discount: item.discount(),
+            totals,
+            position: item.position,
+            version: item.version,
+            updated_at: item.updated_at,
+            updated_by: item.updated_by,
         })
         .collect::<Vec<_>>();
 
     // combine output
 
-    OrderResponseBasic {
+    Ok(OrderResponseBasic {
         id: res.id,
+        number: res.number,
         time_created: res.time_created,
         receiver: res.receiver,
         additional_info: res.additional_info,
+        status: res.status,
+        picked_up: res.picked_up,
+        customer_id: res.customer_id,
+        discount,
+        totals,
         items: mapped_items,
-    }
+        version: res.version,
+        updated_at: res.updated_at,
+        updated_by: res.updated_by,
+    })
 }
 pub async fn read(session: Session, payload: i32, db: Db) -> Result<OrderResponseBasic> {
     trace!(" -- CONTROLLER order::read");
 
     // get order data
-    let res: Order = sqlx::query_as("SELECT * FROM orders WHERE id = $1 and deleted=false")
+    let res: Order = sqlx::query_as("SELECT * FROM orders WHERE id = $1 and status<>'Cancelled'")
         .bind(payload)
         .fetch_optional(&db)
         .await?
@@ -65,7 +451,7 @@ pub async fn read(session: Session, payload: i32, db: Db) -> Result<OrderRespons
     let items = controllers::item::read_where_order_id(session, res.id, db.clone()).await?;
 
     // combine
-    let combined = order_and_items_into_response(res, items);
+    let combined = order_and_items_into_response(res, items)?;
 
     // return
     Ok(combined)
@@ -73,33 +459,53 @@ pub async fn read(session: Session, payload: i32, db: Db) -> Result<OrderRespons
 
 pub async fn list(session: Session, db: Db) -> Result<Vec<OrderResponseBasic>> {
     trace!(" -- CONTROLLER order::list");
-    let res: Vec<Order> = sqlx::query_as("SELECT * FROM orders WHERE deleted=false ORDER BY id")
-        .fetch_all(&db)
-        .await?;
+    let res: Vec<Order> =
+        sqlx::query_as("SELECT * FROM orders WHERE status<>'Cancelled' ORDER BY id")
+            .fetch_all(&db)
+            .await?;
     let mut mapped = vec![];
     for order in res {
         let items =
             controllers::item::read_where_order_id(session.clone(), order.id, db.clone()).await?;
-        mapped.push(order_and_items_into_response(order, items));
+        mapped.push(order_and_items_into_response(order, items)?);
     }
     Ok(mapped)
 }
 
+// dates are days in the shop timezone
 pub async fn list_with_params(
     session: Session,
     params: OrderListParams,
+    timezone: Tz,
     db: Db,
 ) -> Result<Vec<OrderResponseBasic>> {
     trace!(" -- CONTROLLER order::list_with_params");
 
-    let mut builder = sqlx::QueryBuilder::new("SELECT * FROM orders WHERE deleted=false ");
+    let mut builder = sqlx::QueryBuilder::new("SELECT * FROM orders WHERE ");
+    match params.modified_since {
+        None => builder.push("status<>'Cancelled' "),
+        Some(since) => {
+            builder.push("(updated_at >= ");
+            builder.push_bind(since);
+            builder.push(
+                " OR EXISTS (SELECT id FROM items WHERE order_id=orders.id AND updated_at >= ",
+            );
+            builder.push_bind(since);
+            builder.push("))")
+        }
+    };
     if let Some(ds) = params.date_start {
-        builder.push(" AND time_created::date >= ");
-        builder.push_bind(ds);
+        builder.push(" AND time_created >= ");
+        builder.push_bind(time::day_start(timezone, ds));
     }
     if let Some(de) = params.date_end {
-        builder.push(" AND time_created::date <= ");
-        builder.push_bind(de);
+        builder.push(" AND time_created < ");
+        builder.push_bind(time::day_end(timezone, de));
+    }
+    if let Some(number) = params.number {
+        builder.push(" AND number ILIKE ");
+        builder.push_bind(controllers::contains_pattern(&number));
+        builder.push(" ESCAPE '\\'");
     }
     builder.push(" ORDER BY id");
     let query = builder.build_query_as::<Order>();
@@ -108,48 +514,94 @@ pub async fn list_with_params(
     for order in res {
         let items =
             controllers::item::read_where_order_id(session.clone(), order.id, db.clone()).await?;
-        mapped.push(order_and_items_into_response(order, items));
+        mapped.push(order_and_items_into_response(order, items)?);
     }
     Ok(mapped)
 }
 
-pub async fn delete(_session: Session, id: i32, db: Db) -> Result<()> {
-    trace!(" -- CONTROLLER order::delete");
-
+pub async fn update(session: Session, id: i32, payload: OrderForUpdate, db: Db) -> Result<()> {
+    trace!(" -- CONTROLLER order::update");
+    if let Some(customer_id) = payload.customer_id {
+        controllers::customer::ensure_active(&db, customer_id).await?;
+    }
+    let discount = payload
+        .discount
+        .map(|discount| discount.map(Discount::validate).transpose())
+        .transpose()?;
+    let (discount_percent, discount_amount) = Discount::into_columns(discount.flatten());
+    let mut tx = db.begin().await?;
+    lock_version(&mut tx, id, payload.version).await?;
     let result = sqlx::query(
         "
-        UPDATE orders SET deleted=true WHERE id=$1
-    ",
+            UPDATE orders SET
+                receiver=COALESCE($1,receiver),
+                additional_info=COALESCE($2,additional_info),
+                customer_id=COALESCE($3,customer_id),
+                discount_percent=CASE WHEN $4 THEN $5 ELSE discount_percent END,
+                discount_amount=CASE WHEN $4 THEN $6 ELSE discount_amount END,
+                updated_by=$7
+            WHERE id=$8 AND status<>'Cancelled'
+        ",
     )
+    .bind(payload.receiver)
+    .bind(payload.additional_info)
+    .bind(payload.customer_id)
+    .bind(discount.is_some())
+    .bind(discount_percent)
+    .bind(discount_amount)
+    .bind(session.id())
     .bind(id)
-    .execute(&db)
+    .execute(&mut *tx)
     .await?;
-
     if result.rows_affected() == 0 {
         return Err(Error::SQLEntityNotFound {
             entity_type: "order",
             id,
         });
     }
+    // a discount changes what is left to pay
+    if discount.is_some() {
+        sync_with_payments(&mut tx, session.id(), id).await?;
+    }
+    tx.commit().await?;
+    Ok(())
+}
 
+pub async fn delete(session: Session, id: i32, version: Option<i32>, db: Db) -> Result<()> {
+    trace!(" -- CONTROLLER order::delete");
+    let mut tx = db.begin().await?;
+    lock_version(&mut tx, id, version).await?;
+    change_status(&mut tx, session.id(), id, OrderStatus::Cancelled).await?;
+    tx.commit().await?;
     Ok(())
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::models::item::ItemForCreate;
+    use crate::models::{
+        customer::CustomerForCreate,
+        item::{ItemForCreate, ItemForUpdate},
+        money::Money,
+        payment::{PaymentForCreate, PaymentMethod},
+        tax::{Discount, VatRate},
+        time::DEFAULT_TIMEZONE,
+        user::Privileges,
+    };
 
     use super::*;
     use anyhow::Result;
-    use chrono::NaiveDateTime;
+    use chrono::{DateTime, Utc};
 
     #[sqlx::test]
     async fn order_create(pool: Db) -> Result<()> {
         let order_fc = OrderForCreate {
             receiver: "tomek".to_owned(),
             additional_info: None,
+            customer_id: None,
         };
-        let id = controllers::order::create(Session::BASIC(), order_fc, pool.clone()).await?;
+        let id =
+            controllers::order::create(Session::BASIC(), order_fc, DEFAULT_TIMEZONE, pool.clone())
+                .await?;
         assert_eq!(id, 1); // first db item id should be 1
 
         let order: Order = sqlx::query_as("SELECT * FROM orders")
@@ -158,37 +610,134 @@ mod tests {
         assert_eq!(order.id, id);
         assert_eq!(order.receiver, "tomek");
         assert_eq!(order.creator_id, Session::BASIC().id());
-        assert!(!order.deleted);
-        assert!(!order.paid);
+        assert_eq!(order.status, OrderStatus::Submitted);
+        let year = time::date_of(DEFAULT_TIMEZONE, chrono::Utc::now()).year();
+        assert_eq!(order.number, format!("ZAM/{year}/00001"));
 
         Ok(())
     }
 
+    #[sqlx::test]
+    async fn order_number_per_year(pool: Db) -> Result<()> {
+        let mut tx = pool.begin().await?;
+        assert_eq!(next_number(&mut tx, 2025).await?, "ZAM/2025/00001");
+        assert_eq!(next_number(&mut tx, 2025).await?, "ZAM/2025/00002");
+        assert_eq!(next_number(&mut tx, 2026).await?, "ZAM/2026/00001");
+        tx.commit().await?;
+
+        // a rolled back order leaves no gap
+        let mut tx = pool.begin().await?;
+        assert_eq!(next_number(&mut tx, 2026).await?, "ZAM/2026/00002");
+        tx.rollback().await?;
+        let mut tx = pool.begin().await?;
+        assert_eq!(next_number(&mut tx, 2026).await?, "ZAM/2026/00002");
+        tx.commit().await?;
+
+        assert_eq!(format_number(2026, 123456), "ZAM/2026/123456");
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn order_number_concurrent(pool: Db) -> Result<()> {
+        let mut tasks = vec![];
+        for _ in 0..10 {
+            let order_fc = OrderForCreate {
+                receiver: "tomek".to_owned(),
+                additional_info: None,
+                customer_id: None,
+            };
+            tasks.push(tokio::spawn(create(
+                Session::BASIC(),
+                order_fc,
+                DEFAULT_TIMEZONE,
+                pool.clone(),
+            )));
+        }
+        for task in tasks {
+            task.await??;
+        }
+
+        let numbers: Vec<(String,)> = sqlx::query_as("SELECT number FROM orders ORDER BY number")
+            .fetch_all(&pool)
+            .await?;
+        let year = time::date_of(DEFAULT_TIMEZONE, chrono::Utc::now()).year();
+        let expected: Vec<String> = (1..=10).map(|number| format_number(year, number)).collect();
+        let numbers: Vec<String> = numbers.into_iter().map(|number| number.0).collect();
+        assert_eq!(numbers, expected);
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn order_list_with_number(pool: Db) -> Result<()> {
+        for receiver in ["tomek", "janek", "franek"] {
+            let order_fc = OrderForCreate {
+                receiver: receiver.to_owned(),
+                additional_info: None,
+                customer_id: None,
+            };
+            create(Session::BASIC(), order_fc, DEFAULT_TIMEZONE, pool.clone()).await?;
+        }
+        let params = OrderListParams {
+            date_start: None,
+            date_end: None,
+            number: Some(" 00002 ".to_owned()),
+            modified_since: None,
+        };
+        let output =
+            list_with_params(Session::BASIC(), params, DEFAULT_TIMEZONE, pool.clone()).await?;
+        assert_eq!(output.len(), 1);
+        assert_eq!(output[0].receiver, "janek");
+
+        // wildcards are matched literally
+        let params = OrderListParams {
+            date_start: None,
+            date_end: None,
+            number: Some("0000_".to_owned()),
+            modified_since: None,
+        };
+        let output = list_with_params(Session::BASIC(), params, DEFAULT_TIMEZONE, pool).await?;
+        assert!(output.is_empty());
+        Ok(())
+    }
+
     #[sqlx::test]
     async fn order_read(pool: Db) -> Result<()> {
         let item_fc = ItemForCreate {
             quantity: "1".to_owned(),
             name: "1".to_owned(),
-            value: 1,
+            value: Money::pln(1),
             additional_info: None,
+            product_id: None,
+            vat_rate: VatRate::Vat23,
+            discount: None,
         };
-        let id1 =
-            controllers::item::create(Session::BASIC(), item_fc.clone(), 1, pool.clone()).await?;
-        let _id2 = controllers::item::create(Session::BASIC(), item_fc, 1, pool.clone()).await?;
-
         let order_fc = OrderForCreate {
             receiver: "tomek".to_owned(),
             additional_info: None,
+            customer_id: None,
         };
-        let order_id_1 =
-            controllers::order::create(Session::BASIC(), order_fc.clone(), pool.clone()).await?;
+        let order_id_1 = controllers::order::create(
+            Session::BASIC(),
+            order_fc.clone(),
+            DEFAULT_TIMEZONE,
+            pool.clone(),
+        )
+        .await?;
 
         let order_id_2 =
-            controllers::order::create(Session::BASIC(), order_fc, pool.clone()).await?;
+            controllers::order::create(Session::BASIC(), order_fc, DEFAULT_TIMEZONE, pool.clone())
+                .await?;
 
         assert_eq!(order_id_1, 1); // first_item should have id of 1
         assert_eq!(order_id_2, 2);
 
+        let id1 = controllers::item::create(Session::BASIC(), item_fc.clone(), 1, pool.clone())
+            .await?
+            .0;
+        let _id2 = controllers::item::create(Session::BASIC(), item_fc, 1, pool.clone())
+            .await?
+            .0;
+
         // fetch
 
         let order1 = controllers::order::read(Session::BASIC(), order_id_1, pool.clone()).await?;
@@ -200,14 +749,14 @@ mod tests {
         assert_eq!(order1.items[0].id, id1);
         assert_eq!(order1.receiver, "tomek");
 
-        let time = NaiveDateTime::UNIX_EPOCH;
+        let time = DateTime::<Utc>::UNIX_EPOCH;
         // test deleted user
         let id: (i32,) = sqlx::query_as(
             "
             INSERT INTO orders
-                (creator_id,time_created,receiver,additional_info,deleted,paid)
+                (number,creator_id,time_created,receiver,additional_info,status)
             VALUES
-                (0,$1,'tomek',NULL,true,false) RETURNING id
+                ('ZAM/2005/00001',0,$1,'tomek',NULL,'Cancelled') RETURNING id
                 ",
         )
         .bind(time)
@@ -247,15 +796,22 @@ mod tests {
         let order_fc = OrderForCreate {
             receiver: "tomek".to_owned(),
             additional_info: None,
+            customer_id: None,
         };
-        let order_id_1 =
-            controllers::order::create(Session::BASIC(), order_fc.clone(), pool.clone()).await?;
+        let order_id_1 = controllers::order::create(
+            Session::BASIC(),
+            order_fc.clone(),
+            DEFAULT_TIMEZONE,
+            pool.clone(),
+        )
+        .await?;
         let one = controllers::order::list(Session::BASIC(), pool.clone()).await?;
         assert_eq!(one.len(), 1);
         assert_eq!(one[0].id, order_id_1);
 
         let order_id_2 =
-            controllers::order::create(Session::BASIC(), order_fc, pool.clone()).await?;
+            controllers::order::create(Session::BASIC(), order_fc, DEFAULT_TIMEZONE, pool.clone())
+                .await?;
         let both = controllers::order::list(Session::BASIC(), pool.clone()).await?;
         assert_eq!(both.len(), 2);
         assert_eq!(both[1].id, order_id_2);
@@ -267,17 +823,29 @@ mod tests {
         let order_fc = OrderForCreate {
             receiver: "tomek".to_owned(),
             additional_info: None,
+            customer_id: None,
         };
-        let _order_id_1 =
-            controllers::order::create(Session::BASIC(), order_fc.clone(), pool.clone()).await?;
-        let order_id_2 =
-            controllers::order::create(Session::BASIC(), order_fc.clone(), pool.clone()).await?;
+        let _order_id_1 = controllers::order::create(
+            Session::BASIC(),
+            order_fc.clone(),
+            DEFAULT_TIMEZONE,
+            pool.clone(),
+        )
+        .await?;
+        let order_id_2 = controllers::order::create(
+            Session::BASIC(),
+            order_fc.clone(),
+            DEFAULT_TIMEZONE,
+            pool.clone(),
+        )
+        .await?;
         let _order_id_3 =
-            controllers::order::create(Session::BASIC(), order_fc, pool.clone()).await?;
+            controllers::order::create(Session::BASIC(), order_fc, DEFAULT_TIMEZONE, pool.clone())
+                .await?;
         let vec = controllers::order::list(Session::BASIC(), pool.clone()).await?;
         assert_eq!(vec.len(), 3);
 
-        controllers::order::delete(Session::BASIC(), order_id_2, pool.clone()).await?;
+        controllers::order::delete(Session::BASIC(), order_id_2, None, pool.clone()).await?;
 
         let vec = controllers::order::list(Session::BASIC(), pool.clone()).await?;
         assert_eq!(vec.len(), 2);
@@ -287,44 +855,44 @@ mod tests {
 
     #[sqlx::test]
     async fn order_list_with_date_start(pool: Db) -> Result<()> {
-        let time = chrono::Local::now().time();
+        let time = chrono::NaiveTime::from_hms_opt(12, 0, 0).unwrap();
         let day4 = chrono::NaiveDate::from_ymd_opt(2005, 5, 4).unwrap();
         let day5 = chrono::NaiveDate::from_ymd_opt(2005, 5, 5).unwrap();
         let day6 = chrono::NaiveDate::from_ymd_opt(2005, 5, 6).unwrap();
 
-        let time_created = chrono::NaiveDateTime::new(day4, time);
+        let time_created = chrono::NaiveDateTime::new(day4, time).and_utc();
         let _: (i32,) = sqlx::query_as(
             "
             INSERT INTO orders
-                (creator_id,time_created,receiver,additional_info,deleted,paid)
+                (number,creator_id,time_created,receiver,additional_info,status)
             VALUES
-                (0,$1,'tomek',NULL,false,false) RETURNING id
+                ('ZAM/2005/00002',0,$1,'tomek',NULL,'Submitted') RETURNING id
                 ",
         )
         .bind(time_created)
         .fetch_one(&pool)
         .await?;
 
-        let time_created = chrono::NaiveDateTime::new(day5, time);
+        let time_created = chrono::NaiveDateTime::new(day5, time).and_utc();
         let _: (i32,) = sqlx::query_as(
             "
             INSERT INTO orders
-                (creator_id,time_created,receiver,additional_info,deleted,paid)
+                (number,creator_id,time_created,receiver,additional_info,status)
             VALUES
-                (0,$1,'tomek',NULL,false,false) RETURNING id
+                ('ZAM/2005/00003',0,$1,'tomek',NULL,'Submitted') RETURNING id
                 ",
         )
         .bind(time_created)
         .fetch_one(&pool)
         .await?;
 
-        let time_created = chrono::NaiveDateTime::new(day6, time);
+        let time_created = chrono::NaiveDateTime::new(day6, time).and_utc();
         let _: (i32,) = sqlx::query_as(
             "
             INSERT INTO orders
-                (creator_id,time_created,receiver,additional_info,deleted,paid)
+                (number,creator_id,time_created,receiver,additional_info,status)
             VALUES
-                (0,$1,'tomek',NULL,false,false) RETURNING id
+                ('ZAM/2005/00004',0,$1,'tomek',NULL,'Submitted') RETURNING id
                 ",
         )
         .bind(time_created)
@@ -334,27 +902,46 @@ mod tests {
         let params_all = OrderListParams {
             date_start: Some(chrono::NaiveDate::from_ymd_opt(2005, 5, 2).unwrap()),
             date_end: None,
+            number: None,
+            modified_since: None,
         };
-        let all = controllers::order::list_with_params(Session::BASIC(), params_all, pool.clone())
-            .await?;
+        let all = controllers::order::list_with_params(
+            Session::BASIC(),
+            params_all,
+            DEFAULT_TIMEZONE,
+            pool.clone(),
+        )
+        .await?;
         assert_eq!(all.len(), 3);
 
         let params_middle = OrderListParams {
             date_start: Some(chrono::NaiveDate::from_ymd_opt(2005, 5, 5).unwrap()),
             date_end: None,
+            number: None,
+            modified_since: None,
         };
-        let middle =
-            controllers::order::list_with_params(Session::BASIC(), params_middle, pool.clone())
-                .await?;
+        let middle = controllers::order::list_with_params(
+            Session::BASIC(),
+            params_middle,
+            DEFAULT_TIMEZONE,
+            pool.clone(),
+        )
+        .await?;
         assert_eq!(middle.len(), 2);
 
         let params_none = OrderListParams {
             date_start: Some(chrono::NaiveDate::from_ymd_opt(2005, 5, 8).unwrap()),
             date_end: None,
+            number: None,
+            modified_since: None,
         };
-        let none =
-            controllers::order::list_with_params(Session::BASIC(), params_none, pool.clone())
-                .await?;
+        let none = controllers::order::list_with_params(
+            Session::BASIC(),
+            params_none,
+            DEFAULT_TIMEZONE,
+            pool.clone(),
+        )
+        .await?;
         assert_eq!(none.len(), 0);
 
         Ok(())
@@ -362,44 +949,44 @@ mod tests {
 
     #[sqlx::test]
     async fn order_list_with_date_end(pool: Db) -> Result<()> {
-        let time = chrono::Local::now().time();
+        let time = chrono::NaiveTime::from_hms_opt(12, 0, 0).unwrap();
         let day4 = chrono::NaiveDate::from_ymd_opt(2005, 5, 4).unwrap();
         let day5 = chrono::NaiveDate::from_ymd_opt(2005, 5, 5).unwrap();
         let day6 = chrono::NaiveDate::from_ymd_opt(2005, 5, 6).unwrap();
 
-        let time_created = chrono::NaiveDateTime::new(day4, time);
+        let time_created = chrono::NaiveDateTime::new(day4, time).and_utc();
         let _: (i32,) = sqlx::query_as(
             "
             INSERT INTO orders
-                (creator_id,time_created,receiver,additional_info,deleted,paid)
+                (number,creator_id,time_created,receiver,additional_info,status)
             VALUES
-                (0,$1,'tomek',NULL,false,false) RETURNING id
+                ('ZAM/2005/00005',0,$1,'tomek',NULL,'Submitted') RETURNING id
                 ",
         )
         .bind(time_created)
         .fetch_one(&pool)
         .await?;
 
-        let time_created = chrono::NaiveDateTime::new(day5, time);
+        let time_created = chrono::NaiveDateTime::new(day5, time).and_utc();
         let _: (i32,) = sqlx::query_as(
             "
             INSERT INTO orders
-                (creator_id,time_created,receiver,additional_info,deleted,paid)
+                (number,creator_id,time_created,receiver,additional_info,status)
             VALUES
-                (0,$1,'tomek',NULL,false,false) RETURNING id
+                ('ZAM/2005/00006',0,$1,'tomek',NULL,'Submitted') RETURNING id
                 ",
         )
         .bind(time_created)
         .fetch_one(&pool)
         .await?;
 
-        let time_created = chrono::NaiveDateTime::new(day6, time);
+        let time_created = chrono::NaiveDateTime::new(day6, time).and_utc();
         let _: (i32,) = sqlx::query_as(
             "
             INSERT INTO orders
-                (creator_id,time_created,receiver,additional_info,deleted,paid)
+                (number,creator_id,time_created,receiver,additional_info,status)
             VALUES
-                (0,$1,'tomek',NULL,false,false) RETURNING id
+                ('ZAM/2005/00007',0,$1,'tomek',NULL,'Submitted') RETURNING id
                 ",
         )
         .bind(time_created)
@@ -409,27 +996,46 @@ mod tests {
         let params_all = OrderListParams {
             date_start: None,
             date_end: Some(chrono::NaiveDate::from_ymd_opt(2008, 1, 1).unwrap()),
+            number: None,
+            modified_since: None,
         };
-        let all = controllers::order::list_with_params(Session::BASIC(), params_all, pool.clone())
-            .await?;
+        let all = controllers::order::list_with_params(
+            Session::BASIC(),
+            params_all,
+            DEFAULT_TIMEZONE,
+            pool.clone(),
+        )
+        .await?;
         assert_eq!(all.len(), 3);
 
         let params_middle = OrderListParams {
             date_start: None,
             date_end: Some(chrono::NaiveDate::from_ymd_opt(2005, 5, 5).unwrap()),
+            number: None,
+            modified_since: None,
         };
-        let middle =
-            controllers::order::list_with_params(Session::BASIC(), params_middle, pool.clone())
-                .await?;
+        let middle = controllers::order::list_with_params(
+            Session::BASIC(),
+            params_middle,
+            DEFAULT_TIMEZONE,
+            pool.clone(),
+        )
+        .await?;
         assert_eq!(middle.len(), 2);
 
         let params_none = OrderListParams {
             date_start: None,
             date_end: Some(chrono::NaiveDate::from_ymd_opt(2005, 5, 2).unwrap()),
+            number: None,
+            modified_since: None,
         };
-        let none =
-            controllers::order::list_with_params(Session::BASIC(), params_none, pool.clone())
-                .await?;
+        let none = controllers::order::list_with_params(
+            Session::BASIC(),
+            params_none,
+            DEFAULT_TIMEZONE,
+            pool.clone(),
+        )
+        .await?;
         assert_eq!(none.len(), 0);
 
         Ok(())
@@ -437,7 +1043,7 @@ mod tests {
 
     #[sqlx::test]
     async fn order_list_with_date(pool: Db) -> Result<()> {
-        let time = chrono::Local::now().time();
+        let time = chrono::NaiveTime::from_hms_opt(12, 0, 0).unwrap();
         let day4 = chrono::NaiveDate::from_ymd_opt(2005, 5, 4).unwrap();
         let day5 = chrono::NaiveDate::from_ymd_opt(2005, 5, 5).unwrap();
         let day6 = chrono::NaiveDate::from_ymd_opt(2005, 5, 6).unwrap();
@@ -447,38 +1053,118 @@ mod tests {
         sqlx::query(
             "
             INSERT INTO orders
-                (creator_id,time_created,receiver,additional_info,deleted,paid)
+                (number,creator_id,time_created,receiver,additional_info,status)
             VALUES
-                (0,$1,'tomek',NULL,false,false),
-                (0,$2,'tomek',NULL,false,false),
-                (0,$3,'tomek',NULL,false,false),
-                (0,$4,'tomek',NULL,false,false),
-                (0,$5,'tomek',NULL,false,false)
+                ('ZAM/2005/00008',0,$1,'tomek',NULL,'Submitted'),
+                ('ZAM/2005/00009',0,$2,'tomek',NULL,'Submitted'),
+                ('ZAM/2005/00010',0,$3,'tomek',NULL,'Submitted'),
+                ('ZAM/2005/00011',0,$4,'tomek',NULL,'Submitted'),
+                ('ZAM/2005/00012',0,$5,'tomek',NULL,'Submitted')
                 ",
         )
-        .bind(chrono::NaiveDateTime::new(day4, time))
-        .bind(chrono::NaiveDateTime::new(day5, time))
-        .bind(chrono::NaiveDateTime::new(day6, time))
-        .bind(chrono::NaiveDateTime::new(day7, time))
-        .bind(chrono::NaiveDateTime::new(day8, time))
+        .bind(chrono::NaiveDateTime::new(day4, time).and_utc())
+        .bind(chrono::NaiveDateTime::new(day5, time).and_utc())
+        .bind(chrono::NaiveDateTime::new(day6, time).and_utc())
+        .bind(chrono::NaiveDateTime::new(day7, time).and_utc())
+        .bind(chrono::NaiveDateTime::new(day8, time).and_utc())
         .execute(&pool)
         .await?;
 
         let params_three = OrderListParams {
             date_start: Some(chrono::NaiveDate::from_ymd_opt(2005, 5, 5).unwrap()),
             date_end: Some(chrono::NaiveDate::from_ymd_opt(2005, 5, 7).unwrap()),
+            number: None,
+            modified_since: None,
         };
-        let three =
-            controllers::order::list_with_params(Session::BASIC(), params_three, pool.clone())
-                .await?;
+        let three = controllers::order::list_with_params(
+            Session::BASIC(),
+            params_three,
+            DEFAULT_TIMEZONE,
+            pool.clone(),
+        )
+        .await?;
         assert_eq!(three.len(), 3);
 
         Ok(())
     }
 
+    #[sqlx::test]
+    async fn order_list_with_date_dst(pool: Db) -> Result<()> {
+        let at = |s: &str| s.parse::<DateTime<Utc>>().unwrap();
+        let day = |m, d| chrono::NaiveDate::from_ymd_opt(2026, m, d);
+
+        sqlx::query(
+            "
+            INSERT INTO orders
+                (number,creator_id,time_created,receiver,additional_info,status)
+            VALUES
+                ('ZAM/2026/00001',0,$1,'tomek',NULL,'Submitted'),
+                ('ZAM/2026/00002',0,$2,'tomek',NULL,'Submitted'),
+                ('ZAM/2026/00003',0,$3,'tomek',NULL,'Submitted'),
+                ('ZAM/2026/00004',0,$4,'tomek',NULL,'Submitted'),
+                ('ZAM/2026/00005',0,$5,'tomek',NULL,'Submitted'),
+                ('ZAM/2026/00006',0,$6,'tomek',NULL,'Submitted')
+                ",
+        )
+        // 29 March in Warsaw: 00:30 CET and 23:30 CEST, then 00:30 on the 30th
+        .bind(at("2026-03-28T23:30:00Z"))
+        .bind(at("2026-03-29T21:30:00Z"))
+        .bind(at("2026-03-29T22:30:00Z"))
+        // 25 October in Warsaw: 00:30 CEST and 23:30 CET, then 00:30 on the 26th
+        .bind(at("2026-10-24T22:30:00Z"))
+        .bind(at("2026-10-25T22:30:00Z"))
+        .bind(at("2026-10-25T23:30:00Z"))
+        .execute(&pool)
+        .await?;
+
+        let numbers = |orders: Vec<OrderResponseBasic>| {
+            let mut numbers: Vec<String> = orders.into_iter().map(|o| o.number).collect();
+            numbers.sort();
+            numbers
+        };
+
+        for (date, expected) in [
+            (day(3, 29), ["ZAM/2026/00001", "ZAM/2026/00002"]),
+            (day(10, 25), ["ZAM/2026/00004", "ZAM/2026/00005"]),
+        ] {
+            let params = OrderListParams {
+                date_start: date,
+                date_end: date,
+                number: None,
+                modified_since: None,
+            };
+            let orders = controllers::order::list_with_params(
+                Session::BASIC(),
+                params,
+                DEFAULT_TIMEZONE,
+                pool.clone(),
+            )
+            .await?;
+            assert_eq!(numbers(orders), expected);
+        }
+
+        // the same instants in UTC fall on other days
+        let params = OrderListParams {
+            date_start: day(10, 25),
+            date_end: day(10, 25),
+            number: None,
+            modified_since: None,
+        };
+        let orders = controllers::order::list_with_params(
+            Session::BASIC(),
+            params,
+            chrono_tz::UTC,
+            pool.clone(),
+        )
+        .await?;
+        assert_eq!(numbers(orders), ["ZAM/2026/00005", "ZAM/2026/00006"]);
+
+        Ok(())
+    }
+
     #[sqlx::test]
     async fn order_delete_not_found(pool: Db) -> Result<()> {
-        let should_err = controllers::order::delete(Session::BASIC(), 0, pool.clone()).await;
+        let should_err = controllers::order::delete(Session::BASIC(), 0, None, pool.clone()).await;
         let _err = Error::SQLEntityNotFound {
             entity_type: "order",
             id: 0,
@@ -492,13 +1178,16 @@ mod tests {
         let order_fc = OrderForCreate {
             receiver: "tomek".to_owned(),
             additional_info: None,
+            customer_id: None,
         };
-        let id = controllers::order::create(Session::BASIC(), order_fc, pool.clone()).await?;
+        let id =
+            controllers::order::create(Session::BASIC(), order_fc, DEFAULT_TIMEZONE, pool.clone())
+                .await?;
 
         let fetched = controllers::order::read(Session::BASIC(), id, pool.clone()).await?;
         assert_eq!(fetched.additional_info, None);
 
-        controllers::order::delete(Session::BASIC(), id, pool.clone()).await?;
+        controllers::order::delete(Session::BASIC(), id, None, pool.clone()).await?;
 
         let list = controllers::order::list(Session::BASIC(), pool.clone()).await?;
         assert_eq!(list.len(), 0);
@@ -513,47 +1202,642 @@ mod tests {
         Ok(())
     }
 
+    #[sqlx::test]
+    async fn order_totals(pool: Db) -> Result<()> {
+        let order_fc = OrderForCreate {
+            receiver: "tomek".to_owned(),
+            additional_info: None,
+            customer_id: None,
+        };
+        let id = create(Session::BASIC(), order_fc, DEFAULT_TIMEZONE, pool.clone()).await?;
+        let items = [
+            (
+                "2l",
+                Money::pln(6150),
+                VatRate::Vat23,
+                Some(Discount::Percent(1000)),
+            ),
+            ("1", Money::pln(10800), VatRate::Vat8, None),
+        ];
+        for (quantity, value, vat_rate, discount) in items {
+            let item_fc = ItemForCreate {
+                quantity: quantity.to_owned(),
+                name: "farba".to_owned(),
+                value,
+                additional_info: None,
+                product_id: None,
+                vat_rate,
+                discount,
+            };
+            controllers::item::create(Session::BASIC(), item_fc, id, pool.clone()).await?;
+        }
+
+        // 123zł - 10% = 110,70zł at 23% and 108zł at 8%
+        let order = read(Session::BASIC(), id, pool.clone()).await?;
+        assert_eq!(order.items[0].totals.before_discount, Money::pln(12300));
+        assert_eq!(order.items[0].totals.totals.gross, Money::pln(11070));
+        assert_eq!(order.items[0].totals.totals.vat, Money::pln(2070));
+        assert_eq!(order.totals.totals.gross, Money::pln(21870));
+        assert_eq!(order.totals.totals.vat, Money::pln(2870));
+        assert_eq!(order.totals.totals.net, Money::pln(19000));
+
+        let order_fu = OrderForUpdate {
+            discount: Some(Some(Discount::Amount(Money::pln(1870)))),
+            ..Default::default()
+        };
+        update(Session::BASIC(), id, order_fu, pool.clone()).await?;
+        let order = read(Session::BASIC(), id, pool.clone()).await?;
+        assert_eq!(order.discount, Some(Discount::Amount(Money::pln(1870))));
+        assert_eq!(order.totals.totals.gross, Money::pln(20000));
+        assert_eq!(order.totals.discount, Money::pln(3100));
+        let mut conn = pool.acquire().await?;
+        let balance = payment::balance(&mut conn, id).await?;
+        assert_eq!(balance.total, Money::pln(20000));
+
+        let order_fu = OrderForUpdate {
+            discount: Some(Some(Discount::Percent(20000))),
+            ..Default::default()
+        };
+        let should_err = update(Session::BASIC(), id, order_fu, pool.clone()).await;
+        assert_eq!(should_err, Err(Error::DiscountInvalid));
+
+        let order_fu = OrderForUpdate {
+            discount: Some(None),
+            ..Default::default()
+        };
+        update(Session::BASIC(), id, order_fu, pool.clone()).await?;
+        let order = read(Session::BASIC(), id, pool.clone()).await?;
+        assert_eq!(order.discount, None);
+        assert_eq!(order.totals.totals.gross, Money::pln(21870));
+        let balance = payment::balance(&mut conn, id).await?;
+        assert_eq!(balance.total, Money::pln(21870));
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn order_version_conflict(pool: Db) -> Result<()> {
+        let order_fc = OrderForCreate {
+            receiver: "tomek".to_owned(),
+            additional_info: None,
+            customer_id: None,
+        };
+        let id = create(Session::BASIC(), order_fc, DEFAULT_TIMEZONE, pool.clone()).await?;
+        let item_fc = ItemForCreate {
+            quantity: "1l".to_owned(),
+            name: "farba".to_owned(),
+            value: Money::pln(3000),
+            additional_info: None,
+            product_id: None,
+            vat_rate: VatRate::Vat23,
+            discount: None,
+        };
+        let item_id = controllers::item::create(Session::BASIC(), item_fc, id, pool.clone())
+            .await?
+            .0;
+        let order = read(Session::BASIC(), id, pool.clone()).await?;
+        assert_eq!((order.version, order.items[0].version), (1, 1));
+
+        // two people read version 1, the second change is refused
+        let rename = |receiver: &str| OrderForUpdate {
+            receiver: Some(receiver.to_owned()),
+            version: Some(1),
+            ..Default::default()
+        };
+        update(Session::BASIC(), id, rename("Tomasz"), pool.clone()).await?;
+        let should_err = update(Session::BASIC(), id, rename("Tomek"), pool.clone()).await;
+        let conflict = Err(Error::VersionConflict {
+            entity_type: "order",
+            id,
+        });
+        assert_eq!(should_err, conflict);
+        let should_err = delete(Session::BASIC(), id, Some(1), pool.clone()).await;
+        assert_eq!(should_err, conflict);
+        let order = read(Session::BASIC(), id, pool.clone()).await?;
+        assert_eq!((order.receiver.as_str(), order.version), ("Tomasz", 2));
+
+        // items have their own versions. Checking one also moves the order status, which the
+        // order version doesn't follow, the client didn't change anything of the order
+        let check = |version| ItemForUpdate {
+            quantity: None,
+            name: None,
+            value: None,
+            additional_info: None,
+            checked: Some(true),
+            vat_rate: None,
+            discount: None,
+            version: Some(version),
+        };
+        controllers::item::update(Session::BASIC(), item_id, check(1), pool.clone()).await?;
+        let should_err =
+            controllers::item::update(Session::BASIC(), item_id, check(1), pool.clone()).await;
+        let item_conflict = Err(Error::VersionConflict {
+            entity_type: "item",
+            id: item_id,
+        });
+        assert_eq!(should_err, item_conflict);
+        let should_err =
+            controllers::item::delete(Session::BASIC(), item_id, Some(1), pool.clone()).await;
+        assert_eq!(should_err, item_conflict);
+        let order = read(Session::BASIC(), id, pool.clone()).await?;
+        assert_eq!((order.status, order.version), (OrderStatus::Ready, 2));
+        assert_eq!(order.items[0].version, 2);
+        let rename = OrderForUpdate {
+            version: Some(2),
+            ..rename("Tomek")
+        };
+        update(Session::BASIC(), id, rename, pool.clone()).await?;
+
+        controllers::item::delete(Session::BASIC(), item_id, Some(2), pool.clone()).await?;
+        let order = read(Session::BASIC(), id, pool.clone()).await?;
+        delete(Session::BASIC(), id, Some(order.version), pool.clone()).await?;
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn order_modified_since(pool: Db) -> Result<()> {
+        let mut ids = vec![];
+        for receiver in ["anna", "bartek", "celina", "darek"] {
+            let order_fc = OrderForCreate {
+                receiver: receiver.to_owned(),
+                additional_info: None,
+                customer_id: None,
+            };
+            ids.push(create(Session::BASIC(), order_fc, DEFAULT_TIMEZONE, pool.clone()).await?);
+        }
+        let item_fc = ItemForCreate {
+            quantity: "1l".to_owned(),
+            name: "farba".to_owned(),
+            value: Money::pln(3000),
+            additional_info: None,
+            product_id: None,
+            vat_rate: VatRate::Vat23,
+            discount: None,
+        };
+        let item_id = controllers::item::create(Session::BASIC(), item_fc, ids[1], pool.clone())
+            .await?
+            .0;
+        let (since,): (DateTime<Utc>,) = sqlx::query_as("SELECT now()").fetch_one(&pool).await?;
+
+        let user = Session::new(7, Privileges::Basic);
+        let rename = |receiver: &str| OrderForUpdate {
+            receiver: Some(receiver.to_owned()),
+            ..Default::default()
+        };
+        // saving without changes keeps the previous author
+        update(user.clone(), ids[3], rename("darek"), pool.clone()).await?;
+        let order = read(Session::BASIC(), ids[3], pool.clone()).await?;
+        assert_eq!((order.updated_by, order.version), (0, 1));
+        assert_eq!(order.updated_at, order.time_created);
+
+        update(user.clone(), ids[0], rename("Anna"), pool.clone()).await?;
+        let check = ItemForUpdate {
+            quantity: None,
+            name: None,
+            value: None,
+            additional_info: None,
+            checked: Some(true),
+            vat_rate: None,
+            discount: None,
+            version: None,
+        };
+        controllers::item::update(user.clone(), item_id, check, pool.clone()).await?;
+        delete(user, ids[2], None, pool.clone()).await?;
+
+        let params = OrderListParams {
+            date_start: None,
+            date_end: None,
+            number: None,
+            modified_since: Some(since),
+        };
+        let output =
+            list_with_params(Session::BASIC(), params, DEFAULT_TIMEZONE, pool.clone()).await?;
+        let changed: Vec<i32> = output.iter().map(|order| order.id).collect();
+        assert_eq!(changed, ids[..3]);
+        assert_eq!(output[0].updated_by, 7);
+        assert!(output[0].updated_at >= since);
+        // the item changed, and the status of its order with it
+        assert_eq!(output[1].items[0].updated_by, 7);
+        assert_eq!(output[1].status, OrderStatus::Ready);
+        assert_eq!(output[2].status, OrderStatus::Cancelled);
+
+        // changes are stamped when they are made, not when their transaction began
+        let mut tx = pool.begin().await?;
+        let (started,): (DateTime<Utc>,) =
+            sqlx::query_as("SELECT now()").fetch_one(&mut *tx).await?;
+        let (updated_at,): (DateTime<Utc>,) =
+            sqlx::query_as("UPDATE orders SET receiver='Darek' WHERE id=$1 RETURNING updated_at")
+                .bind(ids[3])
+                .fetch_one(&mut *tx)
+                .await?;
+        tx.commit().await?;
+        assert!(updated_at > started);
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn order_update(pool: Db) -> Result<()> {
+        let order_fc = OrderForCreate {
+            receiver: "tomek".to_owned(),
+            additional_info: None,
+            customer_id: None,
+        };
+        let id =
+            controllers::order::create(Session::BASIC(), order_fc, DEFAULT_TIMEZONE, pool.clone())
+                .await?;
+        let customer_fc = CustomerForCreate {
+            name: "Tomasz Nowak".to_owned(),
+            phone: None,
+            email: None,
+            nip: None,
+            notes: None,
+        };
+        let customer_id =
+            controllers::customer::create(Session::BASIC(), customer_fc, pool.clone()).await?;
+
+        let order_fu = OrderForUpdate {
+            receiver: Some("Tomasz Nowak".to_owned()),
+            customer_id: Some(customer_id),
+            ..Default::default()
+        };
+        update(Session::BASIC(), id, order_fu, pool.clone()).await?;
+        let order = read(Session::BASIC(), id, pool.clone()).await?;
+        assert_eq!(order.receiver, "Tomasz Nowak");
+        assert_eq!(order.additional_info, None);
+        assert_eq!(order.customer_id, Some(customer_id));
+
+        let should_err = update(Session::BASIC(), 0, Default::default(), pool).await;
+        assert_eq!(
+            should_err,
+            Err(Error::SQLEntityNotFound {
+                entity_type: "order",
+                id: 0
+            })
+        );
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn order_transition(pool: Db) -> Result<()> {
+        let order_fc = OrderForCreate {
+            receiver: "tomek".to_owned(),
+            additional_info: None,
+            customer_id: None,
+        };
+        let id =
+            controllers::order::create(Session::BASIC(), order_fc, DEFAULT_TIMEZONE, pool.clone())
+                .await?;
+
+        for status in [
+            OrderStatus::InPreparation,
+            OrderStatus::Ready,
+            OrderStatus::PickedUp,
+        ] {
+            transition(Session::BASIC(), id, status, pool.clone()).await?;
+            let order = read(Session::BASIC(), id, pool.clone()).await?;
+            assert_eq!(order.status, status);
+        }
+
+        // paid comes from payments only
+        let should_err = transition(Session::FULL(), id, OrderStatus::Paid, pool.clone()).await;
+        assert_eq!(
+            should_err,
+            Err(Error::OrderInvalidTransition {
+                from: OrderStatus::PickedUp,
+                to: OrderStatus::Paid
+            })
+        );
+
+        let history = history(Session::BASIC(), id, pool.clone()).await?;
+        let changes: Vec<_> = history
+            .iter()
+            .map(|change| (change.previous_status, change.status))
+            .collect();
+        assert_eq!(
+            changes,
+            vec![
+                (None, OrderStatus::Submitted),
+                (Some(OrderStatus::Submitted), OrderStatus::InPreparation),
+                (Some(OrderStatus::InPreparation), OrderStatus::Ready),
+                (Some(OrderStatus::Ready), OrderStatus::PickedUp),
+            ]
+        );
+        assert_eq!(history[3].user_id, Session::BASIC().id());
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn order_transition_invalid(pool: Db) -> Result<()> {
+        let order_fc = OrderForCreate {
+            receiver: "tomek".to_owned(),
+            additional_info: None,
+            customer_id: None,
+        };
+        let id =
+            controllers::order::create(Session::BASIC(), order_fc, DEFAULT_TIMEZONE, pool.clone())
+                .await?;
+
+        let should_err =
+            transition(Session::BASIC(), id, OrderStatus::PickedUp, pool.clone()).await;
+        assert_eq!(
+            should_err,
+            Err(Error::OrderInvalidTransition {
+                from: OrderStatus::Submitted,
+                to: OrderStatus::PickedUp
+            })
+        );
+
+        // cancelled orders stay cancelled
+        delete(Session::BASIC(), id, None, pool.clone()).await?;
+        let should_err =
+            transition(Session::BASIC(), id, OrderStatus::Submitted, pool.clone()).await;
+        assert_eq!(
+            should_err,
+            Err(Error::OrderInvalidTransition {
+                from: OrderStatus::Cancelled,
+                to: OrderStatus::Submitted
+            })
+        );
+
+        let should_err = transition(Session::BASIC(), 0, OrderStatus::Ready, pool.clone()).await;
+        assert_eq!(
+            should_err,
+            Err(Error::SQLEntityNotFound {
+                entity_type: "order",
+                id: 0
+            })
+        );
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn order_delete_paid(pool: Db) -> Result<()> {
+        let order_fc = OrderForCreate {
+            receiver: "tomek".to_owned(),
+            additional_info: None,
+            customer_id: None,
+        };
+        let id =
+            controllers::order::create(Session::BASIC(), order_fc, DEFAULT_TIMEZONE, pool.clone())
+                .await?;
+        let item_fc = ItemForCreate {
+            quantity: "1".to_owned(),
+            name: "pędzel".to_owned(),
+            value: Money::pln(1500),
+            additional_info: None,
+            product_id: None,
+            vat_rate: VatRate::Vat23,
+            discount: None,
+        };
+        controllers::item::create(Session::BASIC(), item_fc, id, pool.clone()).await?;
+        let payment = PaymentForCreate {
+            amount: Money::pln(1500),
+            method: PaymentMethod::Card,
+            note: None,
+        };
+        controllers::admin::payment::create(Session::FULL(), id, payment, pool.clone()).await?;
+
+        let should_err = delete(Session::FULL(), id, None, pool.clone()).await;
+        assert_eq!(
+            should_err,
+            Err(Error::OrderInvalidTransition {
+                from: OrderStatus::Paid,
+                to: OrderStatus::Cancelled
+            })
+        );
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn order_status_follows_items(pool: Db) -> Result<()> {
+        let order_fc = OrderForCreate {
+            receiver: "tomek".to_owned(),
+            additional_info: None,
+            customer_id: None,
+        };
+        let id =
+            controllers::order::create(Session::BASIC(), order_fc, DEFAULT_TIMEZONE, pool.clone())
+                .await?;
+        let item_fc = ItemForCreate {
+            quantity: "1".to_owned(),
+            name: "pędzel".to_owned(),
+            value: Money::pln(1500),
+            additional_info: None,
+            product_id: None,
+            vat_rate: VatRate::Vat23,
+            discount: None,
+        };
+        let item1 = controllers::item::create(Session::BASIC(), item_fc.clone(), id, pool.clone())
+            .await?
+            .0;
+        let item2 = controllers::item::create(Session::BASIC(), item_fc.clone(), id, pool.clone())
+            .await?
+            .0;
+        let check = |checked| ItemForUpdate {
+            quantity: None,
+            name: None,
+            value: None,
+            additional_info: None,
+            checked: Some(checked),
+            vat_rate: None,
+            discount: None,
+            version: None,
+        };
+        let status =
+            || async { Ok::<_, Error>(read(Session::BASIC(), id, pool.clone()).await?.status) };
+
+        // the callers publish the status change
+        let moved =
+            controllers::item::update(Session::BASIC(), item1, check(true), pool.clone()).await?;
+        assert_eq!((status().await?, moved), (OrderStatus::InPreparation, true));
+        let moved =
+            controllers::item::update(Session::BASIC(), item1, check(true), pool.clone()).await?;
+        assert_eq!(
+            (status().await?, moved),
+            (OrderStatus::InPreparation, false)
+        );
+
+        controllers::item::update(Session::BASIC(), item2, check(true), pool.clone()).await?;
+        assert_eq!(status().await?, OrderStatus::Ready);
+
+        controllers::item::update(Session::BASIC(), item2, check(false), pool.clone()).await?;
+        assert_eq!(status().await?, OrderStatus::InPreparation);
+
+        // deleting the last unchecked item leaves only checked ones
+        controllers::item::delete(Session::BASIC(), item2, None, pool.clone()).await?;
+        assert_eq!(status().await?, OrderStatus::Ready);
+
+        // a new unchecked item needs preparing again
+        let (_, moved) =
+            controllers::item::create(Session::BASIC(), item_fc, id, pool.clone()).await?;
+        assert_eq!((status().await?, moved), (OrderStatus::InPreparation, true));
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn order_duplicate(pool: Db) -> Result<()> {
+        let customer_fc = CustomerForCreate {
+            name: "Jan Kowalski".to_owned(),
+            phone: None,
+            email: None,
+            nip: None,
+            notes: None,
+        };
+        let customer_id =
+            controllers::customer::create(Session::BASIC(), customer_fc, pool.clone()).await?;
+        let order_fc = OrderForCreate {
+            receiver: "Jan Kowalski".to_owned(),
+            additional_info: Some("dzwonić przed".to_owned()),
+            customer_id: Some(customer_id),
+        };
+        let id =
+            controllers::order::create(Session::BASIC(), order_fc, DEFAULT_TIMEZONE, pool.clone())
+                .await?;
+        let mut item_ids = vec![];
+        for (name, discount) in [
+            ("biała farba", Some(Discount::Percent(1000))),
+            ("pędzel", None),
+            ("wałek", None),
+        ] {
+            let item_fc = ItemForCreate {
+                quantity: "2".to_owned(),
+                name: name.to_owned(),
+                value: Money::pln(1500),
+                additional_info: None,
+                product_id: None,
+                vat_rate: VatRate::Vat8,
+                discount,
+            };
+            item_ids.push(
+                controllers::item::create(Session::BASIC(), item_fc, id, pool.clone())
+                    .await?
+                    .0,
+            );
+        }
+        let check = ItemForUpdate {
+            quantity: None,
+            name: None,
+            value: None,
+            additional_info: None,
+            checked: Some(true),
+            vat_rate: None,
+            discount: None,
+            version: None,
+        };
+        controllers::item::update(Session::BASIC(), item_ids[0], check, pool.clone()).await?;
+        controllers::item::delete(Session::BASIC(), item_ids[2], None, pool.clone()).await?;
+        let original = read(Session::BASIC(), id, pool.clone()).await?;
+
+        let copy_id = duplicate(Session::BASIC(), id, DEFAULT_TIMEZONE, pool.clone()).await?;
+        let copy = read(Session::BASIC(), copy_id, pool.clone()).await?;
+        assert_ne!(copy.number, original.number);
+        assert_eq!(copy.status, OrderStatus::Submitted);
+        assert_eq!(copy.receiver, "Jan Kowalski");
+        assert_eq!(copy.additional_info.as_deref(), Some("dzwonić przed"));
+        assert_eq!(copy.customer_id, Some(customer_id));
+        let names: Vec<&str> = copy.items.iter().map(|item| item.name.as_str()).collect();
+        assert_eq!(names, vec!["biała farba", "pędzel"]);
+        assert!(copy.items.iter().all(|item| !item.checked));
+        assert_eq!(copy.items[0].discount, Some(Discount::Percent(1000)));
+        assert_eq!(copy.items[0].vat_rate, VatRate::Vat8);
+        assert_eq!(copy.totals, original.totals);
+
+        // the original is left as it was
+        assert_eq!(
+            read(Session::BASIC(), id, pool.clone()).await?.items.len(),
+            2
+        );
+
+        let should_err = duplicate(Session::BASIC(), 99, DEFAULT_TIMEZONE, pool.clone()).await;
+        assert_eq!(
+            should_err,
+            Err(Error::SQLEntityNotFound {
+                entity_type: "order",
+                id: 99
+            })
+        );
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn order_history_not_found(pool: Db) -> Result<()> {
+        let should_err = history(Session::BASIC(), 0, pool).await;
+        assert!(matches!(
+            should_err,
+            Err(Error::SQLEntityNotFound {
+                entity_type: "order",
+                id: 0
+            })
+        ));
+        Ok(())
+    }
+
     #[test]
     fn order_helper_mapper() -> Result<()> {
         // order_and_items_into_response
         let fx_order = Order {
             id: 0,
+            number: "ZAM/1970/00001".to_owned(),
             creator_id: 1,
-            time_created: NaiveDateTime::UNIX_EPOCH,
+            time_created: DateTime::<Utc>::UNIX_EPOCH,
             receiver: "wujek".to_owned(),
             additional_info: Some("Actually info".to_owned()),
-            deleted: true,
-            paid: false,
+            status: OrderStatus::Cancelled,
+            picked_up: false,
+            customer_id: None,
+            discount_percent: None,
+            discount_amount: None,
+            version: 1,
+            updated_at: DateTime::<Utc>::UNIX_EPOCH,
+            updated_by: 1,
         };
         let fx_item1 = Item {
             id: 1,
             order_id: 1,
             creator_id: 1,
-            time_created: NaiveDateTime::UNIX_EPOCH,
+            time_created: DateTime::<Utc>::UNIX_EPOCH,
             quantity: "1".to_owned(),
+            quantity_parsed: None,
+            product_id: None,
             name: "1".to_owned(),
-            value: 1,
+            value: Money::pln(1),
             additional_info: None,
             deleted: true,
             checked: false,
+            vat_rate: VatRate::Vat23,
+            discount_percent: None,
+            discount_amount: None,
+            position: 1,
+            version: 1,
+            updated_at: DateTime::<Utc>::UNIX_EPOCH,
+            updated_by: 1,
         };
 
         let fx_item2 = Item {
             id: 2,
             order_id: 2,
             creator_id: 2,
-            time_created: NaiveDateTime::UNIX_EPOCH,
+            time_created: DateTime::<Utc>::UNIX_EPOCH,
             quantity: "2".to_owned(),
+            quantity_parsed: None,
+            product_id: None,
             name: "2".to_owned(),
-            value: 2,
+            value: Money::pln(2),
             additional_info: None,
             deleted: true,
             checked: false,
+            vat_rate: VatRate::Vat23,
+            discount_percent: None,
+            discount_amount: None,
+            position: 2,
+            version: 1,
+            updated_at: DateTime::<Utc>::UNIX_EPOCH,
+            updated_by: 2,
         };
 
-        let output = order_and_items_into_response(fx_order, vec![fx_item1, fx_item2]);
+        let output = order_and_items_into_response(fx_order, vec![fx_item1, fx_item2])?;
         assert_eq!(output.id, 0);
-        assert_eq!(output.time_created, NaiveDateTime::UNIX_EPOCH);
+        assert_eq!(output.number, "ZAM/1970/00001");
+        assert_eq!(output.time_created, DateTime::<Utc>::UNIX_EPOCH);
         assert_eq!(output.receiver, "wujek");
         assert_eq!(output.items[0].id, 1);
         assert_eq!(output.items[1].id, 2);
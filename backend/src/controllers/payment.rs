@@ -0,0 +1,54 @@
+// Order balances: totals of the items minus payments, refunds are negative payments.
+
+use sqlx::PgConnection;
+
+use crate::{
+    models::{
+        item::Item,
+        money::{Currency, Money},
+        order::Order,
+        payment::OrderBalance,
+        tax::{self, Discount, LineTotals, OrderTotals},
+    },
+    Error, Result,
+};
+
+// totals of the items after line and order discounts, in the order of items
+pub fn totals(
+    items: &[Item],
+    discount: Option<Discount>,
+) -> Result<(Vec<LineTotals>, OrderTotals)> {
+    let lines = items
+        .iter()
+        .map(Item::line)
+        .collect::<Option<Vec<_>>>()
+        .ok_or(Error::OrderTotalOverflow)?;
+    tax::order_totals(&lines, discount).ok_or(Error::OrderTotalOverflow)
+}
+
+pub async fn balance(conn: &mut PgConnection, order_id: i32) -> Result<OrderBalance> {
+    let order: Option<Order> = sqlx::query_as("SELECT * FROM orders WHERE id=$1")
+        .bind(order_id)
+        .fetch_optional(&mut *conn)
+        .await?;
+    let items: Vec<Item> =
+        sqlx::query_as("SELECT * FROM items WHERE order_id=$1 AND deleted=false")
+            .bind(order_id)
+            .fetch_all(&mut *conn)
+            .await?;
+    let payments: Vec<(Money,)> = sqlx::query_as("SELECT amount FROM payments WHERE order_id=$1")
+        .bind(order_id)
+        .fetch_all(&mut *conn)
+        .await?;
+
+    let (_, totals) = totals(&items, order.and_then(|order| order.discount()))?;
+    let total = totals.totals.gross;
+    let paid = Money::checked_sum(Currency::PLN, payments.into_iter().map(|payment| payment.0))
+        .ok_or(Error::OrderTotalOverflow)?;
+    let balance = total.checked_sub(paid).ok_or(Error::OrderTotalOverflow)?;
+    Ok(OrderBalance {
+        total,
+        paid,
+        balance,
+    })
+}
@@ -0,0 +1,72 @@
+use tracing::trace;
+
+use crate::{
+    controllers::contains_pattern, models::product::ProductResponseBasic, session::Session, Db,
+    Result,
+};
+
+const SEARCH_LIMIT: i64 = 20;
+
+// autocomplete for active products, empty query lists them all
+pub async fn search(_session: Session, query: String, db: Db) -> Result<Vec<ProductResponseBasic>> {
+    trace!(" -- CONTROLLER product::search");
+    let pattern = contains_pattern(&query);
+    let res: Vec<ProductResponseBasic> = sqlx::query_as(
+        "
+            SELECT id,name,default_unit,unit_price,category FROM products
+            WHERE active=true AND name ILIKE $1 ESCAPE '\\'
+            ORDER BY lower(name)
+            LIMIT $2
+        ",
+    )
+    .bind(pattern)
+    .bind(SEARCH_LIMIT)
+    .fetch_all(&db)
+    .await?;
+    Ok(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        controllers,
+        models::{money::Money, product::ProductForCreate, quantity::Unit},
+    };
+    use anyhow::Result;
+
+    #[sqlx::test]
+    async fn product_search(pool: Db) -> Result<()> {
+        let names = ["czerwona farba", "Farba biała", "lakier", "stara farba"];
+        let mut ids = vec![];
+        for name in names {
+            let payload = ProductForCreate {
+                name: name.to_string(),
+                default_unit: Unit::L,
+                unit_price: Money::pln(3000),
+                category: None,
+                low_stock_threshold: None,
+            };
+            ids.push(
+                controllers::admin::product::create(Session::FULL(), payload, pool.clone()).await?,
+            );
+        }
+        controllers::admin::product::delete(Session::FULL(), ids[3], pool.clone()).await?;
+
+        // case insensitive, without inactive products
+        let res = search(Session::BASIC(), "FARBA".to_string(), pool.clone()).await?;
+        assert_eq!(res.len(), 2);
+        assert_eq!(res[0].name, "czerwona farba");
+        assert_eq!(res[1].name, "Farba biała");
+
+        let res = search(Session::BASIC(), "".to_string(), pool.clone()).await?;
+        assert_eq!(res.len(), 3);
+
+        // wildcards are matched literally
+        let res = search(Session::BASIC(), "%".to_string(), pool.clone()).await?;
+        assert!(res.is_empty());
+        let res = search(Session::BASIC(), "f_rba".to_string(), pool.clone()).await?;
+        assert!(res.is_empty());
+        Ok(())
+    }
+}
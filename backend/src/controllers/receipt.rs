@@ -0,0 +1,527 @@
+// Receipts of orders as PDF. Text is set in the built in Courier font, so columns line up
+// by counting characters and no font files have to be embedded. Invoices are laid out
+// with the same helpers.
+
+use pdf_writer::{Content, Finish, Name, Pdf, Rect, Ref, Str};
+use tracing::trace;
+
+use crate::{
+    controllers::{self, payment},
+    models::{
+        item::Item,
+        money::Money,
+        order::{Order, OrderStatus},
+        payment::OrderBalance,
+        receipt::{ReceiptLayout, ShopInfo},
+    },
+    session::Session,
+    Db, Error, Result,
+};
+
+// letters missing from WinAnsiEncoding, the font encoding maps them to codes from 128 up
+const POLISH_GLYPHS: [(char, &str); 16] = [
+    ('ą', "aogonek"),
+    ('Ą', "Aogonek"),
+    ('ć', "cacute"),
+    ('Ć', "Cacute"),
+    ('ę', "eogonek"),
+    ('Ę', "Eogonek"),
+    ('ł', "lslash"),
+    ('Ł', "Lslash"),
+    ('ń', "nacute"),
+    ('Ń', "Nacute"),
+    ('ś', "sacute"),
+    ('Ś', "Sacute"),
+    ('ź', "zacute"),
+    ('Ź', "Zacute"),
+    ('ż', "zdotaccent"),
+    ('Ż', "Zdotaccent"),
+];
+
+// Courier glyphs are 0.6em wide
+const CHAR_WIDTH: f32 = 0.6;
+
+pub struct Page {
+    width: f32,
+    height: Option<f32>, // None for thermal rolls, they are as long as the receipt
+    margin: f32,
+    font_size: f32,
+    leading: f32,
+}
+
+impl Page {
+    pub fn of(layout: ReceiptLayout) -> Page {
+        match layout {
+            ReceiptLayout::A4 => Page {
+                width: 595.0,
+                height: Some(842.0),
+                margin: 50.0,
+                font_size: 10.0,
+                leading: 13.0,
+            },
+            ReceiptLayout::Thermal => Page {
+                width: 226.0,
+                height: None,
+                margin: 8.0,
+                font_size: 8.0,
+                leading: 10.0,
+            },
+        }
+    }
+    pub fn columns(&self) -> usize {
+        ((self.width - 2.0 * self.margin) / (self.font_size * CHAR_WIDTH)) as usize
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Line {
+    text: String,
+    bold: bool,
+}
+
+impl Line {
+    pub fn new(text: String) -> Line {
+        Line { text, bold: false }
+    }
+    pub fn bold(text: String) -> Line {
+        Line { text, bold: true }
+    }
+}
+
+pub async fn pdf(
+    session: Session,
+    order_id: i32,
+    shop: ShopInfo,
+    layout: ReceiptLayout,
+    db: Db,
+) -> Result<Vec<u8>> {
+    trace!(" -- CONTROLLER receipt::pdf");
+    let order: Order = sqlx::query_as("SELECT * FROM orders WHERE id=$1 AND status<>'Cancelled'")
+        .bind(order_id)
+        .fetch_optional(&db)
+        .await?
+        .ok_or(Error::SQLEntityNotFound {
+            entity_type: "order",
+            id: order_id,
+        })?;
+    let items = controllers::item::read_where_order_id(session, order_id, db.clone()).await?;
+    let mut conn = db.acquire().await?;
+    let balance = payment::balance(&mut conn, order_id).await?;
+
+    let page = Page::of(layout);
+    let lines = receipt_lines(&shop, &order, &items, &balance, page.columns())?;
+    Ok(render(&lines, &page))
+}
+
+fn receipt_lines(
+    shop: &ShopInfo,
+    order: &Order,
+    items: &[Item],
+    balance: &OrderBalance,
+    columns: usize,
+) -> Result<Vec<Line>> {
+    let mut lines = vec![];
+    let header = [
+        shop.name.clone(),
+        shop.address.clone(),
+        match shop.nip.as_str() {
+            "" => "".to_owned(),
+            nip => format!("NIP: {nip}"),
+        },
+    ];
+    for (i, text) in header.iter().filter(|text| !text.is_empty()).enumerate() {
+        for text in wrap(text, columns) {
+            let text = center(&text, columns);
+            lines.push(if i == 0 {
+                Line::bold(text)
+            } else {
+                Line::new(text)
+            });
+        }
+    }
+    let separator = "-".repeat(columns);
+    lines.push(Line::new(separator.clone()));
+
+    lines.push(Line::bold(format!("Zamówienie nr {}", order.number)));
+    lines.push(Line::new(format!(
+        "Data: {}",
+        order
+            .time_created
+            .with_timezone(&shop.timezone)
+            .format("%d.%m.%Y %H:%M")
+    )));
+    for text in wrap(&format!("Odbiorca: {}", order.receiver), columns) {
+        lines.push(Line::new(text));
+    }
+    if let Some(info) = order
+        .additional_info
+        .as_deref()
+        .filter(|info| !info.is_empty())
+    {
+        for text in wrap(&format!("Dopis: {info}"), columns) {
+            lines.push(Line::new(text));
+        }
+    }
+    lines.push(Line::new(separator.clone()));
+
+    let (line_totals, totals) = payment::totals(items, order.discount())?;
+    for (item, line_totals) in items.iter().zip(line_totals) {
+        let total = line_totals.before_discount;
+        for text in wrap(&item.name, columns) {
+            lines.push(Line::new(text));
+        }
+        let price = format!("  {} x {}", item.quantity, item.value);
+        for text in justify(&price, &total.to_string(), columns) {
+            lines.push(Line::new(text));
+        }
+    }
+    lines.push(Line::new(separator));
+
+    if totals.discount.amount != 0 {
+        for text in justify("Suma:", &totals.before_discount.to_string(), columns) {
+            lines.push(Line::new(text));
+        }
+        let discount = Money::new(-totals.discount.amount, totals.discount.currency);
+        for text in justify("Rabat:", &discount.to_string(), columns) {
+            lines.push(Line::new(text));
+        }
+    }
+    for text in justify("Razem:", &balance.total.to_string(), columns) {
+        lines.push(Line::bold(text));
+    }
+    for rate in &totals.by_rate {
+        let label = format!("  w tym VAT {}%:", rate.rate.percent());
+        for text in justify(&label, &rate.totals.vat.to_string(), columns) {
+            lines.push(Line::new(text));
+        }
+    }
+    if balance.paid.amount != 0 {
+        for text in justify("Wpłacono:", &balance.paid.to_string(), columns) {
+            lines.push(Line::new(text));
+        }
+    }
+    if order.status == OrderStatus::Paid {
+        lines.push(Line::bold("Opłacone".to_owned()));
+    } else {
+        for text in justify("Do zapłaty:", &balance.balance.to_string(), columns) {
+            lines.push(Line::bold(text));
+        }
+    }
+    lines.push(Line::new("".to_owned()));
+    lines.push(Line::new(center("Dokument niefiskalny", columns)));
+    Ok(lines)
+}
+
+// splits text into lines of at most `columns` characters, breaking on spaces when it can
+pub fn wrap(text: &str, columns: usize) -> Vec<String> {
+    let mut lines = vec![];
+    let mut line = String::new();
+    for word in text.split(' ') {
+        let line_len = line.chars().count();
+        let word_len = word.chars().count();
+        if line_len > 0 && line_len + 1 + word_len > columns {
+            lines.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(word);
+        while line.chars().count() > columns {
+            let rest: String = line.chars().skip(columns).collect();
+            lines.push(line.chars().take(columns).collect());
+            line = rest;
+        }
+    }
+    lines.push(line);
+    lines
+}
+
+pub fn center(text: &str, columns: usize) -> String {
+    let padding = columns.saturating_sub(text.chars().count()) / 2;
+    format!("{}{text}", " ".repeat(padding))
+}
+
+// left text and right aligned value, the value gets its own line when both don't fit
+pub fn justify(left: &str, right: &str, columns: usize) -> Vec<String> {
+    let left_len = left.chars().count();
+    let right_len = right.chars().count();
+    if left_len + 1 + right_len <= columns {
+        let padding = columns - left_len - right_len;
+        return vec![format!("{left}{}{right}", " ".repeat(padding))];
+    }
+    let mut lines = wrap(left, columns);
+    lines.push(format!(
+        "{}{right}",
+        " ".repeat(columns.saturating_sub(right_len))
+    ));
+    lines
+}
+
+fn encode(text: &str) -> Vec<u8> {
+    text.chars()
+        .map(|c| match c {
+            ' '..='~' => c as u8,
+            '\u{a0}' => b' ',
+            'ó' => 0xf3,
+            'Ó' => 0xd3,
+            _ => POLISH_GLYPHS
+                .iter()
+                .position(|(polish, _)| *polish == c)
+                .map(|i| 128 + i as u8)
+                .unwrap_or(b'?'),
+        })
+        .collect()
+}
+
+pub fn render(lines: &[Line], page: &Page) -> Vec<u8> {
+    let lines_per_page = match page.height {
+        Some(height) => ((height - 2.0 * page.margin) / page.leading) as usize,
+        None => lines.len().max(1),
+    };
+    let pages: Vec<&[Line]> = lines.chunks(lines_per_page).collect();
+
+    let catalog_id = Ref::new(1);
+    let page_tree_id = Ref::new(2);
+    let font_id = Ref::new(3);
+    let bold_font_id = Ref::new(4);
+    // every page is followed by its content stream
+    let page_ids: Vec<Ref> = (0..pages.len())
+        .map(|i| Ref::new(5 + 2 * i as i32))
+        .collect();
+
+    let mut pdf = Pdf::new();
+    pdf.catalog(catalog_id).pages(page_tree_id);
+    pdf.pages(page_tree_id)
+        .kids(page_ids.iter().copied())
+        .count(pages.len() as i32);
+    for (id, name) in [(font_id, "Courier"), (bold_font_id, "Courier-Bold")] {
+        let mut font = pdf.type1_font(id);
+        font.base_font(Name(name.as_bytes()));
+        let mut encoding = font.encoding_custom();
+        encoding.base_encoding(Name(b"WinAnsiEncoding"));
+        encoding.differences().consecutive(
+            128,
+            POLISH_GLYPHS
+                .iter()
+                .map(|(_, glyph)| Name(glyph.as_bytes())),
+        );
+    }
+
+    for (page_lines, page_id) in pages.iter().zip(&page_ids) {
+        let content_id = Ref::new(page_id.get() + 1);
+        let height = page
+            .height
+            .unwrap_or(2.0 * page.margin + page_lines.len() as f32 * page.leading);
+        let mut page_writer = pdf.page(*page_id);
+        page_writer
+            .media_box(Rect::new(0.0, 0.0, page.width, height))
+            .parent(page_tree_id)
+            .contents(content_id);
+        page_writer
+            .resources()
+            .fonts()
+            .pair(Name(b"F1"), font_id)
+            .pair(Name(b"F2"), bold_font_id);
+        page_writer.finish();
+
+        let mut content = Content::new();
+        let mut y = height - page.margin - page.font_size;
+        for line in page_lines.iter() {
+            let font = if line.bold { b"F2" } else { b"F1" };
+            content.begin_text();
+            content.set_font(Name(font), page.font_size);
+            content.next_line(page.margin, y);
+            content.show(Str(&encode(&line.text)));
+            content.end_text();
+            y -= page.leading;
+        }
+        pdf.stream(content_id, &content.finish());
+    }
+    pdf.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::models::{
+        item::ItemForCreate,
+        money::Money,
+        order::OrderForCreate,
+        payment::{PaymentForCreate, PaymentMethod},
+        tax::VatRate,
+        time::DEFAULT_TIMEZONE,
+    };
+
+    use super::*;
+    use anyhow::Result;
+
+    fn shop() -> ShopInfo {
+        ShopInfo {
+            name: "Farby Kowalski".to_owned(),
+            address: "ul. Długa 1, 00-001 Warszawa".to_owned(),
+            nip: "1234567890".to_owned(),
+            receipt_layout: ReceiptLayout::A4,
+            timezone: DEFAULT_TIMEZONE,
+        }
+    }
+
+    // count of page objects in the document
+    fn page_count(pdf: &[u8]) -> usize {
+        pdf.windows(b"/Type /Page\n".len())
+            .filter(|window| *window == b"/Type /Page\n")
+            .count()
+    }
+
+    // lines with polish letters are written as hex strings
+    fn contains(pdf: &[u8], text: &str) -> bool {
+        let encoded = encode(text);
+        let hex: String = encoded.iter().map(|byte| format!("{byte:02X}")).collect();
+        [encoded, hex.into_bytes()].iter().any(|needle| {
+            pdf.windows(needle.len())
+                .any(|window| window == needle.as_slice())
+        })
+    }
+
+    #[test]
+    fn receipt_encode() {
+        assert_eq!(encode("abc 123"), b"abc 123");
+        assert_eq!(encode("ół"), vec![0xf3, 134]);
+        assert_eq!(encode("Żółć"), vec![143, 0xf3, 134, 130]);
+        assert_eq!(encode("1\u{a0}234,00\u{a0}zł"), b"1 234,00 z\x86");
+        assert_eq!(encode("€"), b"?");
+    }
+
+    #[test]
+    fn receipt_text_layout() {
+        assert_eq!(
+            wrap("czerwona farba akrylowa", 10),
+            vec!["czerwona", "farba", "akrylowa"]
+        );
+        assert_eq!(
+            wrap("bardzodługienazwy", 8),
+            vec!["bardzodł", "ugienazw", "y"]
+        );
+        assert_eq!(center("abc", 9), "   abc");
+        assert_eq!(justify("Razem:", "5,00 zł", 16), vec!["Razem:   5,00 zł"]);
+        assert_eq!(
+            justify("Do zapłaty:", "5,00 zł", 12),
+            vec!["Do zapłaty:", "     5,00 zł"]
+        );
+    }
+
+    #[test]
+    fn receipt_columns() {
+        assert_eq!(Page::of(ReceiptLayout::A4).columns(), 82);
+        assert_eq!(Page::of(ReceiptLayout::Thermal).columns(), 43);
+    }
+
+    #[sqlx::test]
+    async fn receipt_pdf(pool: Db) -> Result<()> {
+        let order_fc = OrderForCreate {
+            receiver: "Łukasz".to_owned(),
+            additional_info: Some("odbiór w piątek".to_owned()),
+            customer_id: None,
+        };
+        let id =
+            controllers::order::create(Session::BASIC(), order_fc, DEFAULT_TIMEZONE, pool.clone())
+                .await?;
+        let item_fc = ItemForCreate {
+            quantity: "2l".to_owned(),
+            name: "biała farba".to_owned(),
+            value: Money::pln(3050),
+            additional_info: None,
+            product_id: None,
+            vat_rate: VatRate::Vat23,
+            discount: None,
+        };
+        controllers::item::create(Session::BASIC(), item_fc, id, pool.clone()).await?;
+        let payment = PaymentForCreate {
+            amount: Money::pln(1000),
+            method: PaymentMethod::Cash,
+            note: None,
+        };
+        controllers::admin::payment::create(Session::FULL(), id, payment, pool.clone()).await?;
+
+        for layout in [ReceiptLayout::A4, ReceiptLayout::Thermal] {
+            let pdf = pdf(Session::BASIC(), id, shop(), layout, pool.clone()).await?;
+            assert!(pdf.starts_with(b"%PDF-"));
+            assert!(pdf.ends_with(b"%%EOF"));
+            assert_eq!(page_count(&pdf), 1);
+            for text in [
+                "Farby Kowalski",
+                "NIP: 1234567890",
+                "Odbiorca: Łukasz",
+                "Dopis: odbiór w piątek",
+                "biała farba",
+                "  2l x 30,50 zł",
+                "61,00 zł",
+                "Wpłacono:",
+                "Do zapłaty:",
+                "51,00 zł",
+            ] {
+                assert!(contains(&pdf, text), "{layout:?} {text}");
+            }
+        }
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn receipt_pdf_pages(pool: Db) -> Result<()> {
+        let order_fc = OrderForCreate {
+            receiver: "tomek".to_owned(),
+            additional_info: None,
+            customer_id: None,
+        };
+        let id =
+            controllers::order::create(Session::BASIC(), order_fc, DEFAULT_TIMEZONE, pool.clone())
+                .await?;
+        let item_fc = ItemForCreate {
+            quantity: "1".to_owned(),
+            name: "pędzel".to_owned(),
+            value: Money::pln(500),
+            additional_info: None,
+            product_id: None,
+            vat_rate: VatRate::Vat23,
+            discount: None,
+        };
+        for _ in 0..40 {
+            controllers::item::create(Session::BASIC(), item_fc.clone(), id, pool.clone()).await?;
+        }
+
+        // A4 continues on the next page, thermal rolls just get longer
+        let pdf_a4 = pdf(
+            Session::BASIC(),
+            id,
+            shop(),
+            ReceiptLayout::A4,
+            pool.clone(),
+        )
+        .await?;
+        assert_eq!(page_count(&pdf_a4), 2);
+        let thermal = pdf(Session::BASIC(), id, shop(), ReceiptLayout::Thermal, pool).await?;
+        assert_eq!(page_count(&thermal), 1);
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn receipt_pdf_not_found(pool: Db) -> Result<()> {
+        let order_fc = OrderForCreate {
+            receiver: "tomek".to_owned(),
+            additional_info: None,
+            customer_id: None,
+        };
+        let id =
+            controllers::order::create(Session::BASIC(), order_fc, DEFAULT_TIMEZONE, pool.clone())
+                .await?;
+        controllers::order::delete(Session::BASIC(), id, None, pool.clone()).await?;
+
+        let should_err = pdf(Session::BASIC(), id, shop(), ReceiptLayout::A4, pool).await;
+        assert_eq!(
+            should_err,
+            Err(Error::SQLEntityNotFound {
+                entity_type: "order",
+                id
+            })
+        );
+        Ok(())
+    }
+}
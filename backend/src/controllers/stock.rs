@@ -0,0 +1,436 @@
+// Automatic stock movements for items linked to products.
+// Every item has at most one outstanding sale, so checking an item and paying or picking up its
+// order decrement the stock only once.
+
+use sqlx::PgConnection;
+use tracing::{trace, warn};
+
+use crate::{
+    models::{item::Item, quantity::Unit, stock::StockMovementKind},
+    Result,
+};
+
+// sum of sales and returns of the item, negative while goods are out
+async fn item_balance(conn: &mut PgConnection, item_id: i32) -> Result<i64> {
+    let res: (i64,) = sqlx::query_as(
+        "SELECT COALESCE(SUM(amount),0)::BIGINT FROM stock_movements WHERE item_id=$1",
+    )
+    .bind(item_id)
+    .fetch_one(&mut *conn)
+    .await?;
+    Ok(res.0)
+}
+
+async fn insert_item_movement(
+    conn: &mut PgConnection,
+    creator_id: i32,
+    product_id: i32,
+    item_id: i32,
+    kind: StockMovementKind,
+    amount: i64,
+) -> Result<()> {
+    let time_created = chrono::Utc::now();
+    sqlx::query(
+        "
+            INSERT INTO stock_movements
+                (product_id,item_id,kind,amount,creator_id,time_created)
+            VALUES
+                ($1,$2,$3,$4,$5,$6)
+        ",
+    )
+    .bind(product_id)
+    .bind(item_id)
+    .bind(kind)
+    .bind(amount)
+    .bind(creator_id)
+    .bind(time_created)
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+// takes the item quantity out of stock unless it already is
+pub async fn issue_for_item(conn: &mut PgConnection, creator_id: i32, item_id: i32) -> Result<()> {
+    trace!(" -- CONTROLLER stock::issue_for_item");
+    let item: Option<Item> =
+        sqlx::query_as("SELECT * FROM items WHERE id=$1 AND deleted=false FOR UPDATE")
+            .bind(item_id)
+            .fetch_optional(&mut *conn)
+            .await?;
+    let Some(Item {
+        product_id: Some(product_id),
+        quantity_parsed: Some(quantity),
+        ..
+    }) = item
+    else {
+        return Ok(());
+    };
+    if item_balance(conn, item_id).await? != 0 {
+        return Ok(());
+    }
+    let unit: (Unit,) = sqlx::query_as("SELECT default_unit FROM products WHERE id=$1")
+        .bind(product_id)
+        .fetch_one(&mut *conn)
+        .await?;
+    let Some(quantity) = quantity.convert(unit.0) else {
+        warn!("item {item_id} quantity can't be converted to product {product_id} unit");
+        return Ok(());
+    };
+    insert_item_movement(
+        conn,
+        creator_id,
+        product_id,
+        item_id,
+        StockMovementKind::Sale,
+        -quantity.amount,
+    )
+    .await
+}
+
+// puts back whatever the item took out of stock
+pub async fn return_for_item(conn: &mut PgConnection, creator_id: i32, item_id: i32) -> Result<()> {
+    trace!(" -- CONTROLLER stock::return_for_item");
+    let item: Option<Item> = sqlx::query_as("SELECT * FROM items WHERE id=$1 FOR UPDATE")
+        .bind(item_id)
+        .fetch_optional(&mut *conn)
+        .await?;
+    let Some(Item {
+        product_id: Some(product_id),
+        ..
+    }) = item
+    else {
+        return Ok(());
+    };
+    let balance = item_balance(conn, item_id).await?;
+    if balance == 0 {
+        return Ok(());
+    }
+    insert_item_movement(
+        conn,
+        creator_id,
+        product_id,
+        item_id,
+        StockMovementKind::Return,
+        -balance,
+    )
+    .await
+}
+
+// swaps the outstanding sale of an item for one of its current quantity
+pub async fn reissue_for_item(
+    conn: &mut PgConnection,
+    creator_id: i32,
+    item_id: i32,
+) -> Result<()> {
+    trace!(" -- CONTROLLER stock::reissue_for_item");
+    if item_balance(conn, item_id).await? == 0 {
+        return Ok(());
+    }
+    return_for_item(conn, creator_id, item_id).await?;
+    issue_for_item(conn, creator_id, item_id).await
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        controllers,
+        error::Error,
+        models::{
+            item::{ItemForCreate, ItemForUpdate},
+            money::Money,
+            order::{OrderForCreate, OrderStatus},
+            payment::{PaymentForCreate, PaymentMethod},
+            product::ProductForCreate,
+            quantity::{Quantity, Unit},
+            stock::StockMovementForCreate,
+            tax::VatRate,
+            time::DEFAULT_TIMEZONE,
+        },
+        session::Session,
+        Db,
+    };
+    use anyhow::Result;
+
+    // product with 10l in stock and an order with 2l of it, returns (product_id, order_id, item_id)
+    async fn setup(pool: &Db, quantity: &str) -> Result<(i32, i32, i32)> {
+        let product = ProductForCreate {
+            name: "czerwona farba".to_owned(),
+            default_unit: Unit::L,
+            unit_price: Money::pln(3000),
+            category: None,
+            low_stock_threshold: None,
+        };
+        let product_id =
+            controllers::admin::product::create(Session::FULL(), product, pool.clone()).await?;
+        let receipt = StockMovementForCreate {
+            product_id,
+            quantity: Quantity::new(10000, Unit::L),
+            reason: None,
+        };
+        controllers::admin::stock::receive(Session::FULL(), receipt, pool.clone()).await?;
+
+        let order = OrderForCreate {
+            receiver: "tomek".to_owned(),
+            additional_info: None,
+            customer_id: None,
+        };
+        let order_id =
+            controllers::order::create(Session::BASIC(), order, DEFAULT_TIMEZONE, pool.clone())
+                .await?;
+        let item = ItemForCreate {
+            quantity: quantity.to_owned(),
+            name: "czerwona farba".to_owned(),
+            value: Money::pln(3000),
+            additional_info: None,
+            product_id: Some(product_id),
+            vat_rate: VatRate::Vat23,
+            discount: None,
+        };
+        let item_id = controllers::item::create(Session::BASIC(), item, order_id, pool.clone())
+            .await?
+            .0;
+        Ok((product_id, order_id, item_id))
+    }
+
+    async fn level(pool: &Db) -> Result<i64> {
+        let levels = controllers::admin::stock::levels(Session::FULL(), pool.clone()).await?;
+        Ok(levels[0].stock.amount)
+    }
+
+    fn check(checked: bool) -> ItemForUpdate {
+        ItemForUpdate {
+            quantity: None,
+            name: None,
+            value: None,
+            additional_info: None,
+            checked: Some(checked),
+            vat_rate: None,
+            discount: None,
+            version: None,
+        }
+    }
+
+    #[sqlx::test]
+    async fn stock_item_check(pool: Db) -> Result<()> {
+        let (_, _, item_id) = setup(&pool, "500ml").await?;
+        assert_eq!(level(&pool).await?, 10000);
+
+        controllers::item::update(Session::BASIC(), item_id, check(true), pool.clone()).await?;
+        assert_eq!(level(&pool).await?, 9500);
+
+        // checking twice doesn't take it out twice
+        controllers::item::update(Session::BASIC(), item_id, check(true), pool.clone()).await?;
+        assert_eq!(level(&pool).await?, 9500);
+
+        controllers::item::update(Session::BASIC(), item_id, check(false), pool.clone()).await?;
+        assert_eq!(level(&pool).await?, 10000);
+
+        controllers::item::update(Session::BASIC(), item_id, check(true), pool.clone()).await?;
+        assert_eq!(level(&pool).await?, 9500);
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn stock_item_quantity_change(pool: Db) -> Result<()> {
+        let (product_id, _, item_id) = setup(&pool, "2l").await?;
+        let quantity = |quantity: &str| ItemForUpdate {
+            quantity: Some(quantity.to_owned()),
+            ..check(true)
+        };
+
+        // checked with the change, at the new quantity
+        controllers::item::update(Session::BASIC(), item_id, quantity("1l"), pool.clone()).await?;
+        assert_eq!(level(&pool).await?, 9000);
+        // the checked goods follow the change
+        controllers::item::update(Session::BASIC(), item_id, quantity("3l"), pool.clone()).await?;
+        assert_eq!(level(&pool).await?, 7000);
+
+        controllers::item::update(Session::BASIC(), item_id, check(false), pool.clone()).await?;
+        assert_eq!(level(&pool).await?, 10000);
+
+        let movements =
+            controllers::admin::stock::movements(Session::FULL(), product_id, pool.clone()).await?;
+        let amounts: Vec<i64> = movements.iter().map(|movement| movement.amount).collect();
+        assert_eq!(amounts, vec![10000, -1000, 1000, -3000, 3000]);
+        Ok(())
+    }
+
+    // pays (or refunds with a negative amount) the whole 2l for 60zł
+    async fn pay(pool: &Db, order_id: i32, amount: i64) -> Result<()> {
+        let payment = PaymentForCreate {
+            amount: Money::pln(amount),
+            method: PaymentMethod::Cash,
+            note: None,
+        };
+        controllers::admin::payment::create(Session::FULL(), order_id, payment, pool.clone())
+            .await?;
+        Ok(())
+    }
+
+    async fn order(pool: &Db, order_id: i32) -> Result<(OrderStatus, bool)> {
+        let order = controllers::order::read(Session::BASIC(), order_id, pool.clone()).await?;
+        Ok((order.status, order.picked_up))
+    }
+
+    #[sqlx::test]
+    async fn stock_order_pay(pool: Db) -> Result<()> {
+        let (_, order_id, item_id) = setup(&pool, "2l").await?;
+
+        // paid in advance, the goods leave the stock with the payment
+        pay(&pool, order_id, 6000).await?;
+        assert_eq!(order(&pool, order_id).await?, (OrderStatus::Paid, false));
+        assert_eq!(level(&pool).await?, 8000);
+
+        // already out of stock with the payment
+        controllers::item::update(Session::BASIC(), item_id, check(true), pool.clone()).await?;
+        assert_eq!(level(&pool).await?, 8000);
+
+        // picking up leaves the order paid and the stock as it was
+        controllers::order::transition(
+            Session::BASIC(),
+            order_id,
+            OrderStatus::PickedUp,
+            pool.clone(),
+        )
+        .await?;
+        assert_eq!(order(&pool, order_id).await?, (OrderStatus::Paid, true));
+        assert_eq!(level(&pool).await?, 8000);
+
+        let should_err = controllers::order::transition(
+            Session::BASIC(),
+            order_id,
+            OrderStatus::PickedUp,
+            pool.clone(),
+        )
+        .await;
+        assert_eq!(
+            should_err,
+            Err(Error::OrderInvalidTransition {
+                from: OrderStatus::Paid,
+                to: OrderStatus::PickedUp
+            })
+        );
+
+        // picked up goods stay out after a refund
+        pay(&pool, order_id, -6000).await?;
+        assert_eq!(order(&pool, order_id).await?, (OrderStatus::PickedUp, true));
+        assert_eq!(level(&pool).await?, 8000);
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn stock_paid_item_uncheck(pool: Db) -> Result<()> {
+        let (_, order_id, item_id) = setup(&pool, "2l").await?;
+
+        controllers::item::update(Session::BASIC(), item_id, check(true), pool.clone()).await?;
+        pay(&pool, order_id, 6000).await?;
+        assert_eq!(level(&pool).await?, 8000);
+
+        // paid goods don't come back by unchecking
+        controllers::item::update(Session::BASIC(), item_id, check(false), pool.clone()).await?;
+        assert_eq!(level(&pool).await?, 8000);
+
+        // unless the payment is refunded before they are picked up
+        pay(&pool, order_id, -6000).await?;
+        assert_eq!(level(&pool).await?, 10000);
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn stock_order_picked_up(pool: Db) -> Result<()> {
+        let (_, order_id, _) = setup(&pool, "2l").await?;
+
+        for status in [OrderStatus::Ready, OrderStatus::PickedUp] {
+            controllers::order::transition(Session::BASIC(), order_id, status, pool.clone())
+                .await?;
+        }
+        assert_eq!(level(&pool).await?, 8000);
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn stock_order_cancel(pool: Db) -> Result<()> {
+        let (_, order_id, item_id) = setup(&pool, "2l").await?;
+
+        controllers::item::update(Session::BASIC(), item_id, check(true), pool.clone()).await?;
+        assert_eq!(level(&pool).await?, 8000);
+
+        controllers::order::delete(Session::BASIC(), order_id, None, pool.clone()).await?;
+        assert_eq!(level(&pool).await?, 10000);
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn stock_cancelled_order_items(pool: Db) -> Result<()> {
+        let (_, order_id, item_id) = setup(&pool, "2l").await?;
+        controllers::order::delete(Session::BASIC(), order_id, None, pool.clone()).await?;
+
+        // nothing would return what the items of a cancelled order take out
+        let should_err =
+            controllers::item::update(Session::BASIC(), item_id, check(true), pool.clone()).await;
+        assert_eq!(should_err, Err(Error::ItemOrderCancelled));
+        let should_err =
+            controllers::bulk::check_items(Session::BASIC(), order_id, true, pool.clone())
+                .await
+                .map(|_| ());
+        assert_eq!(should_err, Err(Error::ItemOrderCancelled));
+        assert_eq!(level(&pool).await?, 10000);
+
+        let item = ItemForCreate {
+            quantity: "1l".to_owned(),
+            name: "czerwona farba".to_owned(),
+            value: Money::pln(3000),
+            additional_info: None,
+            product_id: None,
+            vat_rate: VatRate::Vat23,
+            discount: None,
+        };
+        let should_err =
+            controllers::item::create(Session::BASIC(), item, order_id, pool.clone()).await;
+        assert_eq!(should_err, Err(Error::ItemOrderCancelled));
+        let should_err =
+            controllers::item::delete(Session::BASIC(), item_id, None, pool.clone()).await;
+        assert_eq!(should_err, Err(Error::ItemOrderCancelled));
+        assert_eq!(level(&pool).await?, 10000);
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn stock_item_delete(pool: Db) -> Result<()> {
+        let (product_id, _, item_id) = setup(&pool, "2l").await?;
+
+        controllers::item::update(Session::BASIC(), item_id, check(true), pool.clone()).await?;
+        controllers::item::delete(Session::BASIC(), item_id, None, pool.clone()).await?;
+        assert_eq!(level(&pool).await?, 10000);
+
+        let movements =
+            controllers::admin::stock::movements(Session::FULL(), product_id, pool.clone()).await?;
+        let amounts: Vec<i64> = movements.iter().map(|movement| movement.amount).collect();
+        assert_eq!(amounts, vec![10000, -2000, 2000]);
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn stock_picked_up_item_delete(pool: Db) -> Result<()> {
+        let (_, order_id, item_id) = setup(&pool, "2l").await?;
+        for status in [OrderStatus::Ready, OrderStatus::PickedUp] {
+            controllers::order::transition(Session::BASIC(), order_id, status, pool.clone())
+                .await?;
+        }
+
+        // the goods left with the customer
+        controllers::item::delete(Session::BASIC(), item_id, None, pool.clone()).await?;
+        assert_eq!(level(&pool).await?, 8000);
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn stock_incompatible_unit(pool: Db) -> Result<()> {
+        // pieces can't be taken out of a stock counted in litres
+        let (_, _, item_id) = setup(&pool, "2 szt").await?;
+
+        controllers::item::update(Session::BASIC(), item_id, check(true), pool.clone()).await?;
+        assert_eq!(level(&pool).await?, 10000);
+        Ok(())
+    }
+}
@@ -0,0 +1,275 @@
+// Saved orders of repeat customers. Full users make templates out of orders, everyone can
+// start a new order from one.
+
+use chrono_tz::Tz;
+use tracing::trace;
+
+use crate::{
+    controllers::order,
+    models::{
+        item::ItemForCreate,
+        order::{Order, OrderForCreate},
+        template::{OrderTemplate, OrderTemplateDetail, OrderTemplateForCreate, OrderTemplateItem},
+        user::Privileges,
+    },
+    session::Session,
+    Db, Error, Result,
+};
+
+pub async fn create(session: Session, payload: OrderTemplateForCreate, db: Db) -> Result<i32> {
+    trace!(" -- CONTROLLER template::create");
+    if matches!(session.privileges(), Privileges::Basic) {
+        return Err(Error::AuthNoAccess);
+    }
+    let name = payload.name.trim().to_owned();
+    if name.is_empty() {
+        return Err(Error::TemplateMissingName);
+    }
+    let mut tx = db.begin().await?;
+    let order: Order = sqlx::query_as("SELECT * FROM orders WHERE id=$1 FOR UPDATE")
+        .bind(payload.order_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or(Error::SQLEntityNotFound {
+            entity_type: "order",
+            id: payload.order_id,
+        })?;
+    let res: (i32,) = sqlx::query_as(
+        "
+            INSERT INTO order_templates
+                (name,receiver,additional_info,customer_id,creator_id,time_created)
+            VALUES
+                ($1,$2,$3,$4,$5,$6)
+            RETURNING id
+        ",
+    )
+    .bind(name)
+    .bind(order.receiver)
+    .bind(order.additional_info)
+    .bind(order.customer_id)
+    .bind(session.id())
+    .bind(chrono::Utc::now())
+    .fetch_one(&mut *tx)
+    .await?;
+    sqlx::query(
+        "
+            INSERT INTO order_template_items
+                (template_id,quantity,product_id,name,value,additional_info,
+                vat_rate,discount_percent,discount_amount)
+            SELECT
+                $1,quantity,product_id,name,value,additional_info,
+                vat_rate,discount_percent,discount_amount
+            FROM items WHERE order_id=$2 AND deleted=false ORDER BY position, id
+        ",
+    )
+    .bind(res.0)
+    .bind(order.id)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    Ok(res.0)
+}
+
+pub async fn list(_session: Session, db: Db) -> Result<Vec<OrderTemplate>> {
+    trace!(" -- CONTROLLER template::list");
+    let res: Vec<OrderTemplate> = sqlx::query_as("SELECT * FROM order_templates ORDER BY name")
+        .fetch_all(&db)
+        .await?;
+    Ok(res)
+}
+
+pub async fn read(_session: Session, template_id: i32, db: Db) -> Result<OrderTemplateDetail> {
+    trace!(" -- CONTROLLER template::read");
+    let template: OrderTemplate = sqlx::query_as("SELECT * FROM order_templates WHERE id=$1")
+        .bind(template_id)
+        .fetch_optional(&db)
+        .await?
+        .ok_or(Error::SQLEntityNotFound {
+            entity_type: "template",
+            id: template_id,
+        })?;
+    let items: Vec<OrderTemplateItem> =
+        sqlx::query_as("SELECT * FROM order_template_items WHERE template_id=$1 ORDER BY id")
+            .bind(template_id)
+            .fetch_all(&db)
+            .await?;
+    Ok(OrderTemplateDetail { template, items })
+}
+
+pub async fn delete(session: Session, template_id: i32, db: Db) -> Result<()> {
+    trace!(" -- CONTROLLER template::delete");
+    if matches!(session.privileges(), Privileges::Basic) {
+        return Err(Error::AuthNoAccess);
+    }
+    let mut tx = db.begin().await?;
+    let deleted = sqlx::query("DELETE FROM order_templates WHERE id=$1")
+        .bind(template_id)
+        .execute(&mut *tx)
+        .await?;
+    if deleted.rows_affected() == 0 {
+        return Err(Error::SQLEntityNotFound {
+            entity_type: "template",
+            id: template_id,
+        });
+    }
+    sqlx::query("DELETE FROM order_template_items WHERE template_id=$1")
+        .bind(template_id)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+// new order with the receiver and items of the template
+pub async fn instantiate(session: Session, template_id: i32, timezone: Tz, db: Db) -> Result<i32> {
+    trace!(" -- CONTROLLER template::instantiate");
+    let OrderTemplateDetail { template, items } =
+        read(session.clone(), template_id, db.clone()).await?;
+    let order_fc = OrderForCreate {
+        receiver: template.receiver,
+        additional_info: template.additional_info,
+        customer_id: template.customer_id,
+    };
+    let items = items.into_iter().map(ItemForCreate::from).collect();
+
+    let mut tx = db.begin().await?;
+    let id = order::insert_copy(&mut tx, session.id(), timezone, order_fc, items).await?;
+    tx.commit().await?;
+    Ok(id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        controllers,
+        models::{
+            money::Money,
+            product::ProductForCreate,
+            quantity::Unit,
+            tax::{Discount, VatRate},
+            time::DEFAULT_TIMEZONE,
+        },
+    };
+    use anyhow::Result;
+
+    // order with a product item and a free text item, returns (product_id, order_id)
+    async fn setup(pool: &Db) -> Result<(i32, i32)> {
+        let product = ProductForCreate {
+            name: "biała farba".to_owned(),
+            default_unit: Unit::L,
+            unit_price: Money::pln(3000),
+            category: None,
+            low_stock_threshold: None,
+        };
+        let product_id =
+            controllers::admin::product::create(Session::FULL(), product, pool.clone()).await?;
+        let order_fc = OrderForCreate {
+            receiver: "Malarze Sp. z o.o.".to_owned(),
+            additional_info: None,
+            customer_id: None,
+        };
+        let order_id =
+            controllers::order::create(Session::BASIC(), order_fc, DEFAULT_TIMEZONE, pool.clone())
+                .await?;
+        for (name, product_id, discount) in [
+            ("biała farba", Some(product_id), None),
+            ("taśma", None, Some(Discount::Amount(Money::pln(100)))),
+        ] {
+            let item_fc = ItemForCreate {
+                quantity: "5l".to_owned(),
+                name: name.to_owned(),
+                value: Money::pln(3000),
+                additional_info: None,
+                product_id,
+                vat_rate: VatRate::Vat23,
+                discount,
+            };
+            controllers::item::create(Session::BASIC(), item_fc, order_id, pool.clone()).await?;
+        }
+        Ok((product_id, order_id))
+    }
+
+    fn template(order_id: i32) -> OrderTemplateForCreate {
+        OrderTemplateForCreate {
+            name: " farby dla malarzy ".to_owned(),
+            order_id,
+        }
+    }
+
+    #[sqlx::test]
+    async fn template_create_and_instantiate(pool: Db) -> Result<()> {
+        let (product_id, order_id) = setup(&pool).await?;
+        let id = create(Session::FULL(), template(order_id), pool.clone()).await?;
+
+        let detail = read(Session::BASIC(), id, pool.clone()).await?;
+        assert_eq!(detail.template.name, "farby dla malarzy");
+        assert_eq!(detail.template.receiver, "Malarze Sp. z o.o.");
+        assert_eq!(detail.items.len(), 2);
+        assert_eq!(detail.items[0].product_id, Some(product_id));
+        assert_eq!(
+            detail.items[1].discount(),
+            Some(Discount::Amount(Money::pln(100)))
+        );
+        assert_eq!(
+            list(Session::BASIC(), pool.clone()).await?,
+            vec![detail.template]
+        );
+
+        // workers start orders from it, later changes of the order don't reach the template
+        controllers::order::delete(Session::BASIC(), order_id, None, pool.clone()).await?;
+        let new_id = instantiate(Session::BASIC(), id, DEFAULT_TIMEZONE, pool.clone()).await?;
+        let order = controllers::order::read(Session::BASIC(), new_id, pool.clone()).await?;
+        assert_eq!(order.receiver, "Malarze Sp. z o.o.");
+        let names: Vec<&str> = order.items.iter().map(|item| item.name.as_str()).collect();
+        assert_eq!(names, vec!["biała farba", "taśma"]);
+        assert_eq!(
+            order.items[1].discount,
+            Some(Discount::Amount(Money::pln(100)))
+        );
+
+        // products taken off sale are left out of the link, not the order
+        controllers::admin::product::delete(Session::FULL(), product_id, pool.clone()).await?;
+        let new_id = instantiate(Session::BASIC(), id, DEFAULT_TIMEZONE, pool.clone()).await?;
+        let items =
+            controllers::item::read_where_order_id(Session::BASIC(), new_id, pool.clone()).await?;
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].product_id, None);
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn template_invalid(pool: Db) -> Result<()> {
+        let (_, order_id) = setup(&pool).await?;
+        let should_err = create(Session::BASIC(), template(order_id), pool.clone()).await;
+        assert_eq!(should_err, Err(Error::AuthNoAccess));
+        let unnamed = OrderTemplateForCreate {
+            name: " ".to_owned(),
+            order_id,
+        };
+        let should_err = create(Session::FULL(), unnamed, pool.clone()).await;
+        assert_eq!(should_err, Err(Error::TemplateMissingName));
+        let should_err = create(Session::FULL(), template(99), pool.clone()).await;
+        assert_eq!(
+            should_err,
+            Err(Error::SQLEntityNotFound {
+                entity_type: "order",
+                id: 99
+            })
+        );
+
+        let id = create(Session::FULL(), template(order_id), pool.clone()).await?;
+        let should_err = delete(Session::BASIC(), id, pool.clone()).await;
+        assert_eq!(should_err, Err(Error::AuthNoAccess));
+        delete(Session::FULL(), id, pool.clone()).await?;
+        let not_found = || Error::SQLEntityNotFound {
+            entity_type: "template",
+            id,
+        };
+        let should_err = instantiate(Session::BASIC(), id, DEFAULT_TIMEZONE, pool.clone()).await;
+        assert_eq!(should_err, Err(not_found()));
+        let should_err = delete(Session::FULL(), id, pool.clone()).await;
+        assert_eq!(should_err, Err(not_found()));
+        Ok(())
+    }
+}
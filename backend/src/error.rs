@@ -1,9 +1,18 @@
+use std::collections::BTreeMap;
+
 use axum::{
     http::StatusCode,
     response::{IntoResponse, Response},
+    Json,
 };
 use tracing::error;
 
+use crate::{
+    middlewares::current_request_id,
+    models::order::OrderStatus,
+    validation::{self, ValidationReport},
+};
+
 pub type Result<T> = core::result::Result<T, Error>;
 
 #[derive(Debug, PartialEq)]
@@ -16,12 +25,94 @@ pub enum Error {
     AuthNoAccess,
     SQLFail,
     SQLEntityNotFound { entity_type: &'static str, id: i32 },
+    ItemInvalidQuantity,
+    ItemInvalidReorder,
+    ItemOrderCancelled,
+    ReportOverflow,
+    StockInvalidQuantity,
+    StockIncompatibleUnit,
+    StockMissingReason,
+    StockUnitInUse,
+    OrderInvalidTransition { from: OrderStatus, to: OrderStatus },
+    OrderTotalOverflow,
+    PaymentInvalidAmount,
+    PaymentOrderCancelled,
+    ReceiptInvalidLayout,
+    CustomerInvalidMerge,
+    DiscountInvalid,
+    InvoiceOrderNotPaid,
+    InvoiceAlreadyIssued,
+    InvoiceMissingSellerData,
+    InvoiceInvalidNip,
+    InvoiceInvalidCorrection,
+    TemplateMissingName,
+    BulkTooLarge,
+    VersionMissing,
+    VersionConflict { entity_type: &'static str, id: i32 },
+    ValidationFailed(BTreeMap<String, Vec<String>>), // messages by field
 }
 
 impl IntoResponse for Error {
     fn into_response(self) -> Response {
         error!(" -- {:?}", self);
-        (StatusCode::INTERNAL_SERVER_ERROR, "UNHANDLED CLIENT ERROR").into_response()
+        let variant = ErrorVariant(self.variant());
+        // payloads are checked for these too, the rest come from data like bulk items
+        let error = match self {
+            Error::ItemInvalidQuantity => {
+                Error::ValidationFailed(validation::report("quantity", "quantity"))
+            }
+            Error::DiscountInvalid => {
+                Error::ValidationFailed(validation::report("discount", "discount"))
+            }
+            other => other,
+        };
+        if let Error::ValidationFailed(fields) = error {
+            let body = ValidationReport {
+                message: "VALIDATION FAILED",
+                fields,
+                request_id: current_request_id(),
+            };
+            let mut response = (StatusCode::UNPROCESSABLE_ENTITY, Json(body)).into_response();
+            response.extensions_mut().insert(variant);
+            return response;
+        }
+        let (status, message) = match error {
+            Error::VersionMissing => (StatusCode::PRECONDITION_REQUIRED, "VERSION REQUIRED"),
+            Error::VersionConflict { .. } => (StatusCode::CONFLICT, "VERSION CONFLICT"),
+            Error::OrderInvalidTransition { .. } => (StatusCode::CONFLICT, "INVALID TRANSITION"),
+            Error::ItemOrderCancelled => (StatusCode::CONFLICT, "ORDER CANCELLED"),
+            Error::PaymentInvalidAmount => {
+                (StatusCode::UNPROCESSABLE_ENTITY, "INVALID PAYMENT AMOUNT")
+            }
+            Error::PaymentOrderCancelled => (StatusCode::CONFLICT, "ORDER CANCELLED"),
+            Error::InvoiceOrderNotPaid => (StatusCode::CONFLICT, "ORDER NOT PAID"),
+            Error::InvoiceAlreadyIssued => (StatusCode::CONFLICT, "INVOICE ALREADY ISSUED"),
+            Error::BulkTooLarge => (StatusCode::PAYLOAD_TOO_LARGE, "BULK TOO LARGE"),
+            _ => (StatusCode::INTERNAL_SERVER_ERROR, "UNHANDLED CLIENT ERROR"),
+        };
+        // to find the logs of a failure someone reports
+        let mut response = match current_request_id() {
+            Some(id) => (status, format!("{message} (request {id})")).into_response(),
+            None => (status, message).into_response(),
+        };
+        response.extensions_mut().insert(variant);
+        response
+    }
+}
+
+// set on error responses, for the error counts of /metrics
+#[derive(Clone, Debug)]
+pub struct ErrorVariant(pub String);
+
+impl Error {
+    // the name without the fields, SQLEntityNotFound { .. } is SQLEntityNotFound
+    pub fn variant(&self) -> String {
+        let debug = format!("{self:?}");
+        debug
+            .split([' ', '{', '('])
+            .next()
+            .unwrap_or_default()
+            .to_owned()
     }
 }
 
@@ -39,3 +130,28 @@ impl From<sqlx::Error> for Error {
         Error::SQLFail
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn error_statuses() {
+        let status = |error: Error| error.into_response().status();
+        let invalid = Error::OrderInvalidTransition {
+            from: OrderStatus::Cancelled,
+            to: OrderStatus::Draft,
+        };
+        assert_eq!(status(invalid), StatusCode::CONFLICT);
+        assert_eq!(status(Error::ItemOrderCancelled), StatusCode::CONFLICT);
+        assert_eq!(
+            status(Error::PaymentInvalidAmount),
+            StatusCode::UNPROCESSABLE_ENTITY
+        );
+        assert_eq!(status(Error::PaymentOrderCancelled), StatusCode::CONFLICT);
+        assert_eq!(status(Error::InvoiceOrderNotPaid), StatusCode::CONFLICT);
+        assert_eq!(status(Error::InvoiceAlreadyIssued), StatusCode::CONFLICT);
+        assert_eq!(status(Error::BulkTooLarge), StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(status(Error::SQLFail), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+}
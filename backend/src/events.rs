@@ -0,0 +1,160 @@
+// Broadcast of order and item changes to the clients listening on /api/events.
+// Events are published by the route handlers once the change is committed, controllers tell
+// them about what changed along (order statuses following items, orders of merged customers).
+
+use futures_util::Stream;
+use prometheus::{IntCounterVec, Opts};
+use tokio::sync::{
+    broadcast::{self, error::RecvError},
+    watch,
+};
+
+use crate::models::{
+    event::{Event, EventKind},
+    user::Privileges,
+};
+
+// events kept for clients that are behind, slower ones are told to resync
+const CAPACITY: usize = 256;
+
+#[derive(Clone)]
+pub struct Events {
+    sender: broadcast::Sender<Event>,
+    published: IntCounterVec, // by kind, the business counters of /metrics
+    closed: watch::Sender<bool>,
+}
+
+impl Default for Events {
+    fn default() -> Self {
+        let published = IntCounterVec::new(
+            Opts::new(
+                "shop_events_total",
+                "Published order and item changes by kind",
+            ),
+            &["kind"],
+        )
+        .unwrap();
+        Events {
+            sender: broadcast::channel(CAPACITY).0,
+            published,
+            closed: watch::Sender::new(false),
+        }
+    }
+}
+
+impl Events {
+    // nobody listening is fine
+    pub fn publish(&self, event: Event) {
+        self.published
+            .with_label_values(&[&format!("{:?}", event.kind)])
+            .inc();
+        let _ = self.sender.send(event);
+    }
+
+    pub fn published(&self) -> &IntCounterVec {
+        &self.published
+    }
+
+    // events the viewer may see, from now on until close
+    pub fn stream(&self, privileges: Privileges) -> impl Stream<Item = Event> {
+        let receiver = self.sender.subscribe();
+        let closed = self.closed.subscribe();
+        futures_util::stream::unfold((receiver, closed), move |(mut receiver, mut closed)| {
+            let privileges = privileges.clone();
+            async move {
+                loop {
+                    let received = tokio::select! {
+                        biased;
+                        received = receiver.recv() => received,
+                        // nothing left to deliver
+                        _ = closed.wait_for(|closed| *closed) => return None,
+                    };
+                    let event = match received {
+                        Ok(event) if !event.visible_to(&privileges) => continue,
+                        Ok(event) => event,
+                        Err(RecvError::Lagged(_)) => Event {
+                            kind: EventKind::Resync,
+                            order_id: None,
+                            item_id: None,
+                        },
+                        Err(RecvError::Closed) => return None,
+                    };
+                    return Some((event, (receiver, closed)));
+                }
+            }
+        })
+    }
+
+    // on shutdown, streams deliver what was already published and end,
+    // so their connections don't hold the server up
+    pub fn close(&self) {
+        self.closed.send_replace(true);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::StreamExt;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn events_filtered_by_privileges() {
+        let events = Events::default();
+        let basic = events.stream(Privileges::Basic);
+        let full = events.stream(Privileges::Full);
+        tokio::pin!(basic, full);
+
+        events.publish(Event::order(EventKind::OrderPaid, 1));
+        events.publish(Event::item(EventKind::ItemCreated, 1, 2));
+
+        assert_eq!(
+            full.next().await,
+            Some(Event::order(EventKind::OrderPaid, 1))
+        );
+        assert_eq!(
+            full.next().await,
+            Some(Event::item(EventKind::ItemCreated, 1, 2))
+        );
+        // payments are left out for Basic users
+        assert_eq!(
+            basic.next().await,
+            Some(Event::item(EventKind::ItemCreated, 1, 2))
+        );
+    }
+
+    #[tokio::test]
+    async fn events_lagged_resync() {
+        let events = Events::default();
+        let stream = events.stream(Privileges::Basic);
+        tokio::pin!(stream);
+        for id in 0..CAPACITY as i32 + 1 {
+            events.publish(Event::order(EventKind::OrderUpdated, id));
+        }
+        let resync = stream.next().await.map(|event| event.kind);
+        assert_eq!(resync, Some(EventKind::Resync));
+        // then the ones still kept
+        let next = stream.next().await.and_then(|event| event.order_id);
+        assert_eq!(next, Some(1));
+    }
+
+    #[tokio::test]
+    async fn events_close_after_published() {
+        let events = Events::default();
+        let stream = events.stream(Privileges::Basic);
+        tokio::pin!(stream);
+
+        events.publish(Event::order(EventKind::OrderCreated, 1));
+        events.close();
+        assert_eq!(
+            stream.next().await,
+            Some(Event::order(EventKind::OrderCreated, 1))
+        );
+        assert_eq!(stream.next().await, None);
+
+        // streams opened later end right away
+        let late = events.stream(Privileges::Basic);
+        tokio::pin!(late);
+        assert_eq!(late.next().await, None);
+    }
+}
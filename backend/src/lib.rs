@@ -6,24 +6,40 @@ use jwt_simple::prelude::HS256Key;
 use serde::Deserialize;
 use serde::Serialize;
 
+pub mod cli;
+pub mod config;
 pub mod controllers;
 pub mod error;
+pub mod events;
+pub mod logging;
+pub mod metrics;
 pub mod middlewares;
 pub mod models;
 pub mod routes;
 pub mod session;
+pub mod validation;
 
+use config::AuthConfig;
 pub use error::Error;
 pub use error::Result;
-use models::user::Privileges;
+use events::Events;
+use metrics::Metrics;
+use models::{receipt::ShopInfo, user::Privileges};
 use tracing::trace;
 
 pub type Db = sqlx::PgPool;
 
+// backend/migrations, embedded at build time
+pub static MIGRATOR: sqlx::migrate::Migrator = sqlx::migrate!();
+
 #[derive(Clone)]
 pub struct AppState {
     pub db: Db,
     pub jwt_key: HS256Key,
+    pub shop: ShopInfo,
+    pub auth: AuthConfig,
+    pub events: Events,
+    pub metrics: Metrics,
 }
 
 const AUTH_COOKIE_KEY: &str = "AUTH_TOKEN";
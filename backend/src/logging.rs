@@ -0,0 +1,166 @@
+// Log output of the server: pretty or JSON lines, filtered by RUST_LOG.
+// Every request gets a span with its id, route and user, values of sensitive fields are
+// masked in whatever ends up written.
+
+use std::{borrow::Cow, io::Write};
+
+use axum::extract::Request;
+use tracing::{field::Empty, Span};
+use tracing_subscriber::{fmt::MakeWriter, prelude::*};
+
+use crate::{config::LogFormat, middlewares::RequestId};
+
+// values of fields with these names are never written, in any format
+const SENSITIVE: [&str; 5] = ["password", "token", "cookie", "authorization", "secret"];
+const REDACTED: &str = "[redacted]";
+
+pub fn init(format: LogFormat) {
+    let fmt = tracing_subscriber::fmt::layer()
+        .with_target(false)
+        .with_writer(Redacting(std::io::stdout));
+    let fmt = match format {
+        LogFormat::Pretty => fmt.without_time().boxed(),
+        LogFormat::Json => fmt.json().boxed(),
+    };
+    tracing_subscriber::registry()
+        .with(fmt)
+        .with(tracing_subscriber::filter::EnvFilter::from_default_env())
+        .init();
+}
+
+// for TraceLayer, route and user_id are recorded once known
+pub fn request_span(req: &Request) -> Span {
+    let request_id = match req.extensions().get::<RequestId>() {
+        Some(RequestId(id)) => id.as_str(),
+        None => "",
+    };
+    tracing::info_span!(
+        "request",
+        request_id,
+        method = %req.method(),
+        path = req.uri().path(),
+        route = Empty,
+        user_id = Empty,
+    )
+}
+
+struct Redacting<M>(M);
+
+impl<'a, M: MakeWriter<'a>> MakeWriter<'a> for Redacting<M> {
+    type Writer = RedactingWriter<M::Writer>;
+    fn make_writer(&'a self) -> Self::Writer {
+        RedactingWriter(self.0.make_writer())
+    }
+}
+
+// the fmt layer writes each event at once, so a write is one line
+struct RedactingWriter<W>(W);
+
+impl<W: Write> Write for RedactingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let line = String::from_utf8_lossy(buf);
+        self.0.write_all(redact(&line).as_bytes())?;
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.0.flush()
+    }
+}
+
+// masks the value after password=, password: and "password":, quoted or not
+pub fn redact(line: &str) -> Cow<'_, str> {
+    let lower = line.to_ascii_lowercase();
+    let mut spans = Vec::new();
+    for name in SENSITIVE {
+        for (at, _) in lower.match_indices(name) {
+            if let Some(span) = value_after(line, at + name.len()) {
+                spans.push(span);
+            }
+        }
+    }
+    if spans.is_empty() {
+        return Cow::Borrowed(line);
+    }
+    spans.sort();
+
+    let mut output = String::with_capacity(line.len());
+    let mut copied = 0;
+    for (start, end) in spans {
+        if start < copied {
+            continue;
+        }
+        output.push_str(&line[copied..start]);
+        output.push_str(REDACTED);
+        copied = end;
+    }
+    output.push_str(&line[copied..]);
+    Cow::Owned(output)
+}
+
+// byte range of the value following a field name, without its quotes
+fn value_after(line: &str, name_end: usize) -> Option<(usize, usize)> {
+    let rest = skip_ansi(&line[name_end..]);
+    let after_quote = skip_ansi(rest.strip_prefix('"').unwrap_or(rest));
+    let after_separator = after_quote
+        .strip_prefix('=')
+        .or_else(|| after_quote.strip_prefix(':'))?;
+    let value = skip_ansi(skip_ansi(after_separator).trim_start());
+    let start = line.len() - value.len();
+
+    if let Some(quoted) = value.strip_prefix('"') {
+        let mut escaped = false;
+        for (i, c) in quoted.char_indices() {
+            match c {
+                '\\' if !escaped => escaped = true,
+                '"' if !escaped => return Some((start + 1, start + 1 + i)),
+                _ => escaped = false,
+            }
+        }
+        return Some((start + 1, line.len()));
+    }
+    let len = value
+        .find(|c: char| c.is_whitespace() || matches!(c, ',' | '}' | ')' | ';' | '"'))
+        .unwrap_or(value.len());
+    (len > 0).then_some((start, start + len))
+}
+
+// colors of the pretty format sit between field names and values
+fn skip_ansi(mut s: &str) -> &str {
+    while let Some(code) = s.strip_prefix("\x1b[") {
+        match code.find('m') {
+            Some(end) => s = &code[end + 1..],
+            None => break,
+        }
+    }
+    s
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn logging_redact() {
+        for (line, expected) in [
+            (
+                r#"login=boss password=123 done"#,
+                r#"login=boss password=[redacted] done"#,
+            ),
+            (
+                r#"LoginPaylod { login: "boss", password: "1 \"2\" 3" }"#,
+                r#"LoginPaylod { login: "boss", password: "[redacted]" }"#,
+            ),
+            (
+                r#"{"fields":{"password":"123","token":"ey.j"},"span":{"cookie":"AUTH_TOKEN=ey"}}"#,
+                r#"{"fields":{"password":"[redacted]","token":"[redacted]"},"span":{"cookie":"[redacted]"}}"#,
+            ),
+            (" -- HANDLER GET /token", " -- HANDLER GET /token"),
+            (
+                r#"{"path":"/api/token","Authorization":"Bearer x"}"#,
+                r#"{"path":"/api/token","Authorization":"[redacted]"}"#,
+            ),
+        ] {
+            assert_eq!(redact(line), expected, "{line}");
+        }
+    }
+}
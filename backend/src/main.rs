@@ -1,49 +1,148 @@
-use axum::{middleware, routing::get_service, Router};
-use backend::{middlewares::mw_tracing, routes, AppState};
+use std::future::IntoFuture;
+
+use axum::{http::HeaderValue, middleware, routing::get_service, Router};
+use backend::{
+    cli::{self, Cli, Command},
+    config::Config,
+    logging,
+    metrics::Metrics,
+    middlewares::{mw_metrics, mw_request_id},
+    routes, AppState, Db,
+};
+use clap::Parser;
 use jwt_simple::prelude::HS256Key;
 use sqlx::postgres::PgPoolOptions;
 use tower_cookies::CookieManagerLayer;
-use tower_http::{cors::CorsLayer, services::ServeDir, trace::TraceLayer};
-use tracing_subscriber::prelude::*;
+use tower_http::{
+    cors::{AllowHeaders, AllowMethods, CorsLayer},
+    services::ServeDir,
+    trace::{DefaultOnResponse, TraceLayer},
+};
+use tracing::{info, warn, Level};
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    dotenv::dotenv()?;
-
-    // print tracing with .env configuration
-    tracing_subscriber::registry()
-        .with(
-            tracing_subscriber::fmt::layer()
-                .without_time()
-                .with_target(false),
-        )
-        .with(tracing_subscriber::filter::EnvFilter::from_default_env())
-        .init();
+    // .env is optional, the environment may be set up by other means
+    let _ = dotenv::dotenv();
+    let args = Cli::parse();
+    let command = args.command.unwrap_or(Command::Serve);
+    let config = Config::load(&args.config, matches!(command, Command::Serve))?;
+    logging::init(config.log_format);
 
     // setup database
-    let db_address = std::env::var("DATABASE_URL")?;
-    let pool = PgPoolOptions::new().connect(&db_address).await?;
+    let pool = PgPoolOptions::new()
+        .max_connections(config.db_pool_size)
+        .connect(&config.database_url)
+        .await?;
+
+    match command {
+        Command::Serve => serve(config, pool).await,
+        Command::Migrate(command) => cli::migrate(command, &pool).await,
+        Command::User(command) => cli::user(command, &pool).await,
+        Command::Backup { file } => cli::backup(&config.database_url, &file),
+        Command::Restore { file } => cli::restore(&config.database_url, &file),
+        Command::SeedDemo => cli::seed_demo(&pool).await,
+    }
+}
+
+async fn serve(config: Config, pool: Db) -> anyhow::Result<()> {
+    cli::migrate_up(&pool).await?;
 
     // setup jwt
     let key = HS256Key::generate();
 
+    let events = backend::events::Events::default();
     let state = AppState {
-        db: pool,
+        db: pool.clone(),
         jwt_key: key,
+        shop: config.shop,
+        auth: config.auth,
+        events: events.clone(),
+        metrics: Metrics::new(&events),
     };
 
     let app = Router::new()
-        .nest("/api", routes::routes())
-        .nest_service("/", get_service(ServeDir::new("./dist")))
-        .layer(TraceLayer::new_for_http())
+        .nest(
+            "/api",
+            routes::routes().route_layer(middleware::from_fn_with_state(state.clone(), mw_metrics)),
+        )
+        .merge(routes::metrics_routes())
+        .nest_service("/", get_service(ServeDir::new(&config.static_dir)))
+        .layer(
+            TraceLayer::new_for_http()
+                .make_span_with(logging::request_span)
+                .on_response(DefaultOnResponse::new().level(Level::INFO)),
+        )
+        .layer(middleware::from_fn(mw_request_id))
         .layer(CookieManagerLayer::new())
-        .layer(CorsLayer::very_permissive())
-        .layer(middleware::map_response(mw_tracing)) // new line for each request
+        .layer(cors(config.cors_origins))
         .with_state(state);
 
-    let address = std::env::var("SERVER_FULL_ADDRESS")?;
-    let listener = tokio::net::TcpListener::bind(address).await?;
+    let listener = tokio::net::TcpListener::bind(&config.server_address).await?;
+
+    // stop accepting on SIGTERM or ctrl-c, then let requests in flight finish
+    let (stopping_tx, stopping) = tokio::sync::oneshot::channel();
+    let server = axum::serve(listener, app)
+        .with_graceful_shutdown(async move {
+            shutdown_signal().await;
+            info!("shutting down, draining connections");
+            // open event streams would keep their connections forever
+            events.close();
+            let _ = stopping_tx.send(());
+        })
+        .into_future();
+    let drain_timeout = async {
+        if stopping.await.is_ok() {
+            tokio::time::sleep(config.drain_timeout).await;
+        }
+    };
+    // a failed server still closes the pool before its error is returned
+    let served = tokio::select! {
+        served = server => served,
+        _ = drain_timeout => {
+            warn!("drain timeout reached, dropping open connections");
+            Ok(())
+        }
+    };
+
+    // lets queries finish and closes connections cleanly
+    pool.close().await;
+    info!("shut down");
+    Ok(served?)
+}
+
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if tokio::signal::ctrl_c().await.is_err() {
+            std::future::pending::<()>().await;
+        }
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(_) => std::future::pending::<()>().await,
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+}
 
-    axum::serve(listener, app).await?;
-    Ok(())
+// no origins means any origin, like during development
+fn cors(origins: Vec<HeaderValue>) -> CorsLayer {
+    if origins.is_empty() {
+        return CorsLayer::very_permissive();
+    }
+    CorsLayer::new()
+        .allow_origin(origins)
+        .allow_credentials(true)
+        .allow_methods(AllowMethods::mirror_request())
+        .allow_headers(AllowHeaders::mirror_request())
 }
@@ -0,0 +1,137 @@
+// Prometheus metrics served on /metrics.
+// Requests are counted by mw_metrics, business counters come from the published events.
+
+use std::time::Duration;
+
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounterVec, IntGaugeVec, Opts, Registry, TextEncoder,
+};
+
+use crate::{events::Events, Db};
+
+#[derive(Clone)]
+pub struct Metrics {
+    registry: Registry,
+    requests: IntCounterVec,
+    latency: HistogramVec,
+    errors: IntCounterVec,
+    pool: IntGaugeVec,
+}
+
+impl Metrics {
+    pub fn new(events: &Events) -> Self {
+        let registry = Registry::new();
+        let requests = IntCounterVec::new(
+            Opts::new("http_requests_total", "Requests by route and status"),
+            &["method", "route", "status"],
+        )
+        .unwrap();
+        let latency = HistogramVec::new(
+            HistogramOpts::new("http_request_duration_seconds", "Time to respond by route"),
+            &["method", "route"],
+        )
+        .unwrap();
+        let errors = IntCounterVec::new(
+            Opts::new("errors_total", "Error responses by Error variant"),
+            &["error"],
+        )
+        .unwrap();
+        let pool = IntGaugeVec::new(
+            Opts::new("db_pool_connections", "Database connections by state"),
+            &["state"],
+        )
+        .unwrap();
+
+        // names are unique, registering can't fail
+        registry.register(Box::new(requests.clone())).unwrap();
+        registry.register(Box::new(latency.clone())).unwrap();
+        registry.register(Box::new(errors.clone())).unwrap();
+        registry.register(Box::new(pool.clone())).unwrap();
+        registry
+            .register(Box::new(events.published().clone()))
+            .unwrap();
+
+        Metrics {
+            registry,
+            requests,
+            latency,
+            errors,
+            pool,
+        }
+    }
+
+    pub fn observe(
+        &self,
+        method: &str,
+        route: &str,
+        status: u16,
+        elapsed: Duration,
+        error: Option<&str>,
+    ) {
+        self.requests
+            .with_label_values(&[method, route, &status.to_string()])
+            .inc();
+        self.latency
+            .with_label_values(&[method, route])
+            .observe(elapsed.as_secs_f64());
+        if let Some(error) = error {
+            self.errors.with_label_values(&[error]).inc();
+        }
+    }
+
+    // text exposition format, the pool is read at scrape time
+    pub fn render(&self, db: &Db) -> String {
+        let idle = db.num_idle() as i64;
+        self.pool.with_label_values(&["idle"]).set(idle);
+        self.pool
+            .with_label_values(&["in_use"])
+            .set(db.size() as i64 - idle);
+        self.pool
+            .with_label_values(&["max"])
+            .set(db.options().get_max_connections() as i64);
+
+        let mut buffer = Vec::new();
+        // only fails on a writer error, Vec doesn't have those
+        TextEncoder::new()
+            .encode(&self.registry.gather(), &mut buffer)
+            .unwrap();
+        String::from_utf8(buffer).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::event::{Event, EventKind};
+
+    #[sqlx::test]
+    async fn metrics_render(pool: Db) {
+        let events = Events::default();
+        let metrics = Metrics::new(&events);
+
+        metrics.observe("POST", "/api/orders", 200, Duration::from_millis(30), None);
+        metrics.observe(
+            "PATCH",
+            "/api/orders/:id",
+            409,
+            Duration::from_millis(5),
+            Some("VersionConflict"),
+        );
+        events.publish(Event::order(EventKind::OrderCreated, 1));
+        events.publish(Event::order(EventKind::OrderCreated, 2));
+
+        let text = metrics.render(&pool);
+        let max = pool.options().get_max_connections();
+        let max = format!(r#"db_pool_connections{{state="max"}} {max}"#);
+        for line in [
+            r#"http_requests_total{method="POST",route="/api/orders",status="200"} 1"#,
+            r#"http_requests_total{method="PATCH",route="/api/orders/:id",status="409"} 1"#,
+            r#"http_request_duration_seconds_count{method="POST",route="/api/orders"} 1"#,
+            r#"errors_total{error="VersionConflict"} 1"#,
+            r#"shop_events_total{kind="OrderCreated"} 2"#,
+            &max,
+        ] {
+            assert!(text.lines().any(|l| l == line), "{line} missing in\n{text}");
+        }
+    }
+}
@@ -1,7 +1,99 @@
-use axum::response::Response;
-use tracing::trace;
+use std::time::Instant;
 
-pub async fn mw_tracing(res: Response) -> Response {
-    trace!("");
+use axum::{
+    extract::{MatchedPath, Request, State},
+    http::{HeaderName, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use rand::Rng;
+use tracing::Span;
+
+use crate::{error::ErrorVariant, AppState};
+
+pub const X_REQUEST_ID: HeaderName = HeaderName::from_static("x-request-id");
+
+// in the extensions of every request, also read by request_span
+#[derive(Clone, Debug)]
+pub struct RequestId(pub String);
+
+tokio::task_local! {
+    // for error responses, built without access to the request
+    static REQUEST_ID: String;
+}
+
+pub fn current_request_id() -> Option<String> {
+    REQUEST_ID.try_with(Clone::clone).ok()
+}
+
+// keeps the X-Request-Id of a proxy or client if it's sane, otherwise makes one up,
+// and sends it back with the response
+pub async fn mw_request_id(mut req: Request, next: Next) -> Response {
+    let id = req
+        .headers()
+        .get(X_REQUEST_ID)
+        .and_then(|value| value.to_str().ok())
+        .filter(|id| valid_request_id(id))
+        .map(str::to_owned)
+        .unwrap_or_else(new_request_id);
+    req.extensions_mut().insert(RequestId(id.clone()));
+
+    let mut res = REQUEST_ID.scope(id.clone(), next.run(req)).await;
+    // valid ids are valid header values
+    if let Ok(value) = HeaderValue::from_str(&id) {
+        res.headers_mut().insert(X_REQUEST_ID, value);
+    }
     res
 }
+
+fn valid_request_id(id: &str) -> bool {
+    (1..=64).contains(&id.len())
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+}
+
+fn new_request_id() -> String {
+    let bytes: [u8; 16] = rand::thread_rng().gen();
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+// counts and times requests by their route, /api/orders/:id rather than /api/orders/7
+pub async fn mw_metrics(State(state): State<AppState>, req: Request, next: Next) -> Response {
+    let method = req.method().to_string();
+    let route = match req.extensions().get::<MatchedPath>() {
+        Some(path) => path.as_str().to_owned(),
+        None => "unmatched".to_owned(),
+    };
+    Span::current().record("route", &route);
+    let start = Instant::now();
+    let res = next.run(req).await;
+
+    let error = res.extensions().get::<ErrorVariant>().map(|e| e.0.as_str());
+    state.metrics.observe(
+        &method,
+        &route,
+        res.status().as_u16(),
+        start.elapsed(),
+        error,
+    );
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn middlewares_request_id() {
+        assert!(valid_request_id("3f2a-b_c.1"));
+        assert!(!valid_request_id(""));
+        assert!(!valid_request_id("a b"));
+        assert!(!valid_request_id(&"a".repeat(65)));
+
+        let id = new_request_id();
+        assert_eq!(id.len(), 32);
+        assert!(valid_request_id(&id));
+        assert_ne!(id, new_request_id());
+    }
+}
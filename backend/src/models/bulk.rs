@@ -0,0 +1,56 @@
+// BulkReport, BulkResult
+
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::Error;
+
+// outcome of one entity of a bulk request, failed ones are rolled back alone
+#[derive(Serialize, Debug, PartialEq, ToSchema)]
+pub struct BulkResult {
+    pub id: Option<i32>,       // None for items that couldn't be created
+    pub unchanged: bool,       // already as requested, nothing was done
+    pub error: Option<String>, // the Error variant, like SQLEntityNotFound
+}
+
+#[derive(Serialize, Debug, PartialEq, Default, ToSchema)]
+pub struct BulkReport {
+    pub succeeded: usize,
+    pub unchanged: usize,
+    pub failed: usize,
+    pub results: Vec<BulkResult>, // in the order of the request
+}
+
+impl BulkReport {
+    pub fn succeed(&mut self, id: i32) {
+        self.succeeded += 1;
+        self.results.push(BulkResult {
+            id: Some(id),
+            unchanged: false,
+            error: None,
+        });
+    }
+    pub fn leave(&mut self, id: i32) {
+        self.unchanged += 1;
+        self.results.push(BulkResult {
+            id: Some(id),
+            unchanged: true,
+            error: None,
+        });
+    }
+    pub fn fail(&mut self, id: Option<i32>, error: Error) {
+        self.failed += 1;
+        self.results.push(BulkResult {
+            id,
+            unchanged: false,
+            error: Some(error.variant()),
+        });
+    }
+    // the entities that changed, to publish
+    pub fn succeeded_ids(&self) -> impl Iterator<Item = i32> + '_ {
+        self.results
+            .iter()
+            .filter(|result| result.error.is_none() && !result.unchanged)
+            .filter_map(|result| result.id)
+    }
+}
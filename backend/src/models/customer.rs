@@ -0,0 +1,52 @@
+// Customer, CustomerDetail
+
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+use utoipa::ToSchema;
+
+use super::{money::Money, order::OrderResponseBasic};
+
+#[derive(FromRow, Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+pub struct Customer {
+    pub id: i32,
+    pub name: String,
+    pub phone: Option<String>,
+    pub email: Option<String>,
+    pub nip: Option<String>,
+    pub notes: Option<String>,
+    pub merged_into: Option<i32>,
+    pub time_created: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Clone)]
+pub struct CustomerForCreate {
+    pub name: String,
+    pub phone: Option<String>,
+    pub email: Option<String>,
+    pub nip: Option<String>,
+    pub notes: Option<String>,
+}
+
+#[derive(Clone, Default)]
+pub struct CustomerForUpdate {
+    pub name: Option<String>,
+    pub phone: Option<String>,
+    pub email: Option<String>,
+    pub nip: Option<String>,
+    pub notes: Option<String>,
+}
+
+#[derive(Serialize, Debug, FromRow, PartialEq, ToSchema)]
+pub struct CustomerResponseBasic {
+    pub id: i32,
+    pub name: String,
+    pub phone: Option<String>,
+    pub nip: Option<String>,
+}
+
+#[derive(Serialize, Debug, ToSchema)]
+pub struct CustomerDetail {
+    pub customer: Customer,
+    pub orders: Vec<OrderResponseBasic>, // newest first, without cancelled ones
+    pub outstanding: Money,              // sum of the order balances
+}
@@ -0,0 +1,50 @@
+// Event, EventKind
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::user::Privileges;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, ToSchema)]
+pub enum EventKind {
+    OrderCreated,
+    OrderUpdated, // also its status, items can change it
+    OrderDeleted,
+    OrderPaid, // a payment or refund, for Full users only
+    ItemCreated,
+    ItemUpdated,
+    ItemDeleted,
+    // events were dropped for a slow client, everything should be fetched again
+    Resync,
+}
+
+// only ids, clients fetch whatever they show again
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, ToSchema)]
+pub struct Event {
+    pub kind: EventKind,
+    pub order_id: Option<i32>,
+    pub item_id: Option<i32>,
+}
+
+impl Event {
+    pub fn order(kind: EventKind, order_id: i32) -> Self {
+        Event {
+            kind,
+            order_id: Some(order_id),
+            item_id: None,
+        }
+    }
+    pub fn item(kind: EventKind, order_id: i32, item_id: i32) -> Self {
+        Event {
+            kind,
+            order_id: Some(order_id),
+            item_id: Some(item_id),
+        }
+    }
+    pub fn visible_to(&self, privileges: &Privileges) -> bool {
+        match self.kind {
+            EventKind::OrderPaid => matches!(privileges, Privileges::Full),
+            _ => true,
+        }
+    }
+}
@@ -0,0 +1,171 @@
+// Invoice, InvoiceLine, InvoiceRate, InvoiceDetail
+
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+use utoipa::ToSchema;
+
+use super::{
+    money::Money,
+    tax::{Totals, VatRate},
+};
+use crate::Error;
+
+#[derive(Serialize, Deserialize, sqlx::Type, Clone, Copy, Debug, PartialEq, Eq, ToSchema)]
+pub enum InvoiceKind {
+    Invoice,
+    // faktura korygująca
+    Correction,
+}
+
+impl InvoiceKind {
+    // number prefix, both series are numbered separately
+    pub fn series(self) -> &'static str {
+        match self {
+            InvoiceKind::Invoice => "FV",
+            InvoiceKind::Correction => "FK",
+        }
+    }
+}
+
+// issued invoices are never updated, the database rejects changes
+#[derive(FromRow, Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+pub struct Invoice {
+    pub id: i32,
+    pub number: String, // for example FV/2026/00001
+    pub kind: InvoiceKind,
+    pub order_id: i32,
+    pub corrected_id: Option<i32>,
+    pub correction_reason: Option<String>,
+    pub issue_date: chrono::NaiveDate,
+    pub sale_date: chrono::NaiveDate,
+    pub seller_name: String,
+    pub seller_address: String,
+    pub seller_nip: String,
+    pub buyer_name: String,
+    pub buyer_address: Option<String>,
+    pub buyer_nip: Option<String>,
+    pub net: Money,
+    pub vat: Money,
+    pub gross: Money,
+    pub creator_id: i32,
+    pub time_created: chrono::DateTime<chrono::Utc>,
+}
+
+impl Invoice {
+    pub fn totals(&self) -> Totals {
+        Totals {
+            net: self.net,
+            vat: self.vat,
+            gross: self.gross,
+        }
+    }
+}
+
+#[derive(FromRow, Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+pub struct InvoiceLine {
+    pub id: i32,
+    pub invoice_id: i32,
+    pub position: i32, // from 1
+    pub name: String,
+    pub quantity: String,
+    pub unit_price: Money, // gross
+    pub vat_rate: VatRate,
+    pub discount: Money,
+    pub net: Money,
+    pub vat: Money,
+    pub gross: Money,
+}
+
+#[derive(FromRow, Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+pub struct InvoiceRate {
+    pub invoice_id: i32,
+    pub vat_rate: VatRate,
+    pub net: Money,
+    pub vat: Money,
+    pub gross: Money,
+}
+
+// buyer data defaults to the customer of the order, or its receiver
+#[derive(Clone, Default)]
+pub struct InvoiceForCreate {
+    pub buyer_name: Option<String>,
+    pub buyer_address: Option<String>,
+    pub buyer_nip: Option<String>,
+}
+
+// buyer data defaults to the corrected document
+#[derive(Clone, Default)]
+pub struct CorrectionForCreate {
+    pub reason: String,
+    pub buyer_name: Option<String>,
+    pub buyer_address: Option<String>,
+    pub buyer_nip: Option<String>,
+}
+
+#[derive(Serialize, Debug, ToSchema)]
+pub struct InvoiceDetail {
+    pub invoice: Invoice,
+    pub order_number: String,
+    pub lines: Vec<InvoiceLine>,
+    pub rates: Vec<InvoiceRate>, // highest rate first
+    // corrections only
+    pub corrected: Option<Invoice>,
+    pub difference: Option<Totals>, // after minus before the correction
+}
+
+// "123-456-32-18" becomes "1234563218", polish numbers are checked with their control digit,
+// EU VAT numbers only by their form
+pub fn normalize_nip(nip: &str) -> Result<String, Error> {
+    let nip: String = nip
+        .chars()
+        .filter(|c| !matches!(c, ' ' | '-'))
+        .collect::<String>()
+        .to_uppercase();
+    if !nip.is_ascii() {
+        return Err(Error::InvoiceInvalidNip);
+    }
+    let digits: Vec<u32> = nip.chars().filter_map(|c| c.to_digit(10)).collect();
+    if digits.len() == 10 && nip.len() == 10 {
+        let weights = [6, 5, 7, 2, 3, 4, 5, 6, 7];
+        let sum: u32 = weights.iter().zip(&digits).map(|(w, d)| w * d).sum();
+        if sum % 11 == digits[9] {
+            return Ok(nip);
+        }
+        return Err(Error::InvoiceInvalidNip);
+    }
+    let (country, number) = nip.split_at(nip.len().min(2));
+    let valid = country.len() == 2
+        && country.chars().all(|c| c.is_ascii_uppercase())
+        && (2..=12).contains(&number.len())
+        && number
+            .chars()
+            .all(|c| c.is_ascii_digit() || c.is_ascii_uppercase());
+    if !valid {
+        return Err(Error::InvoiceInvalidNip);
+    }
+    Ok(nip)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invoice_normalize_nip() {
+        assert_eq!(normalize_nip("123-456-32-18"), Ok("1234563218".to_owned()));
+        assert_eq!(normalize_nip("123 456 32 18"), Ok("1234563218".to_owned()));
+        assert_eq!(normalize_nip("de 123456789"), Ok("DE123456789".to_owned()));
+        for nip in [
+            "1234563219",
+            "123456321",
+            "",
+            "1",
+            "PL",
+            "DE1234567890123",
+            "12AB",
+            "ŁÓ12345",
+        ] {
+            assert_eq!(normalize_nip(nip), Err(Error::InvoiceInvalidNip), "{nip}");
+        }
+    }
+}
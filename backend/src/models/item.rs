@@ -1,45 +1,116 @@
 use serde::{Deserialize, Serialize};
 use sqlx::prelude::FromRow;
+use utoipa::ToSchema;
 
-#[derive(FromRow, Clone, Deserialize, Serialize)]
+use super::{
+    money::Money,
+    quantity::Quantity,
+    tax::{Discount, Line, LineTotals, VatRate},
+};
+
+#[derive(FromRow, Clone, Deserialize, Serialize, ToSchema)]
 pub struct Item {
     pub id: i32,
     pub order_id: i32,
     pub creator_id: i32,
-    pub time_created: chrono::NaiveDateTime,
+    pub time_created: chrono::DateTime<chrono::Utc>,
 
-    pub quantity: String, // for example 1l or 5kg
-    pub name: String,     // for example czerwona farba
-    pub value: i32,       // for example 100_00 = 100PLN
+    pub quantity: String,                  // for example 1l or 5kg
+    pub quantity_parsed: Option<Quantity>, // None for items created before units were parsed
+    pub product_id: Option<i32>,           // name and value are copied, not read from the product
+    pub name: String,                      // for example czerwona farba
+    pub value: Money,                      // gross price, for example 100_00 = 100PLN
     pub additional_info: Option<String>,
     pub deleted: bool,
     pub checked: bool,
+    pub vat_rate: VatRate,
+    pub discount_percent: Option<i64>,
+    pub discount_amount: Option<Money>,
+    pub position: i32, // from 1 within the order, deleted items keep theirs
+    pub version: i32,  // raised by the database on every change
+    pub updated_at: chrono::DateTime<chrono::Utc>, // set by the database, time_created until then
+    pub updated_by: i32,
+}
+
+impl Item {
+    // value is the price of one unit, None on overflow
+    pub fn total(&self) -> Option<Money> {
+        match self.quantity_parsed {
+            Some(quantity) => self.value.checked_mul_quantity(quantity),
+            // unparsed quantities count their digits, the same way the order view sums them
+            None => {
+                let digits: String = self.quantity.chars().filter(char::is_ascii_digit).collect();
+                self.value.checked_mul(digits.parse().unwrap_or(0))
+            }
+        }
+    }
+    pub fn discount(&self) -> Option<Discount> {
+        Discount::from_columns(self.discount_percent, self.discount_amount)
+    }
+    pub fn line(&self) -> Option<Line> {
+        Some(Line {
+            gross: self.total()?,
+            rate: self.vat_rate,
+            discount: self.discount(),
+        })
+    }
 }
 
 #[derive(Clone)]
 pub struct ItemForCreate {
     pub quantity: String,
     pub name: String,
-    pub value: i32,
+    pub value: Money,
     pub additional_info: Option<String>,
+    pub product_id: Option<i32>,
+    pub vat_rate: VatRate,
+    pub discount: Option<Discount>,
 }
+
+// copies of items start unchecked
+impl From<Item> for ItemForCreate {
+    fn from(item: Item) -> Self {
+        ItemForCreate {
+            discount: item.discount(),
+            quantity: item.quantity,
+            name: item.name,
+            value: item.value,
+            additional_info: item.additional_info,
+            product_id: item.product_id,
+            vat_rate: item.vat_rate,
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct ItemForUpdate {
     pub quantity: Option<String>,
     pub name: Option<String>,
-    pub value: Option<i32>,
+    pub value: Option<Money>,
     pub additional_info: Option<String>,
     pub checked: Option<bool>,
+    pub vat_rate: Option<VatRate>,
+    pub discount: Option<Option<Discount>>, // Some(None) removes it
+    pub version: Option<i32>,               // the one the client read, None skips the check
 }
 
-#[derive(Serialize, Debug)]
+#[derive(Serialize, Debug, ToSchema)]
 pub struct ItemResponseBasic {
     pub id: i32,
     pub order_id: i32,
-    pub time_created: chrono::NaiveDateTime,
+    pub time_created: chrono::DateTime<chrono::Utc>,
     pub quantity: String, // for example 1l or 5kg
-    pub name: String,     // for example czerwona farba
-    pub value: i32,       // for example 100_00 = 100PLN
+    pub quantity_parsed: Option<Quantity>,
+    pub product_id: Option<i32>,
+    pub name: String, // for example czerwona farba
+    pub value: Money, // gross price, for example 100_00 = 100PLN
     pub checked: bool,
     pub additional_info: Option<String>,
+    pub vat_rate: VatRate,
+    pub discount: Option<Discount>,
+    pub totals: LineTotals,
+    pub position: i32,
+    pub version: i32,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+    pub updated_by: i32,
 }
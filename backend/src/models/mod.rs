@@ -6,3 +6,45 @@ pub mod order;
 
 // Order items
 pub mod item;
+
+// Money amounts in minor units
+pub mod money;
+
+// Item quantities with units
+pub mod quantity;
+
+// Aggregated reports
+pub mod report;
+
+// Product catalog
+pub mod product;
+
+// Stock levels of products
+pub mod stock;
+
+// Payments and refunds of orders
+pub mod payment;
+
+// Printed receipts
+pub mod receipt;
+
+// Customers placing orders
+pub mod customer;
+
+// VAT rates, discounts and totals
+pub mod tax;
+
+// Invoices and their corrections
+pub mod invoice;
+
+// Saved orders to start new ones from
+pub mod template;
+
+// Reports of requests changing many entities at once
+pub mod bulk;
+
+// Changes broadcast to connected clients
+pub mod event;
+
+// Days in the shop timezone
+pub mod time;
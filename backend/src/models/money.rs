@@ -0,0 +1,181 @@
+// Money, Currency
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::quantity::Quantity;
+
+#[derive(
+    Serialize, Deserialize, sqlx::Type, Clone, Copy, Debug, Default, PartialEq, Eq, Hash, ToSchema,
+)]
+#[sqlx(type_name = "currency")]
+pub enum Currency {
+    #[default]
+    PLN,
+}
+
+impl Currency {
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Currency::PLN => "zł",
+        }
+    }
+    // ISO 4217
+    pub fn code(self) -> &'static str {
+        match self {
+            Currency::PLN => "PLN",
+        }
+    }
+}
+
+// amount is stored in minor units, for example 100_00 = 100PLN
+#[derive(Serialize, Deserialize, sqlx::Type, Clone, Copy, Debug, PartialEq, Eq, Hash, ToSchema)]
+#[sqlx(type_name = "money_amount")]
+pub struct Money {
+    pub amount: i64,
+    #[serde(default)]
+    pub currency: Currency,
+}
+
+impl Money {
+    pub fn new(amount: i64, currency: Currency) -> Self {
+        Self { amount, currency }
+    }
+    pub fn pln(amount: i64) -> Self {
+        Self::new(amount, Currency::PLN)
+    }
+    pub fn zero(currency: Currency) -> Self {
+        Self::new(0, currency)
+    }
+    pub fn is_negative(&self) -> bool {
+        self.amount < 0
+    }
+
+    // None on overflow or when currencies differ
+    pub fn checked_add(self, rhs: Money) -> Option<Money> {
+        if self.currency != rhs.currency {
+            return None;
+        }
+        let amount = self.amount.checked_add(rhs.amount)?;
+        Some(Money::new(amount, self.currency))
+    }
+    pub fn checked_sub(self, rhs: Money) -> Option<Money> {
+        if self.currency != rhs.currency {
+            return None;
+        }
+        let amount = self.amount.checked_sub(rhs.amount)?;
+        Some(Money::new(amount, self.currency))
+    }
+    pub fn checked_mul(self, factor: i64) -> Option<Money> {
+        let amount = self.amount.checked_mul(factor)?;
+        Some(Money::new(amount, self.currency))
+    }
+    // price of `quantity` units, rounded half away from zero to whole grosze
+    pub fn checked_mul_quantity(self, quantity: Quantity) -> Option<Money> {
+        let thousandths = self.amount.checked_mul(quantity.amount)?;
+        let rounded = thousandths.checked_add(500 * thousandths.signum())? / 1000;
+        Some(Money::new(rounded, self.currency))
+    }
+    pub fn checked_sum(currency: Currency, iter: impl IntoIterator<Item = Money>) -> Option<Money> {
+        iter.into_iter()
+            .try_fold(Money::zero(currency), |acc, money| acc.checked_add(money))
+    }
+}
+
+// pl-PL: "12 345,67 zł", the frontend shows amounts the same way
+impl core::fmt::Display for Money {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> core::result::Result<(), core::fmt::Error> {
+        fmt.write_str(&shared::money::format(self.amount, self.currency.symbol()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn money_display() {
+        assert_eq!(Money::pln(0).to_string(), "0,00\u{a0}zł");
+        assert_eq!(Money::pln(5).to_string(), "0,05\u{a0}zł");
+        assert_eq!(Money::pln(10000).to_string(), "100,00\u{a0}zł");
+        assert_eq!(Money::pln(123456).to_string(), "1234,56\u{a0}zł");
+        assert_eq!(Money::pln(1234567).to_string(), "12\u{a0}345,67\u{a0}zł");
+        assert_eq!(
+            Money::pln(123456789).to_string(),
+            "1\u{a0}234\u{a0}567,89\u{a0}zł"
+        );
+    }
+
+    #[test]
+    fn money_display_negative() {
+        assert_eq!(Money::pln(-5).to_string(), "-0,05\u{a0}zł");
+        assert_eq!(Money::pln(-15000).to_string(), "-150,00\u{a0}zł");
+        assert_eq!(Money::pln(i64::MIN).to_string().chars().next(), Some('-'));
+    }
+
+    #[test]
+    fn money_checked_arithmetic() {
+        let a = Money::pln(10000);
+        let b = Money::pln(2550);
+        assert_eq!(a.checked_add(b), Some(Money::pln(12550)));
+        assert_eq!(b.checked_sub(a), Some(Money::pln(-7450)));
+        assert_eq!(b.checked_mul(3), Some(Money::pln(7650)));
+        assert_eq!(Money::pln(i64::MAX).checked_add(Money::pln(1)), None);
+        assert_eq!(Money::pln(i64::MIN).checked_sub(Money::pln(1)), None);
+        assert_eq!(Money::pln(i64::MAX).checked_mul(2), None);
+    }
+
+    #[test]
+    fn money_checked_mul_quantity() {
+        use crate::models::quantity::Unit;
+        let price = Money::pln(3000);
+        let half_litre = Quantity::new(500, Unit::L);
+        assert_eq!(
+            price.checked_mul_quantity(half_litre),
+            Some(Money::pln(1500))
+        );
+        // 0,333kg * 0,01zł = 0,00333zł
+        let third = Quantity::new(333, Unit::Kg);
+        assert_eq!(
+            Money::pln(1).checked_mul_quantity(third),
+            Some(Money::pln(0))
+        );
+        // 1,5 * 0,01zł = 0,015zł rounds up
+        let one_and_half = Quantity::new(1500, Unit::Szt);
+        assert_eq!(
+            Money::pln(1).checked_mul_quantity(one_and_half),
+            Some(Money::pln(2))
+        );
+        assert_eq!(
+            Money::pln(-1).checked_mul_quantity(one_and_half),
+            Some(Money::pln(-2))
+        );
+        assert_eq!(
+            Money::pln(i64::MAX).checked_mul_quantity(one_and_half),
+            None
+        );
+    }
+
+    #[test]
+    fn money_checked_sum() {
+        let sum = Money::checked_sum(Currency::PLN, [Money::pln(1), Money::pln(2)]);
+        assert_eq!(sum, Some(Money::pln(3)));
+        let empty = Money::checked_sum(Currency::PLN, []);
+        assert_eq!(empty, Some(Money::zero(Currency::PLN)));
+        let overflow = Money::checked_sum(Currency::PLN, [Money::pln(i64::MAX), Money::pln(1)]);
+        assert_eq!(overflow, None);
+    }
+
+    #[test]
+    fn money_serde() -> anyhow::Result<()> {
+        let json = serde_json::to_value(Money::pln(10000))?;
+        assert_eq!(
+            json,
+            serde_json::json!({"amount": 10000, "currency": "PLN"})
+        );
+        // currency defaults to PLN
+        let money: Money = serde_json::from_value(serde_json::json!({"amount": -500}))?;
+        assert_eq!(money, Money::pln(-500));
+        Ok(())
+    }
+}
@@ -1,48 +1,141 @@
 use serde::{Deserialize, Serialize};
 use sqlx::prelude::FromRow;
+use utoipa::{IntoParams, ToSchema};
 
-use super::item::{Item, ItemResponseBasic};
+use super::{
+    item::{Item, ItemResponseBasic},
+    money::Money,
+    tax::{Discount, OrderTotals},
+};
+
+#[derive(Serialize, Deserialize, sqlx::Type, Clone, Copy, Debug, PartialEq, Eq, ToSchema)]
+pub enum OrderStatus {
+    // still being written down
+    Draft,
+    Submitted,
+    InPreparation,
+    // every item is checked
+    Ready,
+    PickedUp,
+    Paid,
+    Cancelled,
+}
+
+impl OrderStatus {
+    pub fn can_transition_to(self, next: OrderStatus) -> bool {
+        use OrderStatus::*;
+        matches!(
+            (self, next),
+            (Draft, Submitted | Cancelled)
+                | (Submitted, Draft | InPreparation | Ready | Paid | Cancelled)
+                | (InPreparation, Submitted | Ready | Paid | Cancelled)
+                | (Ready, InPreparation | PickedUp | Paid | Cancelled)
+                | (PickedUp, Paid)
+                // reverting a payment
+                | (Paid, Submitted | InPreparation | Ready | PickedUp)
+        )
+    }
+    // goods of orders in these states have left the shop
+    pub fn is_goods_out(self) -> bool {
+        matches!(self, OrderStatus::PickedUp | OrderStatus::Paid)
+    }
+}
 
 #[derive(Serialize, Deserialize, FromRow)]
 pub struct Order {
     pub id: i32,
+    pub number: String, // for example ZAM/2026/00123, restarts every year
     pub creator_id: i32,
-    pub time_created: chrono::NaiveDateTime,
+    pub time_created: chrono::DateTime<chrono::Utc>,
     pub receiver: String,
     pub additional_info: Option<String>,
-    pub deleted: bool,
-    pub paid: bool,
+    pub status: OrderStatus,
+    pub picked_up: bool, // the goods left the shop, stays set once the order is paid
+    pub customer_id: Option<i32>,
+    pub discount_percent: Option<i64>,
+    pub discount_amount: Option<Money>,
+    pub version: i32, // raised by the database when a client editable field changes
+    pub updated_at: chrono::DateTime<chrono::Utc>, // set by the database, time_created until then
+    pub updated_by: i32,
+}
+
+impl Order {
+    pub fn discount(&self) -> Option<Discount> {
+        Discount::from_columns(self.discount_percent, self.discount_amount)
+    }
 }
 
 #[derive(Clone)]
 pub struct OrderForCreate {
     pub receiver: String,
     pub additional_info: Option<String>,
+    pub customer_id: Option<i32>,
 }
 
-#[derive(Serialize, Debug)]
+#[derive(Clone, Default)]
+pub struct OrderForUpdate {
+    pub receiver: Option<String>,
+    pub additional_info: Option<String>,
+    pub customer_id: Option<i32>,
+    pub discount: Option<Option<Discount>>, // replaces the order discount, Some(None) removes it
+    pub version: Option<i32>,               // the one the client read, None skips the check
+}
+
+#[derive(Serialize, Debug, ToSchema)]
 pub struct OrderResponseBasic {
     pub id: i32,
-    pub time_created: chrono::NaiveDateTime,
+    pub number: String,
+    pub time_created: chrono::DateTime<chrono::Utc>,
     pub receiver: String,
     pub additional_info: Option<String>,
+    pub status: OrderStatus,
+    pub picked_up: bool,
+    pub customer_id: Option<i32>,
+    pub discount: Option<Discount>,
+    pub totals: OrderTotals,
     pub items: Vec<ItemResponseBasic>,
+    pub version: i32,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+    pub updated_by: i32,
 }
-#[derive(Serialize, Deserialize, FromRow)]
+#[derive(Serialize, Deserialize, FromRow, ToSchema)]
 pub struct OrderResponseFull {
     pub id: i32,
+    pub number: String,
     pub creator_id: i32,
-    pub time_created: chrono::NaiveDateTime,
+    pub time_created: chrono::DateTime<chrono::Utc>,
     pub receiver: String,
     pub additional_info: Option<String>,
-    pub deleted: bool,
-    pub paid: bool,
+    pub status: OrderStatus,
+    pub picked_up: bool,
+    pub customer_id: Option<i32>,
+    pub discount: Option<Discount>,
+    pub totals: OrderTotals,
     pub items: Vec<Item>,
+    pub version: i32,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+    pub updated_by: i32,
+}
+#[derive(Serialize, Deserialize, FromRow, Debug, ToSchema)]
+pub struct StatusChange {
+    pub id: i32,
+    pub order_id: i32,
+    pub previous_status: Option<OrderStatus>,
+    pub status: OrderStatus,
+    pub user_id: i32,
+    pub time_created: chrono::DateTime<chrono::Utc>,
 }
-#[derive(Deserialize)]
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct OrderListParams {
     #[serde(alias = "dateStart")]
     pub date_start: Option<chrono::NaiveDate>,
     #[serde(alias = "dateEnd")]
     pub date_end: Option<chrono::NaiveDate>,
+    // part of the order number, "123" finds ZAM/2026/00123
+    pub number: Option<String>,
+    // orders changed since then, their items included. Cancelled orders are listed too, so
+    // syncing clients can drop them
+    #[serde(alias = "modifiedSince")]
+    pub modified_since: Option<chrono::DateTime<chrono::Utc>>,
 }
@@ -0,0 +1,40 @@
+// Payment, PaymentMethod, OrderBalance
+
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+use utoipa::ToSchema;
+
+use super::money::Money;
+
+#[derive(Serialize, Deserialize, sqlx::Type, Clone, Copy, Debug, PartialEq, Eq, ToSchema)]
+pub enum PaymentMethod {
+    Cash,
+    Card,
+    Transfer,
+}
+
+// refunds are payments with a negative amount
+#[derive(FromRow, Serialize, Deserialize, Debug, PartialEq, ToSchema)]
+pub struct Payment {
+    pub id: i32,
+    pub order_id: i32,
+    pub amount: Money,
+    pub method: PaymentMethod,
+    pub note: Option<String>,
+    pub creator_id: i32,
+    pub time_created: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Clone)]
+pub struct PaymentForCreate {
+    pub amount: Money,
+    pub method: PaymentMethod,
+    pub note: Option<String>,
+}
+
+#[derive(Serialize, Debug, PartialEq, ToSchema)]
+pub struct OrderBalance {
+    pub total: Money,   // sum of item totals
+    pub paid: Money,    // sum of payments and refunds
+    pub balance: Money, // still to pay, negative when overpaid
+}
@@ -0,0 +1,44 @@
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+use utoipa::ToSchema;
+
+use super::{money::Money, quantity::Unit};
+
+#[derive(FromRow, Clone, Deserialize, Serialize, Debug, PartialEq, ToSchema)]
+pub struct Product {
+    pub id: i32,
+    pub name: String,       // for example czerwona farba
+    pub default_unit: Unit, // for example l
+    pub unit_price: Money,  // price of one default_unit
+    pub category: Option<String>,
+    pub active: bool,                     // inactive products are hidden from search
+    pub low_stock_threshold: Option<i64>, // thousandths of default_unit
+}
+
+#[derive(Clone)]
+pub struct ProductForCreate {
+    pub name: String,
+    pub default_unit: Unit,
+    pub unit_price: Money,
+    pub category: Option<String>,
+    pub low_stock_threshold: Option<i64>,
+}
+
+#[derive(Clone, Default)]
+pub struct ProductForUpdate {
+    pub name: Option<String>,
+    pub default_unit: Option<Unit>,
+    pub unit_price: Option<Money>,
+    pub category: Option<String>,
+    pub active: Option<bool>,
+    pub low_stock_threshold: Option<i64>,
+}
+
+#[derive(Serialize, Debug, FromRow, ToSchema)]
+pub struct ProductResponseBasic {
+    pub id: i32,
+    pub name: String,
+    pub default_unit: Unit,
+    pub unit_price: Money,
+    pub category: Option<String>,
+}
@@ -0,0 +1,226 @@
+// Quantity, Unit
+
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::Error;
+
+#[derive(
+    Serialize,
+    Deserialize,
+    sqlx::Type,
+    Clone,
+    Copy,
+    Debug,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    ToSchema,
+)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "unit", rename_all = "lowercase")]
+pub enum Unit {
+    Szt,
+    Kg,
+    G,
+    L,
+    Ml,
+    M,
+    M2,
+}
+
+impl Unit {
+    // unit that quantities are totaled in, for example g and kg are both totaled in kg
+    pub fn base(self) -> Unit {
+        match self {
+            Unit::G => Unit::Kg,
+            Unit::Ml => Unit::L,
+            unit => unit,
+        }
+    }
+    // how many of this unit make one base unit
+    fn per_base(self) -> i64 {
+        match self {
+            Unit::G | Unit::Ml => 1000,
+            _ => 1,
+        }
+    }
+}
+
+impl FromStr for Unit {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.to_lowercase();
+        let unit = match s.strip_suffix('.').unwrap_or(&s) {
+            "" | "szt" => Unit::Szt,
+            "kg" => Unit::Kg,
+            "g" => Unit::G,
+            "l" => Unit::L,
+            "ml" => Unit::Ml,
+            "m" => Unit::M,
+            "m2" | "m²" => Unit::M2,
+            _ => return Err(Error::ItemInvalidQuantity),
+        };
+        Ok(unit)
+    }
+}
+
+// amount is stored in thousandths of the unit, for example 1_500 with Unit::Kg = 1.5kg
+#[derive(Serialize, Deserialize, sqlx::Type, Clone, Copy, Debug, PartialEq, Eq, Hash, ToSchema)]
+#[sqlx(type_name = "quantity_amount")]
+pub struct Quantity {
+    pub amount: i64,
+    pub unit: Unit,
+}
+
+impl Quantity {
+    pub fn new(amount: i64, unit: Unit) -> Self {
+        Self { amount, unit }
+    }
+    pub fn to_base(self) -> Quantity {
+        let amount = div_round(self.amount, self.unit.per_base());
+        Quantity::new(amount, self.unit.base())
+    }
+    // None if units measure different things (kg and l) or on overflow
+    pub fn convert(self, unit: Unit) -> Option<Quantity> {
+        if self.unit.base() != unit.base() {
+            return None;
+        }
+        let amount = div_round(
+            self.amount.checked_mul(unit.per_base())?,
+            self.unit.per_base(),
+        );
+        Some(Quantity::new(amount, unit))
+    }
+}
+
+// rounds halves away from zero, so 0.5g in kg is 0.001kg
+fn div_round(amount: i64, divisor: i64) -> i64 {
+    let (quotient, remainder) = (amount / divisor, amount % divisor);
+    if remainder.abs() * 2 >= divisor {
+        quotient + amount.signum()
+    } else {
+        quotient
+    }
+}
+
+// accepts for example "1l", "5 kg", "1,5kg", "0.25 l", "3 szt." and "2" (defaults to szt). The
+// grammar is the one the quantity_units migration parsed existing items with
+impl FromStr for Quantity {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let whole_end = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+        let (whole, rest) = s.split_at(whole_end);
+        // a separator not followed by digits isn't a fraction, "3." is 3 szt.
+        let fraction_end = rest
+            .strip_prefix(['.', ','])
+            .map(|fraction| {
+                fraction
+                    .find(|c: char| !c.is_ascii_digit())
+                    .unwrap_or(fraction.len())
+            })
+            .unwrap_or(0);
+        let (fraction, unit) = if fraction_end > 0 {
+            (&rest[1..fraction_end + 1], &rest[fraction_end + 1..])
+        } else {
+            ("", rest)
+        };
+        let unit: Unit = unit.trim_start().parse()?;
+        if whole.is_empty() || fraction.len() > 3 {
+            return Err(Error::ItemInvalidQuantity);
+        }
+        let whole: i64 = whole.parse().map_err(|_| Error::ItemInvalidQuantity)?;
+        let fraction: i64 = format!("{fraction:0<3}")
+            .parse()
+            .map_err(|_| Error::ItemInvalidQuantity)?;
+        let amount = whole
+            .checked_mul(1000)
+            .and_then(|whole| whole.checked_add(fraction))
+            .ok_or(Error::ItemInvalidQuantity)?;
+        if amount == 0 {
+            return Err(Error::ItemInvalidQuantity);
+        }
+        Ok(Quantity::new(amount, unit))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quantity_parse() {
+        let cases = [
+            ("1l", Quantity::new(1000, Unit::L)),
+            ("5kg", Quantity::new(5000, Unit::Kg)),
+            ("5 KG", Quantity::new(5000, Unit::Kg)),
+            ("1,5kg", Quantity::new(1500, Unit::Kg)),
+            ("0.25 l", Quantity::new(250, Unit::L)),
+            ("250ml", Quantity::new(250000, Unit::Ml)),
+            ("3 szt.", Quantity::new(3000, Unit::Szt)),
+            ("2", Quantity::new(2000, Unit::Szt)),
+            ("12m2", Quantity::new(12000, Unit::M2)),
+            ("12 m²", Quantity::new(12000, Unit::M2)),
+            ("7m", Quantity::new(7000, Unit::M)),
+            ("100g", Quantity::new(100000, Unit::G)),
+            ("3.", Quantity::new(3000, Unit::Szt)),
+            ("3 Szt", Quantity::new(3000, Unit::Szt)),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(input.parse::<Quantity>(), Ok(expected), "{input}");
+        }
+    }
+
+    #[test]
+    fn quantity_parse_invalid() {
+        for input in [
+            "", "kg", "foidaj", "1,2345kg", "1.2.3l", "5 beczek", "0kg", "-1l", "0,000", "1.kg",
+            "3 szt..", ",5l",
+        ] {
+            assert_eq!(
+                input.parse::<Quantity>(),
+                Err(Error::ItemInvalidQuantity),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn quantity_to_base() {
+        let g = Quantity::new(250000, Unit::G);
+        assert_eq!(g.to_base(), Quantity::new(250, Unit::Kg));
+        let ml = Quantity::new(1500000, Unit::Ml);
+        assert_eq!(ml.to_base(), Quantity::new(1500, Unit::L));
+        let szt = Quantity::new(3000, Unit::Szt);
+        assert_eq!(szt.to_base(), szt);
+        // rounded, not truncated
+        assert_eq!(
+            Quantity::new(1500, Unit::G).to_base(),
+            Quantity::new(2, Unit::Kg)
+        );
+        assert_eq!(
+            Quantity::new(1499, Unit::G).to_base(),
+            Quantity::new(1, Unit::Kg)
+        );
+        assert_eq!(
+            Quantity::new(-1500, Unit::Ml).to_base(),
+            Quantity::new(-2, Unit::L)
+        );
+    }
+
+    #[test]
+    fn quantity_convert() {
+        let kg = Quantity::new(1500, Unit::Kg);
+        assert_eq!(kg.convert(Unit::G), Some(Quantity::new(1500000, Unit::G)));
+        let ml = Quantity::new(250000, Unit::Ml);
+        assert_eq!(ml.convert(Unit::L), Some(Quantity::new(250, Unit::L)));
+        assert_eq!(ml.convert(Unit::Ml), Some(ml));
+        assert_eq!(kg.convert(Unit::L), None);
+        assert_eq!(kg.convert(Unit::Szt), None);
+    }
+}
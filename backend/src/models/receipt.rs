@@ -0,0 +1,58 @@
+// ShopInfo, ReceiptLayout
+
+use std::str::FromStr;
+
+use chrono_tz::Tz;
+use serde::Deserialize;
+use utoipa::{IntoParams, ToSchema};
+
+use super::time::DEFAULT_TIMEZONE;
+use crate::Error;
+
+// header printed on receipts
+#[derive(Clone, Debug)]
+pub struct ShopInfo {
+    pub name: String,
+    pub address: String,
+    pub nip: String,
+    pub receipt_layout: ReceiptLayout,
+    pub timezone: Tz, // days of date filters, reports and documents
+}
+
+impl Default for ShopInfo {
+    fn default() -> Self {
+        ShopInfo {
+            name: Default::default(),
+            address: Default::default(),
+            nip: Default::default(),
+            receipt_layout: Default::default(),
+            timezone: DEFAULT_TIMEZONE,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ReceiptLayout {
+    #[default]
+    A4,
+    // 80mm roll of a thermal printer
+    Thermal,
+}
+
+impl FromStr for ReceiptLayout {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "a4" => Ok(ReceiptLayout::A4),
+            "thermal" => Ok(ReceiptLayout::Thermal),
+            _ => Err(Error::ReceiptInvalidLayout),
+        }
+    }
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ReceiptParams {
+    pub layout: Option<ReceiptLayout>,
+}
@@ -0,0 +1,11 @@
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use super::quantity::Quantity;
+
+// total amount of one item name sold, in its base unit
+#[derive(Serialize, Debug, PartialEq, ToSchema)]
+pub struct QuantityTotal {
+    pub name: String,
+    pub quantity: Quantity,
+}
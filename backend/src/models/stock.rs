@@ -0,0 +1,45 @@
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+use utoipa::ToSchema;
+
+use super::quantity::Quantity;
+
+#[derive(Serialize, Deserialize, sqlx::Type, Clone, Copy, Debug, PartialEq, Eq, ToSchema)]
+pub enum StockMovementKind {
+    // goods in
+    Receipt,
+    // goods out with a checked item or a paid order
+    Sale,
+    // sale taken back after unchecking, unpaying or deleting
+    Return,
+    // manual correction with a reason
+    Adjustment,
+}
+
+#[derive(FromRow, Serialize, Debug, ToSchema)]
+pub struct StockMovement {
+    pub id: i32,
+    pub product_id: i32,
+    pub item_id: Option<i32>,
+    pub kind: StockMovementKind,
+    pub amount: i64, // thousandths of the product default_unit, negative for goods out
+    pub reason: Option<String>,
+    pub creator_id: i32,
+    pub time_created: chrono::DateTime<chrono::Utc>,
+}
+
+// quantity can be given in any unit convertible to the product default_unit
+#[derive(Clone)]
+pub struct StockMovementForCreate {
+    pub product_id: i32,
+    pub quantity: Quantity,
+    pub reason: Option<String>,
+}
+
+#[derive(Serialize, Debug, PartialEq, ToSchema)]
+pub struct StockLevel {
+    pub product_id: i32,
+    pub name: String,
+    pub stock: Quantity,
+    pub low_stock_threshold: Option<Quantity>,
+}
@@ -0,0 +1,388 @@
+// VatRate, Discount and totals of items and orders
+//
+// Item values are gross prices, the way they are written on the shop floor. Line discounts
+// come first, the order discount is then spread over the lines in proportion to what is left
+// of them. VAT is computed from the gross of every rate summed over the order, so the order
+// totals match an invoice, while the per line VAT is only informative.
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::money::{Currency, Money};
+use crate::Error;
+
+#[derive(
+    Serialize,
+    Deserialize,
+    sqlx::Type,
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    ToSchema,
+)]
+pub enum VatRate {
+    #[default]
+    #[serde(rename = "23")]
+    #[sqlx(rename = "23")]
+    Vat23,
+    #[serde(rename = "8")]
+    #[sqlx(rename = "8")]
+    Vat8,
+    #[serde(rename = "5")]
+    #[sqlx(rename = "5")]
+    Vat5,
+    #[serde(rename = "0")]
+    #[sqlx(rename = "0")]
+    Vat0,
+}
+
+impl VatRate {
+    pub fn percent(self) -> i64 {
+        match self {
+            VatRate::Vat23 => 23,
+            VatRate::Vat8 => 8,
+            VatRate::Vat5 => 5,
+            VatRate::Vat0 => 0,
+        }
+    }
+}
+
+// {"kind": "percent", "value": 1050} takes 10,5% off, {"kind": "amount", "value": {"amount": 500}} 5zł
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, ToSchema)]
+#[serde(tag = "kind", content = "value", rename_all = "lowercase")]
+pub enum Discount {
+    Percent(i64), // hundredths of a percent, 10000 = 100%
+    Amount(Money),
+}
+
+impl Discount {
+    pub fn validate(self) -> Result<Self, Error> {
+        match self {
+            Discount::Percent(0..=10000) => Ok(self),
+            Discount::Amount(money) if !money.is_negative() => Ok(self),
+            _ => Err(Error::DiscountInvalid),
+        }
+    }
+    // discounts are stored in two columns, at most one of them is set
+    pub fn from_columns(percent: Option<i64>, amount: Option<Money>) -> Option<Discount> {
+        match (percent, amount) {
+            (Some(percent), _) => Some(Discount::Percent(percent)),
+            (None, Some(amount)) => Some(Discount::Amount(amount)),
+            (None, None) => None,
+        }
+    }
+    pub fn into_columns(discount: Option<Discount>) -> (Option<i64>, Option<Money>) {
+        match discount {
+            Some(Discount::Percent(percent)) => (Some(percent), None),
+            Some(Discount::Amount(amount)) => (None, Some(amount)),
+            None => (None, None),
+        }
+    }
+    // the part of gross taken off, never more than gross and nothing off negative amounts
+    fn of(self, gross: i64) -> i64 {
+        if gross <= 0 {
+            return 0;
+        }
+        match self {
+            Discount::Percent(percent) => div_round(gross as i128 * percent as i128, 10000) as i64,
+            Discount::Amount(money) => money.amount.clamp(0, gross),
+        }
+    }
+}
+
+// division rounded half away from zero, d is positive
+fn div_round(n: i128, d: i128) -> i128 {
+    (n + n.signum() * d / 2) / d
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, ToSchema)]
+pub struct Totals {
+    pub net: Money,
+    pub vat: Money,
+    pub gross: Money,
+}
+
+impl Totals {
+    fn from_gross(gross: Money, rate: VatRate) -> Totals {
+        let percent = rate.percent() as i128;
+        let vat = div_round(gross.amount as i128 * percent, 100 + percent) as i64;
+        Totals {
+            net: Money::new(gross.amount - vat, gross.currency),
+            vat: Money::new(vat, gross.currency),
+            gross,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, ToSchema)]
+pub struct LineTotals {
+    pub before_discount: Money, // value times quantity
+    pub discount: Money,        // the line discount and its part of the order discount
+    #[serde(flatten)]
+    pub totals: Totals,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, ToSchema)]
+pub struct RateTotals {
+    pub rate: VatRate,
+    #[serde(flatten)]
+    pub totals: Totals,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, ToSchema)]
+pub struct OrderTotals {
+    pub before_discount: Money, // sum of the lines before any discount
+    pub discount: Money,        // line and order discounts together
+    #[serde(flatten)]
+    pub totals: Totals,
+    pub by_rate: Vec<RateTotals>, // highest rate first, only rates in use
+}
+
+// what a line is priced from
+pub struct Line {
+    pub gross: Money, // before discount
+    pub rate: VatRate,
+    pub discount: Option<Discount>,
+}
+
+// None on overflow
+pub fn order_totals(
+    lines: &[Line],
+    order_discount: Option<Discount>,
+) -> Option<(Vec<LineTotals>, OrderTotals)> {
+    let currency = Currency::PLN;
+    let line_discounts: Vec<i64> = lines
+        .iter()
+        .map(|line| {
+            line.discount
+                .map_or(0, |discount| discount.of(line.gross.amount))
+        })
+        .collect();
+    let discounted: Vec<i64> = lines
+        .iter()
+        .zip(&line_discounts)
+        .map(|(line, discount)| line.gross.amount.checked_sub(*discount))
+        .collect::<Option<_>>()?;
+
+    // the order discount is spread by the largest remainder method so the parts add up exactly
+    let weights: Vec<i128> = discounted
+        .iter()
+        .map(|gross| (*gross).max(0) as i128)
+        .collect();
+    let weight: i128 = weights.iter().sum();
+    let subtotal: i64 = discounted
+        .iter()
+        .try_fold(0i64, |sum, gross| sum.checked_add(*gross))?;
+    let order_discount = order_discount.map_or(0, |discount| discount.of(subtotal)) as i128;
+    let mut shares: Vec<i128> = vec![0; lines.len()];
+    if weight > 0 {
+        let mut remainders = vec![];
+        for (i, w) in weights.iter().enumerate() {
+            shares[i] = order_discount * w / weight;
+            remainders.push((order_discount * w % weight, i));
+        }
+        let left = order_discount - shares.iter().sum::<i128>();
+        remainders.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+        for (_, i) in remainders.into_iter().take(left as usize) {
+            shares[i] += 1;
+        }
+    }
+
+    let mut line_totals = vec![];
+    let mut by_rate: Vec<(VatRate, i64)> = vec![];
+    for (i, line) in lines.iter().enumerate() {
+        let gross = discounted[i] - shares[i] as i64;
+        let discount = line_discounts[i] + shares[i] as i64;
+        line_totals.push(LineTotals {
+            before_discount: line.gross,
+            discount: Money::new(discount, currency),
+            totals: Totals::from_gross(Money::new(gross, currency), line.rate),
+        });
+        match by_rate.iter_mut().find(|(rate, _)| *rate == line.rate) {
+            Some((_, sum)) => *sum = sum.checked_add(gross)?,
+            None => by_rate.push((line.rate, gross)),
+        }
+    }
+    by_rate.sort();
+    let by_rate: Vec<RateTotals> = by_rate
+        .into_iter()
+        .map(|(rate, gross)| RateTotals {
+            rate,
+            totals: Totals::from_gross(Money::new(gross, currency), rate),
+        })
+        .collect();
+
+    let sum = |f: fn(&Totals) -> Money| {
+        Money::checked_sum(currency, by_rate.iter().map(|rate| f(&rate.totals)))
+    };
+    let totals = OrderTotals {
+        before_discount: Money::checked_sum(currency, lines.iter().map(|line| line.gross))?,
+        discount: Money::checked_sum(currency, line_totals.iter().map(|line| line.discount))?,
+        totals: Totals {
+            net: sum(|totals| totals.net)?,
+            vat: sum(|totals| totals.vat)?,
+            gross: sum(|totals| totals.gross)?,
+        },
+        by_rate,
+    };
+    Some((line_totals, totals))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(gross: i64, rate: VatRate, discount: Option<Discount>) -> Line {
+        Line {
+            gross: Money::pln(gross),
+            rate,
+            discount,
+        }
+    }
+
+    #[test]
+    fn tax_totals_from_gross() {
+        // 123zł gross at 23% is 100zł net
+        let totals = Totals::from_gross(Money::pln(12300), VatRate::Vat23);
+        assert_eq!(totals.net, Money::pln(10000));
+        assert_eq!(totals.vat, Money::pln(2300));
+        // 0,99zł * 8/108 = 0,0733zł
+        let totals = Totals::from_gross(Money::pln(99), VatRate::Vat8);
+        assert_eq!(totals.vat, Money::pln(7));
+        assert_eq!(totals.net, Money::pln(92));
+        // 0,21zł * 5/105 = 0,01zł exactly, 0,31zł * 5/105 = 0,0148zł
+        assert_eq!(
+            Totals::from_gross(Money::pln(21), VatRate::Vat5).vat,
+            Money::pln(1)
+        );
+        assert_eq!(
+            Totals::from_gross(Money::pln(31), VatRate::Vat5).vat,
+            Money::pln(1)
+        );
+        assert_eq!(
+            Totals::from_gross(Money::pln(-12300), VatRate::Vat23).vat,
+            Money::pln(-2300)
+        );
+        assert_eq!(
+            Totals::from_gross(Money::pln(500), VatRate::Vat0).vat,
+            Money::pln(0)
+        );
+    }
+
+    #[test]
+    fn tax_discount_of() {
+        assert_eq!(Discount::Percent(1000).of(12345), 1235);
+        assert_eq!(Discount::Percent(10000).of(12345), 12345);
+        assert_eq!(Discount::Amount(Money::pln(500)).of(12345), 500);
+        // never more than the line
+        assert_eq!(Discount::Amount(Money::pln(50000)).of(12345), 12345);
+        assert_eq!(Discount::Percent(1000).of(-500), 0);
+
+        assert_eq!(
+            Discount::Percent(10001).validate(),
+            Err(Error::DiscountInvalid)
+        );
+        assert_eq!(
+            Discount::Percent(-1).validate(),
+            Err(Error::DiscountInvalid)
+        );
+        assert_eq!(
+            Discount::Amount(Money::pln(-1)).validate(),
+            Err(Error::DiscountInvalid)
+        );
+        assert_eq!(
+            Discount::Percent(550).validate(),
+            Ok(Discount::Percent(550))
+        );
+    }
+
+    #[test]
+    fn tax_order_totals() {
+        let lines = [
+            line(12300, VatRate::Vat23, Some(Discount::Percent(1000))),
+            line(10800, VatRate::Vat8, None),
+            line(
+                2460,
+                VatRate::Vat23,
+                Some(Discount::Amount(Money::pln(460))),
+            ),
+        ];
+        let (line_totals, totals) = order_totals(&lines, None).unwrap();
+        assert_eq!(line_totals[0].discount, Money::pln(1230));
+        assert_eq!(line_totals[0].totals.gross, Money::pln(11070));
+        assert_eq!(line_totals[2].totals.gross, Money::pln(2000));
+
+        assert_eq!(totals.before_discount, Money::pln(25560));
+        assert_eq!(totals.discount, Money::pln(1690));
+        assert_eq!(totals.totals.gross, Money::pln(23870));
+        assert_eq!(totals.by_rate.len(), 2);
+        // 130,70zł at 23% and 108zł at 8%
+        assert_eq!(totals.by_rate[0].rate, VatRate::Vat23);
+        assert_eq!(totals.by_rate[0].totals.gross, Money::pln(13070));
+        assert_eq!(totals.by_rate[0].totals.vat, Money::pln(2444));
+        assert_eq!(totals.by_rate[1].totals.vat, Money::pln(800));
+        assert_eq!(totals.totals.vat, Money::pln(3244));
+        assert_eq!(totals.totals.net, Money::pln(20626));
+    }
+
+    #[test]
+    fn tax_order_discount_spread() {
+        // 1zł off three equal lines can't be split evenly, the first line takes the extra grosz
+        let lines = [
+            line(1000, VatRate::Vat23, None),
+            line(1000, VatRate::Vat8, None),
+            line(1000, VatRate::Vat0, None),
+        ];
+        let (line_totals, totals) =
+            order_totals(&lines, Some(Discount::Amount(Money::pln(100)))).unwrap();
+        let discounts: Vec<i64> = line_totals
+            .iter()
+            .map(|line| line.discount.amount)
+            .collect();
+        assert_eq!(discounts, vec![34, 33, 33]);
+        assert_eq!(totals.totals.gross, Money::pln(2900));
+        assert_eq!(totals.discount, Money::pln(100));
+
+        // percent of the order after line discounts
+        let lines = [
+            line(10000, VatRate::Vat23, Some(Discount::Percent(5000))),
+            line(5000, VatRate::Vat23, None),
+        ];
+        let (_, totals) = order_totals(&lines, Some(Discount::Percent(1000))).unwrap();
+        assert_eq!(totals.totals.gross, Money::pln(9000));
+        assert_eq!(totals.discount, Money::pln(6000));
+    }
+
+    #[test]
+    fn tax_order_totals_empty_and_overflow() {
+        let (lines, totals) = order_totals(&[], Some(Discount::Percent(1000))).unwrap();
+        assert!(lines.is_empty());
+        assert_eq!(totals.totals.gross, Money::pln(0));
+        assert!(totals.by_rate.is_empty());
+
+        let lines = [
+            line(i64::MAX, VatRate::Vat23, None),
+            line(1, VatRate::Vat23, None),
+        ];
+        assert_eq!(order_totals(&lines, None), None);
+    }
+
+    #[test]
+    fn tax_serde() -> anyhow::Result<()> {
+        assert_eq!(serde_json::to_value(VatRate::Vat8)?, serde_json::json!("8"));
+        let discount: Discount = serde_json::from_value(
+            serde_json::json!({"kind": "amount", "value": {"amount": 500}}),
+        )?;
+        assert_eq!(discount, Discount::Amount(Money::pln(500)));
+        let discount: Discount =
+            serde_json::from_value(serde_json::json!({"kind": "percent", "value": 1050}))?;
+        assert_eq!(discount, Discount::Percent(1050));
+        Ok(())
+    }
+}
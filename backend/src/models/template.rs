@@ -0,0 +1,68 @@
+// OrderTemplate, OrderTemplateItem, OrderTemplateDetail
+
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+use utoipa::ToSchema;
+
+use super::{
+    item::ItemForCreate,
+    money::Money,
+    tax::{Discount, VatRate},
+};
+
+#[derive(FromRow, Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+pub struct OrderTemplate {
+    pub id: i32,
+    pub name: String, // for example farby dla Kowalskiego
+    pub receiver: String,
+    pub additional_info: Option<String>,
+    pub customer_id: Option<i32>,
+    pub creator_id: i32,
+    pub time_created: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(FromRow, Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+pub struct OrderTemplateItem {
+    pub id: i32,
+    pub template_id: i32,
+    pub quantity: String,
+    pub product_id: Option<i32>,
+    pub name: String,
+    pub value: Money,
+    pub additional_info: Option<String>,
+    pub vat_rate: VatRate,
+    pub discount_percent: Option<i64>,
+    pub discount_amount: Option<Money>,
+}
+
+impl OrderTemplateItem {
+    pub fn discount(&self) -> Option<Discount> {
+        Discount::from_columns(self.discount_percent, self.discount_amount)
+    }
+}
+
+impl From<OrderTemplateItem> for ItemForCreate {
+    fn from(item: OrderTemplateItem) -> Self {
+        ItemForCreate {
+            discount: item.discount(),
+            quantity: item.quantity,
+            name: item.name,
+            value: item.value,
+            additional_info: item.additional_info,
+            product_id: item.product_id,
+            vat_rate: item.vat_rate,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct OrderTemplateForCreate {
+    pub name: String,
+    pub order_id: i32,
+}
+
+#[derive(Serialize, Debug, PartialEq, ToSchema)]
+pub struct OrderTemplateDetail {
+    pub template: OrderTemplate,
+    pub items: Vec<OrderTemplateItem>,
+}
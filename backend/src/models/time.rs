@@ -0,0 +1,76 @@
+// Days in the shop timezone. Times are stored in UTC, a day runs from one local midnight to the
+// next, so around the DST switches it lasts 23 or 25 hours
+
+use chrono::{DateTime, Duration, NaiveDate, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+
+// also the zone migration 20261019220000_timestamptz assumes for older times
+pub const DEFAULT_TIMEZONE: Tz = chrono_tz::Europe::Warsaw;
+
+// first moment of the day, a midnight skipped by a DST switch moves to the first local time
+// that exists
+pub fn day_start(timezone: Tz, date: NaiveDate) -> DateTime<Utc> {
+    let mut local = date.and_time(NaiveTime::MIN);
+    loop {
+        if let Some(start) = timezone.from_local_datetime(&local).earliest() {
+            return start.with_timezone(&Utc);
+        }
+        local += Duration::minutes(15);
+    }
+}
+
+// first moment after the day
+pub fn day_end(timezone: Tz, date: NaiveDate) -> DateTime<Utc> {
+    match date.succ_opt() {
+        Some(next) => day_start(timezone, next),
+        None => DateTime::<Utc>::MAX_UTC,
+    }
+}
+
+pub fn date_of(timezone: Tz, time: DateTime<Utc>) -> NaiveDate {
+    time.with_timezone(&timezone).date_naive()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(s: &str) -> DateTime<Utc> {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn time_dst_days() {
+        let warsaw = DEFAULT_TIMEZONE;
+        // winter, +01:00
+        let date = NaiveDate::from_ymd_opt(2026, 1, 15).unwrap();
+        assert_eq!(day_start(warsaw, date), utc("2026-01-14T23:00:00Z"));
+        assert_eq!(day_end(warsaw, date), utc("2026-01-15T23:00:00Z"));
+
+        // 2:00 becomes 3:00, the day lasts 23 hours
+        let spring = NaiveDate::from_ymd_opt(2026, 3, 29).unwrap();
+        assert_eq!(day_start(warsaw, spring), utc("2026-03-28T23:00:00Z"));
+        assert_eq!(day_end(warsaw, spring), utc("2026-03-29T22:00:00Z"));
+        assert_eq!(date_of(warsaw, utc("2026-03-29T21:59:59Z")), spring);
+        assert_eq!(
+            date_of(warsaw, utc("2026-03-29T22:00:00Z")),
+            spring.succ_opt().unwrap()
+        );
+
+        // 3:00 becomes 2:00 again, the day lasts 25 hours
+        let autumn = NaiveDate::from_ymd_opt(2026, 10, 25).unwrap();
+        assert_eq!(day_start(warsaw, autumn), utc("2026-10-24T22:00:00Z"));
+        assert_eq!(day_end(warsaw, autumn), utc("2026-10-25T23:00:00Z"));
+        assert_eq!(date_of(warsaw, utc("2026-10-24T22:30:00Z")), autumn);
+        assert_eq!(date_of(warsaw, utc("2026-10-25T22:59:59Z")), autumn);
+    }
+
+    #[test]
+    fn time_skipped_midnight() {
+        // Santiago moved its clocks from 0:00 to 1:00 on 2026-09-06
+        let santiago = chrono_tz::America::Santiago;
+        let date = NaiveDate::from_ymd_opt(2026, 9, 6).unwrap();
+        assert_eq!(day_start(santiago, date), utc("2026-09-06T04:00:00Z"));
+        assert_eq!(date_of(santiago, day_start(santiago, date)), date);
+    }
+}
@@ -1,9 +1,14 @@
 // User, UserForCreate
 
+use argon2::{
+    password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    Argon2,
+};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
+use utoipa::ToSchema;
 
-#[derive(Serialize, Deserialize, sqlx::Type, Clone)]
+#[derive(Serialize, Deserialize, sqlx::Type, Clone, ToSchema)]
 pub enum Privileges {
     // Add new orders, see orders
     Basic,
@@ -19,3 +24,55 @@ pub struct User {
     pub password: String,
     pub privileges: Privileges,
 }
+
+impl User {
+    // passwords set before they were hashed are still plaintext, login hashes them
+    pub fn password_is_hashed(&self) -> bool {
+        PasswordHash::new(&self.password).is_ok()
+    }
+    pub fn password_matches(&self, password: &str) -> bool {
+        match PasswordHash::new(&self.password) {
+            Ok(hash) => Argon2::default()
+                .verify_password(password.as_bytes(), &hash)
+                .is_ok(),
+            Err(_) => self.password == password,
+        }
+    }
+}
+
+// the argon2 PHC string stored in users.password
+pub fn hash_password(password: &str) -> Result<String, argon2::password_hash::Error> {
+    let salt = SaltString::generate(&mut OsRng);
+    let hash = Argon2::default().hash_password(password.as_bytes(), &salt)?;
+    Ok(hash.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user(password: String) -> User {
+        User {
+            id: 1,
+            name: "ania".to_owned(),
+            password,
+            privileges: Privileges::Basic,
+        }
+    }
+
+    #[test]
+    fn user_password() {
+        let hashed = user(hash_password("tajne").unwrap());
+        assert!(hashed.password_is_hashed());
+        assert!(hashed.password.starts_with("$argon2"));
+        assert!(hashed.password_matches("tajne"));
+        assert!(!hashed.password_matches("inne"));
+        // salted, the same password hashes differently
+        assert_ne!(hash_password("tajne").unwrap(), hashed.password);
+
+        let plain = user("123".to_owned());
+        assert!(!plain.password_is_hashed());
+        assert!(plain.password_matches("123"));
+        assert!(!plain.password_matches("1234"));
+    }
+}
@@ -1,14 +1,111 @@
-use axum::{extract::Path, routing::get, Json, Router};
+use axum::{
+    extract::{Path, Query},
+    http::header,
+    response::IntoResponse,
+    routing::{get, post},
+    Json, Router,
+};
 use serde::Deserialize;
+use utoipa::{OpenApi, ToSchema};
+use validator::Validate;
 
-use crate::{controllers, models::order::OrderResponseFull, session::Session, AppState, Result};
+use super::Created;
+use crate::{
+    controllers,
+    models::{
+        bulk::BulkReport,
+        event::{Event, EventKind},
+        invoice::{CorrectionForCreate, Invoice, InvoiceDetail, InvoiceForCreate},
+        money::Money,
+        order::{OrderListParams, OrderResponseFull},
+        payment::{OrderBalance, Payment, PaymentForCreate, PaymentMethod},
+        product::{Product, ProductForCreate, ProductForUpdate},
+        quantity::{Quantity, Unit},
+        report::QuantityTotal,
+        stock::{StockLevel, StockMovement, StockMovementForCreate},
+    },
+    session::Session,
+    validation::{
+        non_negative, non_zero, non_zero_quantity, trimmed, trimmed_option, ValidJson,
+        ValidationReport,
+    },
+    AppState, Result,
+};
 
 pub fn routes() -> Router<AppState> {
     Router::new()
-        .route("/admin/order/:id", get(read).patch(pay))
+        .route("/admin/order/:id", get(read))
         .route("/admin/order", get(list))
+        .route(
+            "/admin/order/:id/payments",
+            get(payment_list).post(payment_create),
+        )
+        .route("/admin/order/:id/balance", get(payment_balance))
+        .route("/admin/order/bulk/pay", post(bulk_pay))
+        .route("/admin/order/bulk/unpay", post(bulk_unpay))
+        .route("/admin/order/:id/invoice", post(invoice_issue))
+        .route("/admin/invoices", get(invoice_list))
+        .route("/admin/invoices/schema.xsd", get(invoice_schema))
+        .route("/admin/invoices/:id", get(invoice_read))
+        .route("/admin/invoices/:id/corrections", post(invoice_correct))
+        .route("/admin/invoices/:id/invoice.pdf", get(invoice_pdf))
+        .route("/admin/invoices/:id/invoice.xml", get(invoice_xml))
+        .route("/admin/customers/:id/merge", post(customer_merge))
+        .route("/admin/products", get(product_list).post(product_create))
+        .route(
+            "/admin/products/:id",
+            get(product_read)
+                .patch(product_update)
+                .delete(product_delete),
+        )
+        .route("/admin/stock", get(stock_levels))
+        .route("/admin/stock/receipts", post(stock_receive))
+        .route("/admin/stock/adjustments", post(stock_adjust))
+        .route("/admin/stock/:product_id/movements", get(stock_movements))
+        .route("/admin/report/quantities", get(report_quantities))
+        .route("/admin/report/low-stock", get(report_low_stock))
 }
 
+// every route needs Full privileges
+#[derive(OpenApi)]
+#[openapi(paths(
+    read,
+    list,
+    payment_list,
+    payment_create,
+    payment_balance,
+    bulk_pay,
+    bulk_unpay,
+    invoice_issue,
+    invoice_list,
+    invoice_schema,
+    invoice_read,
+    invoice_correct,
+    invoice_pdf,
+    invoice_xml,
+    customer_merge,
+    product_list,
+    product_create,
+    product_read,
+    product_update,
+    product_delete,
+    stock_levels,
+    stock_receive,
+    stock_adjust,
+    stock_movements,
+    report_quantities,
+    report_low_stock
+))]
+pub struct Api;
+
+#[utoipa::path(
+    get,
+    path = "/admin/order/{id}",
+    operation_id = "admin_order_read",
+    tag = "admin",
+    params(("id" = i32, Path)),
+    responses((status = 200, body = OrderResponseFull))
+)]
 async fn read(
     session: Session,
     AppState { db, .. }: AppState,
@@ -18,21 +115,145 @@ async fn read(
     Ok(Json(ord))
 }
 
-#[derive(Deserialize)]
-struct PayPayload {
-    paid: bool,
+// negative amounts are refunds
+#[derive(Deserialize, Validate, ToSchema)]
+struct PaymentPayload {
+    #[validate(custom(function = "non_zero"))]
+    amount: Money,
+    method: PaymentMethod,
+    #[serde(default, deserialize_with = "trimmed_option")]
+    #[validate(length(max = 500))]
+    note: Option<String>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/order/{id}/payments",
+    operation_id = "admin_payment_create",
+    tag = "admin",
+    params(("id" = i32, Path)),
+    request_body = PaymentPayload,
+    responses(
+        (status = 200, body = Created),
+        (status = 409, description = "the order is cancelled"),
+        (status = 422, body = ValidationReport),
+    )
+)]
+async fn payment_create(
+    session: Session,
+    AppState { db, events, .. }: AppState,
+    Path(order_id): Path<i32>,
+    ValidJson(payload): ValidJson<PaymentPayload>,
+) -> Result<Json<Created>> {
+    let payment = PaymentForCreate {
+        amount: payload.amount,
+        method: payload.method,
+        note: payload.note,
+    };
+    let id = controllers::admin::payment::create(session, order_id, payment, db).await?;
+    // paying can change the status too
+    events.publish(Event::order(EventKind::OrderPaid, order_id));
+    events.publish(Event::order(EventKind::OrderUpdated, order_id));
+    Ok(Json(Created { id }))
+}
+
+// the whole balance of every order is paid, or everything paid is refunded
+#[derive(Deserialize, Validate, ToSchema)]
+struct BulkPaymentPayload {
+    #[validate(length(min = 1, max = controllers::bulk::LIMIT))]
+    order_ids: Vec<i32>,
+    method: PaymentMethod,
 }
 
-async fn pay(
+#[utoipa::path(
+    post,
+    path = "/admin/order/bulk/pay",
+    operation_id = "admin_bulk_pay",
+    tag = "admin",
+    request_body = BulkPaymentPayload,
+    responses(
+        (status = 200, body = BulkReport),
+        (status = 422, body = ValidationReport),
+    )
+)]
+async fn bulk_pay(
+    session: Session,
+    AppState { db, events, .. }: AppState,
+    ValidJson(payload): ValidJson<BulkPaymentPayload>,
+) -> Result<Json<BulkReport>> {
+    let output = controllers::bulk::pay(session, payload.order_ids, payload.method, db).await?;
+    for id in output.succeeded_ids() {
+        events.publish(Event::order(EventKind::OrderPaid, id));
+        events.publish(Event::order(EventKind::OrderUpdated, id));
+    }
+    Ok(Json(output))
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/order/bulk/unpay",
+    operation_id = "admin_bulk_unpay",
+    tag = "admin",
+    request_body = BulkPaymentPayload,
+    responses(
+        (status = 200, body = BulkReport),
+        (status = 422, body = ValidationReport),
+    )
+)]
+async fn bulk_unpay(
+    session: Session,
+    AppState { db, events, .. }: AppState,
+    ValidJson(payload): ValidJson<BulkPaymentPayload>,
+) -> Result<Json<BulkReport>> {
+    let output = controllers::bulk::unpay(session, payload.order_ids, payload.method, db).await?;
+    for id in output.succeeded_ids() {
+        events.publish(Event::order(EventKind::OrderPaid, id));
+        events.publish(Event::order(EventKind::OrderUpdated, id));
+    }
+    Ok(Json(output))
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/order/{id}/payments",
+    operation_id = "admin_payment_list",
+    tag = "admin",
+    params(("id" = i32, Path)),
+    responses((status = 200, body = Vec<Payment>))
+)]
+async fn payment_list(
     session: Session,
     AppState { db, .. }: AppState,
     Path(order_id): Path<i32>,
-    Json(payload): Json<PayPayload>,
-) -> Result<()> {
-    controllers::admin::order::pay(session, order_id, payload.paid, db).await?;
-    Ok(())
+) -> Result<Json<Vec<Payment>>> {
+    let out = controllers::admin::payment::list(session, order_id, db).await?;
+    Ok(Json(out))
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/order/{id}/balance",
+    operation_id = "admin_payment_balance",
+    tag = "admin",
+    params(("id" = i32, Path)),
+    responses((status = 200, body = OrderBalance))
+)]
+async fn payment_balance(
+    session: Session,
+    AppState { db, .. }: AppState,
+    Path(order_id): Path<i32>,
+) -> Result<Json<OrderBalance>> {
+    let out = controllers::admin::payment::balance(session, order_id, db).await?;
+    Ok(Json(out))
 }
 
+#[utoipa::path(
+    get,
+    path = "/admin/order",
+    operation_id = "admin_order_list",
+    tag = "admin",
+    responses((status = 200, body = Vec<OrderResponseFull>))
+)]
 async fn list(
     session: Session,
     AppState { db, .. }: AppState,
@@ -40,3 +261,481 @@ async fn list(
     let out = controllers::admin::order::list(session, db).await?;
     Ok(Json(out))
 }
+
+// orders of the duplicates move to the customer in the path
+#[derive(Deserialize, Validate, ToSchema)]
+#[schema(as = CustomerMergePayload)]
+struct MergePayload {
+    #[validate(length(min = 1, max = controllers::bulk::LIMIT))]
+    duplicate_ids: Vec<i32>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/customers/{id}/merge",
+    operation_id = "admin_customer_merge",
+    tag = "admin",
+    params(("id" = i32, Path)),
+    request_body = MergePayload,
+    responses(
+        (status = 200),
+        (status = 422, body = ValidationReport),
+    )
+)]
+async fn customer_merge(
+    session: Session,
+    AppState { db, events, .. }: AppState,
+    Path(customer_id): Path<i32>,
+    ValidJson(payload): ValidJson<MergePayload>,
+) -> Result<()> {
+    let moved =
+        controllers::admin::customer::merge(session, customer_id, payload.duplicate_ids, db)
+            .await?;
+    for id in moved {
+        events.publish(Event::order(EventKind::OrderUpdated, id));
+    }
+    Ok(())
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/report/quantities",
+    operation_id = "admin_report_quantities",
+    tag = "admin",
+    params(OrderListParams),
+    responses((status = 200, body = Vec<QuantityTotal>))
+)]
+async fn report_quantities(
+    session: Session,
+    AppState { db, shop, .. }: AppState,
+    Query(params): Query<OrderListParams>,
+) -> Result<Json<Vec<QuantityTotal>>> {
+    let out = controllers::admin::report::quantities(session, params, shop.timezone, db).await?;
+    Ok(Json(out))
+}
+
+#[derive(Deserialize, Validate, ToSchema)]
+struct ProductCreatePayload {
+    #[serde(deserialize_with = "trimmed")]
+    #[validate(length(min = 1, max = 200))]
+    name: String,
+    default_unit: Unit,
+    #[validate(custom(function = "non_negative"))]
+    unit_price: Money,
+    #[serde(default, deserialize_with = "trimmed_option")]
+    #[validate(length(max = 100))]
+    category: Option<String>,
+    #[validate(range(min = 0))]
+    low_stock_threshold: Option<i64>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/products",
+    operation_id = "admin_product_create",
+    tag = "admin",
+    request_body = ProductCreatePayload,
+    responses(
+        (status = 200, body = Created),
+        (status = 422, body = ValidationReport),
+    )
+)]
+async fn product_create(
+    session: Session,
+    AppState { db, .. }: AppState,
+    ValidJson(payload): ValidJson<ProductCreatePayload>,
+) -> Result<Json<Created>> {
+    let product_fc = ProductForCreate {
+        name: payload.name,
+        default_unit: payload.default_unit,
+        unit_price: payload.unit_price,
+        category: payload.category,
+        low_stock_threshold: payload.low_stock_threshold,
+    };
+    let id = controllers::admin::product::create(session, product_fc, db).await?;
+    Ok(Json(Created { id }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/products",
+    operation_id = "admin_product_list",
+    tag = "admin",
+    responses((status = 200, body = Vec<Product>))
+)]
+async fn product_list(
+    session: Session,
+    AppState { db, .. }: AppState,
+) -> Result<Json<Vec<Product>>> {
+    let out = controllers::admin::product::list(session, db).await?;
+    Ok(Json(out))
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/products/{id}",
+    operation_id = "admin_product_read",
+    tag = "admin",
+    params(("id" = i32, Path)),
+    responses((status = 200, body = Product))
+)]
+async fn product_read(
+    session: Session,
+    AppState { db, .. }: AppState,
+    Path(product_id): Path<i32>,
+) -> Result<Json<Product>> {
+    let out = controllers::admin::product::read(session, product_id, db).await?;
+    Ok(Json(out))
+}
+
+#[derive(Deserialize, Validate, ToSchema)]
+struct ProductUpdatePayload {
+    #[serde(default, deserialize_with = "trimmed_option")]
+    #[validate(length(min = 1, max = 200))]
+    name: Option<String>,
+    default_unit: Option<Unit>,
+    #[validate(custom(function = "non_negative"))]
+    unit_price: Option<Money>,
+    #[serde(default, deserialize_with = "trimmed_option")]
+    #[validate(length(max = 100))]
+    category: Option<String>,
+    active: Option<bool>,
+    #[validate(range(min = 0))]
+    low_stock_threshold: Option<i64>,
+}
+
+#[utoipa::path(
+    patch,
+    path = "/admin/products/{id}",
+    operation_id = "admin_product_update",
+    tag = "admin",
+    params(("id" = i32, Path)),
+    request_body = ProductUpdatePayload,
+    responses(
+        (status = 200),
+        (status = 422, body = ValidationReport),
+    )
+)]
+async fn product_update(
+    session: Session,
+    AppState { db, .. }: AppState,
+    Path(product_id): Path<i32>,
+    ValidJson(payload): ValidJson<ProductUpdatePayload>,
+) -> Result<()> {
+    let product_fu = ProductForUpdate {
+        name: payload.name,
+        default_unit: payload.default_unit,
+        unit_price: payload.unit_price,
+        category: payload.category,
+        active: payload.active,
+        low_stock_threshold: payload.low_stock_threshold,
+    };
+    controllers::admin::product::update(session, product_id, product_fu, db).await?;
+    Ok(())
+}
+
+#[utoipa::path(
+    delete,
+    path = "/admin/products/{id}",
+    operation_id = "admin_product_delete",
+    tag = "admin",
+    params(("id" = i32, Path)),
+    responses((status = 200))
+)]
+async fn product_delete(
+    session: Session,
+    AppState { db, .. }: AppState,
+    Path(product_id): Path<i32>,
+) -> Result<()> {
+    controllers::admin::product::delete(session, product_id, db).await?;
+    Ok(())
+}
+
+#[derive(Deserialize, Validate, ToSchema)]
+struct StockMovementPayload {
+    product_id: i32,
+    #[validate(custom(function = "non_zero_quantity"))]
+    quantity: Quantity,
+    #[serde(default, deserialize_with = "trimmed_option")]
+    #[validate(length(max = 500))]
+    reason: Option<String>,
+}
+
+impl From<StockMovementPayload> for StockMovementForCreate {
+    fn from(payload: StockMovementPayload) -> Self {
+        StockMovementForCreate {
+            product_id: payload.product_id,
+            quantity: payload.quantity,
+            reason: payload.reason,
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/stock/receipts",
+    operation_id = "admin_stock_receive",
+    tag = "admin",
+    request_body = StockMovementPayload,
+    responses(
+        (status = 200, body = Created),
+        (status = 422, body = ValidationReport),
+    )
+)]
+async fn stock_receive(
+    session: Session,
+    AppState { db, .. }: AppState,
+    ValidJson(payload): ValidJson<StockMovementPayload>,
+) -> Result<Json<Created>> {
+    let id = controllers::admin::stock::receive(session, payload.into(), db).await?;
+    Ok(Json(Created { id }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/stock/adjustments",
+    operation_id = "admin_stock_adjust",
+    tag = "admin",
+    request_body = StockMovementPayload,
+    responses(
+        (status = 200, body = Created),
+        (status = 422, body = ValidationReport),
+    )
+)]
+async fn stock_adjust(
+    session: Session,
+    AppState { db, .. }: AppState,
+    ValidJson(payload): ValidJson<StockMovementPayload>,
+) -> Result<Json<Created>> {
+    let id = controllers::admin::stock::adjust(session, payload.into(), db).await?;
+    Ok(Json(Created { id }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/stock",
+    operation_id = "admin_stock_levels",
+    tag = "admin",
+    responses((status = 200, body = Vec<StockLevel>))
+)]
+async fn stock_levels(
+    session: Session,
+    AppState { db, .. }: AppState,
+) -> Result<Json<Vec<StockLevel>>> {
+    let out = controllers::admin::stock::levels(session, db).await?;
+    Ok(Json(out))
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/stock/{product_id}/movements",
+    operation_id = "admin_stock_movements",
+    tag = "admin",
+    params(("product_id" = i32, Path)),
+    responses((status = 200, body = Vec<StockMovement>))
+)]
+async fn stock_movements(
+    session: Session,
+    AppState { db, .. }: AppState,
+    Path(product_id): Path<i32>,
+) -> Result<Json<Vec<StockMovement>>> {
+    let out = controllers::admin::stock::movements(session, product_id, db).await?;
+    Ok(Json(out))
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/report/low-stock",
+    operation_id = "admin_report_low_stock",
+    tag = "admin",
+    responses((status = 200, body = Vec<StockLevel>))
+)]
+async fn report_low_stock(
+    session: Session,
+    AppState { db, .. }: AppState,
+) -> Result<Json<Vec<StockLevel>>> {
+    let out = controllers::admin::stock::low(session, db).await?;
+    Ok(Json(out))
+}
+
+// buyer data is taken from the customer of the order when left out
+#[derive(Deserialize, Validate, ToSchema)]
+struct InvoicePayload {
+    #[serde(default, deserialize_with = "trimmed_option")]
+    #[validate(length(min = 1, max = 200))]
+    buyer_name: Option<String>,
+    #[serde(default, deserialize_with = "trimmed_option")]
+    #[validate(length(min = 1, max = 300))]
+    buyer_address: Option<String>,
+    #[serde(default, deserialize_with = "trimmed_option")]
+    #[validate(length(min = 1, max = 20))]
+    buyer_nip: Option<String>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/order/{id}/invoice",
+    operation_id = "admin_invoice_issue",
+    tag = "admin",
+    params(("id" = i32, Path)),
+    request_body = InvoicePayload,
+    responses(
+        (status = 200, body = Created),
+        (status = 409, description = "the order isn't paid or has an invoice already"),
+        (status = 422, body = ValidationReport),
+    )
+)]
+async fn invoice_issue(
+    session: Session,
+    AppState { db, shop, .. }: AppState,
+    Path(order_id): Path<i32>,
+    ValidJson(payload): ValidJson<InvoicePayload>,
+) -> Result<Json<Created>> {
+    let invoice = InvoiceForCreate {
+        buyer_name: payload.buyer_name,
+        buyer_address: payload.buyer_address,
+        buyer_nip: payload.buyer_nip,
+    };
+    let id = controllers::invoice::issue(session, order_id, shop, invoice, db).await?;
+    Ok(Json(Created { id }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/invoices",
+    operation_id = "admin_invoice_list",
+    tag = "admin",
+    responses((status = 200, body = Vec<Invoice>))
+)]
+async fn invoice_list(
+    session: Session,
+    AppState { db, .. }: AppState,
+) -> Result<Json<Vec<Invoice>>> {
+    let output = controllers::invoice::list(session, db).await?;
+    Ok(Json(output))
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/invoices/{id}",
+    operation_id = "admin_invoice_read",
+    tag = "admin",
+    params(("id" = i32, Path)),
+    responses((status = 200, body = InvoiceDetail))
+)]
+async fn invoice_read(
+    session: Session,
+    AppState { db, .. }: AppState,
+    Path(invoice_id): Path<i32>,
+) -> Result<Json<InvoiceDetail>> {
+    let output = controllers::invoice::read(session, invoice_id, db).await?;
+    Ok(Json(output))
+}
+
+// buyer data is taken from the corrected invoice when left out
+#[derive(Deserialize, Validate, ToSchema)]
+struct CorrectionPayload {
+    #[serde(deserialize_with = "trimmed")]
+    #[validate(length(min = 1, max = 500))]
+    reason: String,
+    #[serde(default, deserialize_with = "trimmed_option")]
+    #[validate(length(min = 1, max = 200))]
+    buyer_name: Option<String>,
+    #[serde(default, deserialize_with = "trimmed_option")]
+    #[validate(length(min = 1, max = 300))]
+    buyer_address: Option<String>,
+    #[serde(default, deserialize_with = "trimmed_option")]
+    #[validate(length(min = 1, max = 20))]
+    buyer_nip: Option<String>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/invoices/{id}/corrections",
+    operation_id = "admin_invoice_correct",
+    tag = "admin",
+    params(("id" = i32, Path)),
+    request_body = CorrectionPayload,
+    responses(
+        (status = 200, body = Created),
+        (status = 422, body = ValidationReport),
+    )
+)]
+async fn invoice_correct(
+    session: Session,
+    AppState { db, shop, .. }: AppState,
+    Path(invoice_id): Path<i32>,
+    ValidJson(payload): ValidJson<CorrectionPayload>,
+) -> Result<Json<Created>> {
+    let correction = CorrectionForCreate {
+        reason: payload.reason,
+        buyer_name: payload.buyer_name,
+        buyer_address: payload.buyer_address,
+        buyer_nip: payload.buyer_nip,
+    };
+    let id =
+        controllers::invoice::correct(session, invoice_id, shop.timezone, correction, db).await?;
+    Ok(Json(Created { id }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/invoices/{id}/invoice.pdf",
+    operation_id = "admin_invoice_pdf",
+    tag = "admin",
+    params(("id" = i32, Path)),
+    responses((status = 200, content_type = "application/pdf"))
+)]
+async fn invoice_pdf(
+    session: Session,
+    AppState { db, .. }: AppState,
+    Path(invoice_id): Path<i32>,
+) -> Result<impl IntoResponse> {
+    let output = controllers::invoice::pdf(session, invoice_id, db).await?;
+    let headers = [
+        (header::CONTENT_TYPE, "application/pdf".to_owned()),
+        (
+            header::CONTENT_DISPOSITION,
+            format!("inline; filename=\"faktura-{invoice_id}.pdf\""),
+        ),
+    ];
+    Ok((headers, output))
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/invoices/{id}/invoice.xml",
+    operation_id = "admin_invoice_xml",
+    tag = "admin",
+    params(("id" = i32, Path)),
+    responses((status = 200, content_type = "application/xml"))
+)]
+async fn invoice_xml(
+    session: Session,
+    AppState { db, .. }: AppState,
+    Path(invoice_id): Path<i32>,
+) -> Result<impl IntoResponse> {
+    let output = controllers::invoice::xml(session, invoice_id, db).await?;
+    let headers = [
+        (header::CONTENT_TYPE, "application/xml".to_owned()),
+        (
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"faktura-{invoice_id}.xml\""),
+        ),
+    ];
+    Ok((headers, output))
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/invoices/schema.xsd",
+    operation_id = "admin_invoice_schema",
+    tag = "admin",
+    responses((status = 200, content_type = "application/xml"))
+)]
+async fn invoice_schema() -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, "application/xml")],
+        controllers::invoice::SCHEMA,
+    )
+}
@@ -0,0 +1,175 @@
+use axum::{
+    extract::{Path, Query},
+    routing::get,
+    Json, Router,
+};
+use serde::Deserialize;
+use tracing::trace;
+use utoipa::{IntoParams, OpenApi, ToSchema};
+use validator::Validate;
+
+use super::Created;
+use crate::{
+    controllers,
+    models::customer::{
+        CustomerDetail, CustomerForCreate, CustomerForUpdate, CustomerResponseBasic,
+    },
+    session::Session,
+    validation::{trimmed, trimmed_option, ValidJson, ValidationReport},
+    AppState, Result,
+};
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/customers", get(search).post(create))
+        .route("/customers/:id", get(read).patch(update))
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(search, create, read, update))]
+pub struct Api;
+
+// GET /customers?q=kowalski
+// POST /customers
+// GET /customers/:id
+// PATCH /customers/:id
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct SearchParams {
+    q: Option<String>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/customers",
+    operation_id = "customer_search",
+    tag = "customers",
+    params(SearchParams),
+    responses((status = 200, body = Vec<CustomerResponseBasic>))
+)]
+async fn search(
+    session: Session,
+    AppState { db, .. }: AppState,
+    Query(params): Query<SearchParams>,
+) -> Result<Json<Vec<CustomerResponseBasic>>> {
+    trace!(" -- HANDLER GET /customers");
+    let output = controllers::customer::search(session, params.q.unwrap_or_default(), db).await?;
+    Ok(Json(output))
+}
+
+#[derive(Deserialize, Validate, ToSchema)]
+#[schema(as = CustomerCreatePayload)]
+struct CreatePayload {
+    #[serde(deserialize_with = "trimmed")]
+    #[validate(length(min = 1, max = 200))]
+    name: String,
+    #[serde(default, deserialize_with = "trimmed_option")]
+    #[validate(length(max = 30))]
+    phone: Option<String>,
+    #[serde(default, deserialize_with = "trimmed_option")]
+    #[validate(email)]
+    email: Option<String>,
+    #[serde(default, deserialize_with = "trimmed_option")]
+    #[validate(length(max = 20))]
+    nip: Option<String>,
+    #[serde(default, deserialize_with = "trimmed_option")]
+    #[validate(length(max = 2000))]
+    notes: Option<String>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/customers",
+    operation_id = "customer_create",
+    tag = "customers",
+    request_body = CreatePayload,
+    responses(
+        (status = 200, body = Created),
+        (status = 422, body = ValidationReport),
+    )
+)]
+async fn create(
+    session: Session,
+    AppState { db, .. }: AppState,
+    ValidJson(payload): ValidJson<CreatePayload>,
+) -> Result<Json<Created>> {
+    trace!(" -- HANDLER POST /customers");
+    let customer_fc = CustomerForCreate {
+        name: payload.name,
+        phone: payload.phone,
+        email: payload.email,
+        nip: payload.nip,
+        notes: payload.notes,
+    };
+    let id = controllers::customer::create(session, customer_fc, db).await?;
+    Ok(Json(Created { id }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/customers/{id}",
+    operation_id = "customer_read",
+    tag = "customers",
+    params(("id" = i32, Path)),
+    responses((status = 200, body = CustomerDetail))
+)]
+async fn read(
+    session: Session,
+    AppState { db, .. }: AppState,
+    Path(id): Path<i32>,
+) -> Result<Json<CustomerDetail>> {
+    trace!(" -- HANDLER GET /customers/{}", id);
+    let output = controllers::customer::read(session, id, db).await?;
+    Ok(Json(output))
+}
+
+#[derive(Deserialize, Validate, ToSchema)]
+#[schema(as = CustomerUpdatePayload)]
+struct UpdatePayload {
+    #[serde(default, deserialize_with = "trimmed_option")]
+    #[validate(length(min = 1, max = 200))]
+    name: Option<String>,
+    #[serde(default, deserialize_with = "trimmed_option")]
+    #[validate(length(max = 30))]
+    phone: Option<String>,
+    #[serde(default, deserialize_with = "trimmed_option")]
+    #[validate(email)]
+    email: Option<String>,
+    #[serde(default, deserialize_with = "trimmed_option")]
+    #[validate(length(max = 20))]
+    nip: Option<String>,
+    #[serde(default, deserialize_with = "trimmed_option")]
+    #[validate(length(max = 2000))]
+    notes: Option<String>,
+}
+
+#[utoipa::path(
+    patch,
+    path = "/customers/{id}",
+    operation_id = "customer_update",
+    tag = "customers",
+    params(("id" = i32, Path)),
+    request_body = UpdatePayload,
+    responses(
+        (status = 200),
+        (status = 422, body = ValidationReport),
+    )
+)]
+async fn update(
+    session: Session,
+    AppState { db, .. }: AppState,
+    Path(id): Path<i32>,
+    ValidJson(payload): ValidJson<UpdatePayload>,
+) -> Result<()> {
+    trace!(" -- HANDLER PATCH /customers/{}", id);
+    let customer_fu = CustomerForUpdate {
+        name: payload.name,
+        phone: payload.phone,
+        email: payload.email,
+        nip: payload.nip,
+        notes: payload.notes,
+    };
+    controllers::customer::update(session, id, customer_fu, db).await?;
+    Ok(())
+}
@@ -0,0 +1,48 @@
+use std::convert::Infallible;
+
+use axum::{
+    response::sse::{self, KeepAlive, Sse},
+    routing::get,
+    Router,
+};
+use futures_util::{Stream, StreamExt};
+use tracing::trace;
+use utoipa::OpenApi;
+
+use crate::{models::event::Event, session::Session, AppState};
+
+pub fn routes() -> Router<AppState> {
+    Router::new().route("/events", get(events))
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(events))]
+pub struct Api;
+
+// GET /events
+// server-sent events, one json Event per message
+
+#[utoipa::path(
+    get,
+    path = "/events",
+    operation_id = "events",
+    tag = "events",
+    responses(
+        (
+            status = 200,
+            description = "one json Event per message",
+            content_type = "text/event-stream",
+            body = Event
+        ),
+    )
+)]
+async fn events(
+    session: Session,
+    AppState { events, .. }: AppState,
+) -> Sse<impl Stream<Item = Result<sse::Event, Infallible>>> {
+    trace!(" -- HANDLER GET /events");
+    let stream = events
+        .stream(session.privileges())
+        .filter_map(|event| async move { sse::Event::default().json_data(event).ok().map(Ok) });
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
@@ -0,0 +1,111 @@
+use axum::{extract::State, http::StatusCode, routing::get, Json, Router};
+use serde::Serialize;
+use sqlx::migrate::Migrate;
+use tracing::trace;
+use utoipa::{OpenApi, ToSchema};
+
+use crate::{AppState, Db, MIGRATOR};
+
+// GET /health/live
+// GET /health/ready
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/health/live", get(live))
+        .route("/health/ready", get(ready))
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(live, ready))]
+pub struct Api;
+
+// GET /metrics, outside of /api
+pub fn metrics_routes() -> Router<AppState> {
+    Router::new().route("/metrics", get(metrics))
+}
+
+// the process answers
+#[utoipa::path(
+    get,
+    path = "/health/live",
+    operation_id = "health_live",
+    tag = "health",
+    security(()),
+    responses((status = 200, body = String, example = "ok"))
+)]
+async fn live() -> &'static str {
+    "ok"
+}
+
+#[derive(Serialize, Debug, PartialEq, ToSchema)]
+struct Readiness {
+    database: bool,
+    migration: Option<i64>, // last applied
+    expected_migration: i64,
+}
+
+impl Readiness {
+    fn ready(&self) -> bool {
+        self.database && self.migration == Some(self.expected_migration)
+    }
+}
+
+// the database answers and is migrated to what this build expects
+#[utoipa::path(
+    get,
+    path = "/health/ready",
+    operation_id = "health_ready",
+    tag = "health",
+    security(()),
+    responses(
+        (status = 200, body = Readiness),
+        (status = 503, description = "no database or not migrated yet", body = Readiness),
+    )
+)]
+async fn ready(State(AppState { db, .. }): State<AppState>) -> (StatusCode, Json<Readiness>) {
+    trace!(" -- HANDLER GET /health/ready");
+    let readiness = readiness(&db).await;
+    let status = match readiness.ready() {
+        true => StatusCode::OK,
+        false => StatusCode::SERVICE_UNAVAILABLE,
+    };
+    (status, Json(readiness))
+}
+
+async fn readiness(db: &Db) -> Readiness {
+    let expected_migration = MIGRATOR.iter().map(|m| m.version).max().unwrap_or(0);
+    let migration = match db.acquire().await {
+        Ok(mut conn) => match conn.list_applied_migrations().await {
+            Ok(applied) => Ok(applied.iter().map(|a| a.version).max()),
+            Err(_) => Err(()),
+        },
+        Err(_) => Err(()),
+    };
+    Readiness {
+        database: migration.is_ok(),
+        migration: migration.unwrap_or(None),
+        expected_migration,
+    }
+}
+
+async fn metrics(State(AppState { db, metrics, .. }): State<AppState>) -> String {
+    metrics.render(&db)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[sqlx::test]
+    async fn health_ready_migrated(pool: Db) {
+        let readiness = readiness(&pool).await;
+        assert!(readiness.ready(), "{readiness:?}");
+    }
+
+    #[sqlx::test(migrations = false)]
+    async fn health_not_ready_unmigrated(pool: Db) {
+        let readiness = readiness(&pool).await;
+        // no _sqlx_migrations table yet
+        assert!(!readiness.ready());
+        assert_eq!(readiness.migration, None);
+    }
+}
@@ -1,59 +1,344 @@
 use axum::{
-    extract::Path,
+    extract::{Path, Query},
+    http::HeaderMap,
+    response::Response,
     routing::{delete, post},
     Json, Router,
 };
 use serde::Deserialize;
-use serde_json::{json, Value};
 use std::convert::Into;
 use tracing::trace;
+use utoipa::{OpenApi, ToSchema};
+use validator::Validate;
 
+use super::{expected_version, or_current_order, Created, VersionParams};
 use crate::{
     controllers,
-    models::item::{ItemForCreate, ItemForUpdate},
+    models::{
+        bulk::BulkReport,
+        event::{Event, EventKind},
+        item::{ItemForCreate, ItemForUpdate},
+        money::Money,
+        order::OrderResponseBasic,
+        tax::{Discount, VatRate},
+    },
     session::Session,
+    validation::{
+        non_negative, nullable, trimmed, trimmed_option, valid_discount, valid_quantity, ValidJson,
+        ValidationReport,
+    },
     AppState, Result,
 };
 
 pub fn routes() -> Router<AppState> {
     Router::new()
         .route("/orders/:order_id/items", post(handler_create))
-        .route("/orders/:order_id/items/:item_id", delete(handler_delete))
+        .route("/orders/:order_id/items/bulk", post(handler_create_bulk))
+        .route("/orders/:order_id/items/check", post(handler_check_all))
+        .route("/orders/:order_id/items/reorder", post(handler_reorder))
+        .route(
+            "/orders/:order_id/items/:item_id",
+            delete(handler_delete).patch(handler_update),
+        )
 }
 
-#[derive(Deserialize)]
+#[derive(OpenApi)]
+#[openapi(paths(
+    handler_create,
+    handler_create_bulk,
+    handler_check_all,
+    handler_reorder,
+    handler_update,
+    handler_delete
+))]
+pub struct Api;
+
+#[derive(Deserialize, Validate, ToSchema)]
+#[schema(as = ItemCreatePayload)]
 struct CreatePayload {
+    #[serde(deserialize_with = "trimmed")]
+    #[validate(length(min = 1, max = 50), custom(function = "valid_quantity"))]
     quantity: String,
+    #[serde(deserialize_with = "trimmed")]
+    #[validate(length(min = 1, max = 200))]
     name: String,
-    value: i32,
+    #[validate(custom(function = "non_negative"))]
+    value: Money,
+    #[serde(default, deserialize_with = "trimmed_option")]
+    #[validate(length(max = 1000))]
     additional_info: Option<String>,
+    product_id: Option<i32>,
+    #[serde(default)]
+    vat_rate: VatRate,
+    #[validate(custom(function = "valid_discount"))]
+    discount: Option<Discount>,
+}
+
+impl From<CreatePayload> for ItemForCreate {
+    fn from(payload: CreatePayload) -> Self {
+        ItemForCreate {
+            quantity: payload.quantity,
+            name: payload.name,
+            value: payload.value,
+            additional_info: payload.additional_info,
+            product_id: payload.product_id,
+            vat_rate: payload.vat_rate,
+            discount: payload.discount,
+        }
+    }
 }
+
+#[utoipa::path(
+    post,
+    path = "/orders/{order_id}/items",
+    operation_id = "item_create",
+    tag = "items",
+    params(("order_id" = i32, Path)),
+    request_body = CreatePayload,
+    responses(
+        (status = 200, body = Created),
+        (status = 422, body = ValidationReport),
+    )
+)]
 async fn handler_create(
     session: Session,
-    AppState { db, .. }: AppState,
+    AppState { db, events, .. }: AppState,
     Path(order_id): Path<i32>,
-    payload: Json<CreatePayload>,
-) -> Result<Json<Value>> {
+    ValidJson(payload): ValidJson<CreatePayload>,
+) -> Result<Json<Created>> {
     trace!(" -- HANDLER CREATE /orders/{order_id}/items");
-    let item_fc = ItemForCreate {
-        quantity: payload.quantity.clone(),
-        name: payload.name.clone(),
+    let (id, status_moved) =
+        controllers::item::create(session, payload.into(), order_id, db).await?;
+    events.publish(Event::item(EventKind::ItemCreated, order_id, id));
+    if status_moved {
+        events.publish(Event::order(EventKind::OrderUpdated, order_id));
+    }
+    Ok(Json(Created { id }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/orders/{order_id}/items/bulk",
+    operation_id = "item_create_bulk",
+    tag = "items",
+    params(("order_id" = i32, Path)),
+    request_body = Vec<CreatePayload>,
+    responses(
+        (status = 200, body = BulkReport),
+        (status = 422, body = ValidationReport),
+    )
+)]
+async fn handler_create_bulk(
+    session: Session,
+    AppState { db, events, .. }: AppState,
+    Path(order_id): Path<i32>,
+    ValidJson(payload): ValidJson<Vec<CreatePayload>>,
+) -> Result<Json<BulkReport>> {
+    trace!(" -- HANDLER POST /orders/{order_id}/items/bulk");
+    let items = payload.into_iter().map(Into::into).collect();
+    let (output, status_moved) =
+        controllers::bulk::create_items(session, order_id, items, db).await?;
+    for id in output.succeeded_ids() {
+        events.publish(Event::item(EventKind::ItemCreated, order_id, id));
+    }
+    if status_moved {
+        events.publish(Event::order(EventKind::OrderUpdated, order_id));
+    }
+    Ok(Json(output))
+}
+
+#[derive(Deserialize, Validate, ToSchema)]
+#[schema(as = ItemCheckPayload)]
+struct CheckPayload {
+    checked: bool,
+}
+
+// checks or unchecks every item of the order
+#[utoipa::path(
+    post,
+    path = "/orders/{order_id}/items/check",
+    operation_id = "item_check_all",
+    tag = "items",
+    params(("order_id" = i32, Path)),
+    request_body = CheckPayload,
+    responses(
+        (status = 200, body = BulkReport),
+        (status = 422, body = ValidationReport),
+    )
+)]
+async fn handler_check_all(
+    session: Session,
+    AppState { db, events, .. }: AppState,
+    Path(order_id): Path<i32>,
+    ValidJson(payload): ValidJson<CheckPayload>,
+) -> Result<Json<BulkReport>> {
+    trace!(" -- HANDLER POST /orders/{order_id}/items/check");
+    let (output, status_moved) =
+        controllers::bulk::check_items(session, order_id, payload.checked, db).await?;
+    for id in output.succeeded_ids() {
+        events.publish(Event::item(EventKind::ItemUpdated, order_id, id));
+    }
+    if status_moved {
+        events.publish(Event::order(EventKind::OrderUpdated, order_id));
+    }
+    Ok(Json(output))
+}
+
+#[derive(Deserialize, Validate, ToSchema)]
+#[schema(as = ItemUpdatePayload)]
+struct UpdatePayload {
+    #[serde(default, deserialize_with = "trimmed_option")]
+    #[validate(length(min = 1, max = 50), custom(function = "valid_quantity"))]
+    quantity: Option<String>,
+    #[serde(default, deserialize_with = "trimmed_option")]
+    #[validate(length(min = 1, max = 200))]
+    name: Option<String>,
+    #[validate(custom(function = "non_negative"))]
+    value: Option<Money>,
+    #[serde(default, deserialize_with = "trimmed_option")]
+    #[validate(length(max = 1000))]
+    additional_info: Option<String>,
+    checked: Option<bool>,
+    vat_rate: Option<VatRate>,
+    // null removes the discount
+    #[serde(default, deserialize_with = "nullable")]
+    #[schema(value_type = Option<Discount>)]
+    #[validate(custom(function = "valid_discount"))]
+    discount: Option<Option<Discount>>,
+    version: Option<i32>,
+}
+
+// If-Match or version in the payload, a conflict answers with the current order
+#[utoipa::path(
+    patch,
+    path = "/orders/{order_id}/items/{item_id}",
+    operation_id = "item_update",
+    tag = "items",
+    params(
+        ("order_id" = i32, Path),
+        ("item_id" = i32, Path),
+        (
+            "If-Match" = Option<String>,
+            Header,
+            description = "version the client read, unless in the payload"
+        ),
+    ),
+    request_body = UpdatePayload,
+    responses(
+        (status = 200),
+        (
+            status = 409,
+            description = "changed since it was read, the current order",
+            body = OrderResponseBasic
+        ),
+        (status = 422, body = ValidationReport),
+        (status = 428, description = "no version given"),
+    )
+)]
+async fn handler_update(
+    session: Session,
+    AppState { db, events, .. }: AppState,
+    Path((order_id, item_id)): Path<(i32, i32)>,
+    headers: HeaderMap,
+    ValidJson(payload): ValidJson<UpdatePayload>,
+) -> Result<Response> {
+    trace!(" -- HANDLER PATCH /orders/{order_id}/items/{item_id}");
+    controllers::item::ensure_in_order(&db, order_id, item_id).await?;
+    let item_fu = ItemForUpdate {
+        quantity: payload.quantity,
+        name: payload.name,
         value: payload.value,
-        additional_info: payload.additional_info.clone(),
+        additional_info: payload.additional_info,
+        checked: payload.checked,
+        vat_rate: payload.vat_rate,
+        discount: payload.discount,
+        version: Some(expected_version(&headers, payload.version)?),
     };
-    let output = controllers::item::create(session, item_fc, order_id, db).await?;
-    let json = json!({
-        "id": output
-    });
-    Ok(Json(json))
+    let result = controllers::item::update(session.clone(), item_id, item_fu, db.clone()).await;
+    if let Ok(status_moved) = result {
+        events.publish(Event::item(EventKind::ItemUpdated, order_id, item_id));
+        if status_moved {
+            events.publish(Event::order(EventKind::OrderUpdated, order_id));
+        }
+    }
+    or_current_order(result.map(|_| ()), session, order_id, db).await
 }
 
+// If-Match or ?version=
+#[utoipa::path(
+    delete,
+    path = "/orders/{order_id}/items/{item_id}",
+    operation_id = "item_delete",
+    tag = "items",
+    params(
+        ("order_id" = i32, Path),
+        ("item_id" = i32, Path),
+        (
+            "If-Match" = Option<String>,
+            Header,
+            description = "version the client read, unless in ?version="
+        ),
+        VersionParams,
+    ),
+    responses(
+        (status = 200),
+        (
+            status = 409,
+            description = "changed since it was read, the current order",
+            body = OrderResponseBasic
+        ),
+        (status = 428, description = "no version given"),
+    )
+)]
 async fn handler_delete(
     session: Session,
-    AppState { db, .. }: AppState,
+    AppState { db, events, .. }: AppState,
     Path((order_id, item_id)): Path<(i32, i32)>,
-) -> Result<()> {
+    headers: HeaderMap,
+    Query(params): Query<VersionParams>,
+) -> Result<Response> {
     trace!(" -- HANDLER DELETE /orders/{order_id}/items/{item_id}");
-    controllers::item::delete(session, item_id, db).await?;
+    controllers::item::ensure_in_order(&db, order_id, item_id).await?;
+    let version = expected_version(&headers, params.version)?;
+    let result =
+        controllers::item::delete(session.clone(), item_id, Some(version), db.clone()).await;
+    if let Ok(status_moved) = result {
+        events.publish(Event::item(EventKind::ItemDeleted, order_id, item_id));
+        if status_moved {
+            events.publish(Event::order(EventKind::OrderUpdated, order_id));
+        }
+    }
+    or_current_order(result.map(|_| ()), session, order_id, db).await
+}
+
+// every item of the order that isn't deleted, in the new order
+#[derive(Deserialize, Validate, ToSchema)]
+#[schema(as = ItemReorderPayload)]
+struct ReorderPayload {
+    #[validate(length(min = 1, max = controllers::bulk::LIMIT))]
+    item_ids: Vec<i32>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/orders/{order_id}/items/reorder",
+    operation_id = "item_reorder",
+    tag = "items",
+    params(("order_id" = i32, Path)),
+    request_body = ReorderPayload,
+    responses(
+        (status = 200),
+        (status = 422, body = ValidationReport),
+    )
+)]
+async fn handler_reorder(
+    session: Session,
+    AppState { db, events, .. }: AppState,
+    Path(order_id): Path<i32>,
+    ValidJson(payload): ValidJson<ReorderPayload>,
+) -> Result<()> {
+    trace!(" -- HANDLER POST /orders/{order_id}/items/reorder");
+    controllers::item::reorder(session, order_id, payload.item_ids, db).await?;
+    events.publish(Event::order(EventKind::OrderUpdated, order_id));
     Ok(())
 }
@@ -7,14 +7,16 @@ use jwt_simple::{
     claims::Claims,
     prelude::{Duration, MACLike},
 };
-use serde::Deserialize;
-use serde_json::{json, Value};
-use tower_cookies::{Cookie, Cookies};
-use tracing::trace;
+use serde::{Deserialize, Serialize};
+use tower_cookies::{cookie::time, Cookie, Cookies};
+use tracing::{error, trace};
+use utoipa::{OpenApi, ToSchema};
 
 use crate::{
-    error::Result, models::user::User, session::Session, AppState, Error, JWTClaims,
-    AUTH_COOKIE_KEY,
+    error::Result,
+    models::user::{hash_password, Privileges, User},
+    session::Session,
+    AppState, Error, JWTClaims, AUTH_COOKIE_KEY,
 };
 
 pub fn routes() -> Router<AppState> {
@@ -23,30 +25,52 @@ pub fn routes() -> Router<AppState> {
         .route("/token", get(token))
 }
 
+#[derive(OpenApi)]
+#[openapi(paths(login, token))]
+pub struct Api;
+
+#[derive(Serialize, ToSchema)]
+struct TokenInfo {
+    privileges: Privileges,
+}
+
 // just verify the token
-async fn token(session: Session) -> Result<Json<Value>> {
+#[utoipa::path(
+    get,
+    path = "/token",
+    operation_id = "token",
+    tag = "login",
+    responses((status = 200, body = TokenInfo))
+)]
+async fn token(session: Session) -> Result<Json<TokenInfo>> {
     trace!(" -- HANDLER GET /token");
-    Ok(Json(json!({
-        "privileges": session.privileges()
-    })))
+    Ok(Json(TokenInfo {
+        privileges: session.privileges(),
+    }))
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
+#[schema(as = LoginPayload)]
 struct LoginPaylod {
     login: String,
     password: String,
 }
 
+#[utoipa::path(
+    post,
+    path = "/login",
+    operation_id = "login",
+    tag = "login",
+    security(()),
+    request_body = LoginPaylod,
+    responses((status = 200, description = "the token is set in the AUTH_TOKEN cookie"))
+)]
 async fn login(
     State(state): State<AppState>,
     cookies: Cookies,
     payload: Json<LoginPaylod>,
 ) -> Result<()> {
-    trace!(
-        " -- HANDLER POST /login ({} {})",
-        &payload.login,
-        &payload.password
-    );
+    trace!(" -- HANDLER POST /login ({})", &payload.login);
 
     let output: User = sqlx::query_as("SELECT * FROM users WHERE name=$1")
         .bind(&payload.login)
@@ -54,24 +78,41 @@ async fn login(
         .await
         .map_err(|_| Error::LoginDoesntExist)?;
 
-    // TODO: password hashing (not really necessary)
-    if output.password != payload.password {
+    if !output.password_matches(&payload.password) {
         return Err(Error::LoginBadPassword);
     }
+    // users from before the hashing keep their password, stored hashed from now on
+    if !output.password_is_hashed() {
+        match hash_password(&payload.password) {
+            Ok(hash) => {
+                sqlx::query("UPDATE users SET password=$1 WHERE id=$2")
+                    .bind(hash)
+                    .bind(output.id)
+                    .execute(&state.db)
+                    .await?;
+            }
+            Err(err) => error!("hashing the password of {}: {err}", output.name),
+        }
+    }
 
     let claims_content = JWTClaims {
         id: output.id,
         privileges: output.privileges,
     };
 
-    let claims = Claims::with_custom_claims(claims_content, Duration::from_hours(2));
+    let lifetime = state.auth.token_lifetime_hours;
+    let claims = Claims::with_custom_claims(claims_content, Duration::from_hours(lifetime));
     let token = state
         .jwt_key
         .authenticate(claims)
         .map_err(|_| Error::LoginFailedToGenerateToken)?;
 
-    // create cookie
-    cookies.add(Cookie::new(AUTH_COOKIE_KEY, token));
+    // create cookie, expiring with the token
+    let cookie = Cookie::build((AUTH_COOKIE_KEY, token))
+        .secure(state.auth.cookie_secure)
+        .same_site(state.auth.cookie_same_site)
+        .max_age(time::Duration::hours(lifetime as i64));
+    cookies.add(cookie.into());
 
     Ok(())
 }
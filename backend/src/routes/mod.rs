@@ -1,18 +1,110 @@
-use axum::Router;
+use axum::{
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
 
-use crate::AppState;
+use crate::{controllers, session::Session, AppState, Db, Error, Result};
 
 mod admin;
+mod customer;
+mod event;
+mod health;
 mod item;
 mod login;
+mod openapi;
 mod order;
 mod ping;
+mod product;
+mod template;
+
+pub use health::metrics_routes;
 
 pub fn routes() -> Router<AppState> {
     Router::new()
         .merge(ping::routes())
+        .merge(health::routes())
         .merge(login::routes())
         .merge(order::routes())
         .merge(admin::routes())
         .merge(item::routes())
+        .merge(product::routes())
+        .merge(customer::routes())
+        .merge(template::routes())
+        .merge(event::routes())
+        .merge(openapi::routes())
+}
+
+// answer to creating something
+#[derive(Serialize, ToSchema)]
+struct Created {
+    id: i32,
+}
+
+// DELETE requests without If-Match send the version as ?version=
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct VersionParams {
+    version: Option<i32>,
+}
+
+// changes of orders and items carry the version the client read, in If-Match ("3") or in the
+// payload. If-Match compares strongly, a weak W/"3" never matches
+fn expected_version(headers: &HeaderMap, field: Option<i32>) -> Result<i32> {
+    let Some(if_match) = headers.get(header::IF_MATCH) else {
+        return field.ok_or(Error::VersionMissing);
+    };
+    let if_match = if_match.to_str().map_err(|_| Error::VersionMissing)?.trim();
+    if if_match.starts_with("W/") {
+        return Err(Error::VersionMissing);
+    }
+    if_match
+        .trim_matches('"')
+        .parse()
+        .map_err(|_| Error::VersionMissing)
+}
+
+// on a version conflict the client gets the current order, to redo its change on top of it
+async fn or_current_order(
+    result: Result<()>,
+    session: Session,
+    order_id: i32,
+    db: Db,
+) -> Result<Response> {
+    match result {
+        Err(Error::VersionConflict { .. }) => {
+            let current = controllers::order::read(session, order_id, db).await?;
+            Ok((StatusCode::CONFLICT, Json(current)).into_response())
+        }
+        result => result.map(IntoResponse::into_response),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::HeaderValue;
+
+    use super::*;
+
+    #[test]
+    fn routes_expected_version() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(expected_version(&headers, Some(2)), Ok(2));
+        assert_eq!(expected_version(&headers, None), Err(Error::VersionMissing));
+        for if_match in ["\"3\"", "3"] {
+            headers.insert(header::IF_MATCH, HeaderValue::from_static(if_match));
+            // the header wins over the payload
+            assert_eq!(expected_version(&headers, Some(2)), Ok(3), "{if_match}");
+        }
+        for if_match in ["*", "W/\"3\""] {
+            headers.insert(header::IF_MATCH, HeaderValue::from_static(if_match));
+            assert_eq!(
+                expected_version(&headers, Some(2)),
+                Err(Error::VersionMissing),
+                "{if_match}"
+            );
+        }
+    }
 }
@@ -0,0 +1,214 @@
+// OpenAPI 3 document of the API, served at /api/openapi.json.
+// Handlers are described with #[utoipa::path] and listed in the Api next to the routes() of
+// their module. backend/openapi.json is the committed copy, openapi_snapshot fails when they
+// drift apart, UPDATE_OPENAPI=1 cargo test openapi writes it again.
+
+use axum::{routing::get, Json, Router};
+use utoipa::{
+    openapi::{
+        security::{ApiKey, ApiKeyValue, SecurityScheme},
+        OpenApi as Document,
+    },
+    Modify, OpenApi,
+};
+
+use super::{admin, customer, event, health, item, login, order, ping, product, template};
+use crate::{AppState, AUTH_COOKIE_KEY};
+
+pub fn routes() -> Router<AppState> {
+    Router::new().route("/openapi.json", get(openapi_json))
+}
+
+// GET /openapi.json
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "Paint shop orders",
+        description = "API of the backend, the frontend is served next to it"
+    ),
+    servers((url = "/api")),
+    paths(openapi_json),
+    modifiers(&AuthCookie),
+    security(("auth_cookie" = []))
+)]
+struct ApiDoc;
+
+// the token set by POST /login
+struct AuthCookie;
+
+impl Modify for AuthCookie {
+    fn modify(&self, openapi: &mut Document) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "auth_cookie",
+            SecurityScheme::ApiKey(ApiKey::Cookie(ApiKeyValue::new(AUTH_COOKIE_KEY))),
+        );
+    }
+}
+
+// the same modules as routes()
+pub fn document() -> Document {
+    let mut document = ApiDoc::openapi();
+    for api in [
+        ping::Api::openapi(),
+        health::Api::openapi(),
+        login::Api::openapi(),
+        order::Api::openapi(),
+        admin::Api::openapi(),
+        item::Api::openapi(),
+        product::Api::openapi(),
+        customer::Api::openapi(),
+        template::Api::openapi(),
+        event::Api::openapi(),
+    ] {
+        document.merge(api);
+    }
+    // Cargo.toml has none
+    document.info.license = None;
+    document
+}
+
+#[utoipa::path(
+    get,
+    path = "/openapi.json",
+    operation_id = "openapi",
+    tag = "health",
+    security(()),
+    responses((status = 200, description = "this document"))
+)]
+async fn openapi_json() -> Json<Document> {
+    Json(document())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeSet, HashSet};
+
+    use axum::{body::Body, extract::Request, http::StatusCode};
+    use jwt_simple::prelude::HS256Key;
+    use tower::ServiceExt;
+
+    use super::*;
+    use crate::{events::Events, metrics::Metrics, Db};
+
+    #[test]
+    fn openapi_snapshot() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/openapi.json");
+        let spec = document().to_pretty_json().unwrap() + "\n";
+        if std::env::var_os("UPDATE_OPENAPI").is_some() {
+            std::fs::write(path, &spec).unwrap();
+        }
+        let snapshot = std::fs::read_to_string(path).unwrap_or_default();
+        assert!(
+            spec == snapshot,
+            "backend/openapi.json differs from the routes, check the changes and run \
+             UPDATE_OPENAPI=1 cargo test openapi"
+        );
+    }
+
+    // every documented operation reaches a handler, whatever it answers without a login, and
+    // every route is documented
+    #[sqlx::test]
+    async fn openapi_paths_are_routed(pool: Db) {
+        let events = Events::default();
+        let state = AppState {
+            db: pool,
+            jwt_key: HS256Key::generate(),
+            shop: Default::default(),
+            auth: Default::default(),
+            metrics: Metrics::new(&events),
+            events,
+        };
+        let app = super::super::routes().with_state(state);
+
+        let document = document();
+        assert!(document.paths.paths.len() > 40);
+        let mut operation_ids = HashSet::new();
+        let mut documented = BTreeSet::new();
+        for (path, item) in &document.paths.paths {
+            let uri = path
+                .split('/')
+                .map(|part| match part.starts_with('{') {
+                    true => "1",
+                    false => part,
+                })
+                .collect::<Vec<_>>()
+                .join("/");
+            for (method, operation) in [
+                ("GET", &item.get),
+                ("POST", &item.post),
+                ("PUT", &item.put),
+                ("PATCH", &item.patch),
+                ("DELETE", &item.delete),
+            ] {
+                let Some(operation) = operation else {
+                    continue;
+                };
+                documented.insert(format!("{method} {path}"));
+                let operation_id = operation.operation_id.clone().unwrap_or_default();
+                assert!(
+                    operation_ids.insert(operation_id),
+                    "{method} {path} id repeats"
+                );
+                let req = Request::builder()
+                    .method(method)
+                    .uri(&uri)
+                    .body(Body::empty())
+                    .unwrap();
+                let status = app.clone().oneshot(req).await.unwrap().status();
+                assert!(
+                    !matches!(
+                        status,
+                        StatusCode::NOT_FOUND | StatusCode::METHOD_NOT_ALLOWED
+                    ),
+                    "{method} {path} isn't routed"
+                );
+            }
+        }
+
+        let routed = routed();
+        assert!(routed.len() > 40);
+        let undocumented: Vec<_> = routed.difference(&documented).collect();
+        assert!(
+            undocumented.is_empty(),
+            "not in the document: {undocumented:?}"
+        );
+    }
+
+    // "GET /orders/{id}" for each method of each route in the routes() of the modules, axum
+    // can't list the routes of a router
+    fn routed() -> BTreeSet<String> {
+        let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/src/routes");
+        let mut routed = BTreeSet::new();
+        for entry in std::fs::read_dir(dir).unwrap() {
+            let source = std::fs::read_to_string(entry.unwrap().path()).unwrap();
+            let Some(start) = source.find("pub fn routes()") else {
+                continue;
+            };
+            let body = &source[start..];
+            let body = &body[..body.find("\n}\n").unwrap_or(body.len())];
+            for route in body.split(".route(").skip(1) {
+                let path = route.split('"').nth(1).unwrap();
+                // /orders/:id is /orders/{id} in the document
+                let path = path
+                    .split('/')
+                    .map(|part| match part.strip_prefix(':') {
+                        Some(param) => format!("{{{param}}}"),
+                        None => part.to_owned(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join("/");
+                for method in ["get", "post", "put", "patch", "delete"] {
+                    let called = route.match_indices(&format!("{method}(")).any(|(i, _)| {
+                        !route[..i].ends_with(|c: char| c.is_alphanumeric() || c == '_')
+                    });
+                    if called {
+                        routed.insert(format!("{} {path}", method.to_uppercase()));
+                    }
+                }
+            }
+        }
+        routed
+    }
+}
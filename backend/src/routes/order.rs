@@ -1,54 +1,119 @@
 use axum::{
     extract::{Path, Query},
+    http::{header, HeaderMap},
+    response::{IntoResponse, Response},
     routing::{get, post},
     Json, Router,
 };
 use serde::Deserialize;
-use serde_json::{json, Value};
 use tracing::trace;
+use utoipa::{OpenApi, ToSchema};
+use validator::Validate;
 
+use super::{expected_version, or_current_order, Created, VersionParams};
 use crate::{
     controllers,
-    models::order::{OrderForCreate, OrderListParams, OrderResponseBasic},
+    models::{
+        bulk::BulkReport,
+        event::{Event, EventKind},
+        order::{
+            OrderForCreate, OrderForUpdate, OrderListParams, OrderResponseBasic, OrderStatus,
+            StatusChange,
+        },
+        receipt::ReceiptParams,
+        tax::Discount,
+    },
     session::Session,
+    validation::{nullable, trimmed, trimmed_option, valid_discount, ValidJson, ValidationReport},
     AppState, Result,
 };
 
 pub fn routes() -> Router<AppState> {
     Router::new()
         .route("/orders", post(create).get(list))
-        .route("/orders/:id", get(read).delete(delete))
+        .route("/orders/:id", get(read).patch(update).delete(delete))
+        .route("/orders/:id/status", post(transition))
+        .route("/orders/:id/history", get(history))
+        .route("/orders/:id/receipt.pdf", get(receipt))
+        .route("/orders/:id/duplicate", post(duplicate))
+        .route("/orders/bulk/delete", post(delete_bulk))
 }
 
+#[derive(OpenApi)]
+#[openapi(paths(
+    create,
+    list,
+    read,
+    update,
+    delete,
+    transition,
+    history,
+    receipt,
+    duplicate,
+    delete_bulk
+))]
+pub struct Api;
+
 // POST /orders
-// GET /orders
+// GET /orders?date_start=&date_end=&number=&modified_since=
 // GET /orders/:id
-// PUT /orders/:id
-// DELETE /orders/:id
+// PATCH /orders/:id (If-Match or version in the payload)
+// DELETE /orders/:id (If-Match or ?version=)
+// POST /orders/:id/status
+// GET /orders/:id/history
+// GET /orders/:id/receipt.pdf?layout=a4|thermal
+// POST /orders/:id/duplicate
+// POST /orders/bulk/delete
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Validate, ToSchema)]
+#[schema(as = OrderCreatePayload)]
 struct CreatePayload {
+    #[serde(deserialize_with = "trimmed")]
+    #[validate(length(min = 1, max = 100))]
     receiver: String,
+    #[serde(default, deserialize_with = "trimmed_option")]
+    #[validate(length(max = 1000))]
     additional_info: Option<String>,
+    customer_id: Option<i32>,
 }
 
+#[utoipa::path(
+    post,
+    path = "/orders",
+    operation_id = "order_create",
+    tag = "orders",
+    request_body = CreatePayload,
+    responses(
+        (status = 200, body = Created),
+        (status = 422, body = ValidationReport),
+    )
+)]
 async fn create(
     session: Session,
-    AppState { db, .. }: AppState,
-    payload: Json<CreatePayload>,
-) -> Result<Json<Value>> {
+    AppState {
+        db, shop, events, ..
+    }: AppState,
+    ValidJson(payload): ValidJson<CreatePayload>,
+) -> Result<Json<Created>> {
     trace!(" -- HANDLER POST /orders");
     let orderfc = OrderForCreate {
-        receiver: payload.receiver.clone(),
-        additional_info: payload.additional_info.clone(),
+        receiver: payload.receiver,
+        additional_info: payload.additional_info,
+        customer_id: payload.customer_id,
     };
-    let output = controllers::order::create(session, orderfc, db).await?;
-    let json = json!({
-        "id": output
-    });
-    Ok(Json(json))
+    let output = controllers::order::create(session, orderfc, shop.timezone, db).await?;
+    events.publish(Event::order(EventKind::OrderCreated, output));
+    Ok(Json(Created { id: output }))
 }
 
+#[utoipa::path(
+    get,
+    path = "/orders/{id}",
+    operation_id = "order_read",
+    tag = "orders",
+    params(("id" = i32, Path)),
+    responses((status = 200, body = OrderResponseBasic))
+)]
 async fn read(
     session: Session,
     AppState { db, .. }: AppState,
@@ -59,27 +124,298 @@ async fn read(
     Ok(Json(output))
 }
 
+#[utoipa::path(
+    get,
+    path = "/orders",
+    operation_id = "order_list",
+    tag = "orders",
+    params(OrderListParams),
+    responses((status = 200, body = Vec<OrderResponseBasic>))
+)]
 async fn list(
     session: Session,
-    AppState { db, .. }: AppState,
+    AppState { db, shop, .. }: AppState,
     params: Option<Query<OrderListParams>>,
 ) -> Result<Json<Vec<OrderResponseBasic>>> {
     trace!(" -- HANDLER GET /orders");
     if let Some(Query(params)) = params {
-        let output = controllers::order::list_with_params(session, params, db).await?;
+        let output =
+            controllers::order::list_with_params(session, params, shop.timezone, db).await?;
         return Ok(Json(output));
     }
     let output = controllers::order::list(session, db).await?;
     Ok(Json(output))
 }
 
+#[derive(Deserialize, Validate, ToSchema)]
+#[schema(as = OrderUpdatePayload)]
+struct UpdatePayload {
+    #[serde(default, deserialize_with = "trimmed_option")]
+    #[validate(length(min = 1, max = 100))]
+    receiver: Option<String>,
+    #[serde(default, deserialize_with = "trimmed_option")]
+    #[validate(length(max = 1000))]
+    additional_info: Option<String>,
+    customer_id: Option<i32>,
+    // null removes the discount
+    #[serde(default, deserialize_with = "nullable")]
+    #[schema(value_type = Option<Discount>)]
+    #[validate(custom(function = "valid_discount"))]
+    discount: Option<Option<Discount>>,
+    version: Option<i32>,
+}
+
+#[utoipa::path(
+    patch,
+    path = "/orders/{id}",
+    operation_id = "order_update",
+    tag = "orders",
+    params(
+        ("id" = i32, Path),
+        (
+            "If-Match" = Option<String>,
+            Header,
+            description = "version the client read, unless in the payload"
+        ),
+    ),
+    request_body = UpdatePayload,
+    responses(
+        (status = 200),
+        (
+            status = 409,
+            description = "changed since it was read, the current order",
+            body = OrderResponseBasic
+        ),
+        (status = 422, body = ValidationReport),
+        (status = 428, description = "no version given"),
+    )
+)]
+async fn update(
+    session: Session,
+    AppState { db, events, .. }: AppState,
+    Path(id): Path<i32>,
+    headers: HeaderMap,
+    ValidJson(payload): ValidJson<UpdatePayload>,
+) -> Result<Response> {
+    trace!(" -- HANDLER PATCH /orders/{}", id);
+    let order_fu = OrderForUpdate {
+        receiver: payload.receiver,
+        additional_info: payload.additional_info,
+        customer_id: payload.customer_id,
+        discount: payload.discount,
+        version: Some(expected_version(&headers, payload.version)?),
+    };
+    let result = controllers::order::update(session.clone(), id, order_fu, db.clone()).await;
+    if result.is_ok() {
+        events.publish(Event::order(EventKind::OrderUpdated, id));
+    }
+    or_current_order(result, session, id, db).await
+}
+
+#[utoipa::path(
+    delete,
+    path = "/orders/{id}",
+    operation_id = "order_delete",
+    tag = "orders",
+    params(
+        ("id" = i32, Path),
+        (
+            "If-Match" = Option<String>,
+            Header,
+            description = "version the client read, unless in ?version="
+        ),
+        VersionParams,
+    ),
+    responses(
+        (status = 200),
+        (
+            status = 409,
+            description = "changed since it was read, the current order",
+            body = OrderResponseBasic
+        ),
+        (status = 428, description = "no version given"),
+    )
+)]
 async fn delete(
     session: Session,
-    AppState { db, .. }: AppState,
+    AppState { db, events, .. }: AppState,
     Path(id): Path<i32>,
-) -> Result<()> {
+    headers: HeaderMap,
+    Query(params): Query<VersionParams>,
+) -> Result<Response> {
     trace!(" -- HANDLER DELETE /orders/{}", id);
+    let version = expected_version(&headers, params.version)?;
+    let result = controllers::order::delete(session.clone(), id, Some(version), db.clone()).await;
+    if result.is_ok() {
+        events.publish(Event::order(EventKind::OrderDeleted, id));
+    }
+    or_current_order(result, session, id, db).await
+}
+
+#[derive(Deserialize, Validate, ToSchema)]
+#[schema(as = OrderStatusPayload)]
+struct TransitionPayload {
+    status: OrderStatus,
+}
 
-    controllers::order::delete(session, id, db).await?;
+#[utoipa::path(
+    post,
+    path = "/orders/{id}/status",
+    operation_id = "order_transition",
+    tag = "orders",
+    params(("id" = i32, Path)),
+    request_body = TransitionPayload,
+    responses(
+        (status = 200),
+        (status = 409, description = "not allowed from the current status"),
+        (status = 422, body = ValidationReport),
+    )
+)]
+async fn transition(
+    session: Session,
+    AppState { db, events, .. }: AppState,
+    Path(id): Path<i32>,
+    ValidJson(payload): ValidJson<TransitionPayload>,
+) -> Result<()> {
+    trace!(" -- HANDLER POST /orders/{}/status", id);
+    controllers::order::transition(session, id, payload.status, db).await?;
+    events.publish(Event::order(EventKind::OrderUpdated, id));
     Ok(())
 }
+
+#[utoipa::path(
+    get,
+    path = "/orders/{id}/history",
+    operation_id = "order_history",
+    tag = "orders",
+    params(("id" = i32, Path)),
+    responses((status = 200, body = Vec<StatusChange>))
+)]
+async fn history(
+    session: Session,
+    AppState { db, .. }: AppState,
+    Path(id): Path<i32>,
+) -> Result<Json<Vec<StatusChange>>> {
+    trace!(" -- HANDLER GET /orders/{}/history", id);
+    let output = controllers::order::history(session, id, db).await?;
+    Ok(Json(output))
+}
+
+#[utoipa::path(
+    get,
+    path = "/orders/{id}/receipt.pdf",
+    operation_id = "order_receipt",
+    tag = "orders",
+    params(("id" = i32, Path), ReceiptParams),
+    responses((status = 200, content_type = "application/pdf"))
+)]
+async fn receipt(
+    session: Session,
+    AppState { db, shop, .. }: AppState,
+    Path(id): Path<i32>,
+    Query(params): Query<ReceiptParams>,
+) -> Result<impl IntoResponse> {
+    trace!(" -- HANDLER GET /orders/{}/receipt.pdf", id);
+    let layout = params.layout.unwrap_or(shop.receipt_layout);
+    let output = controllers::receipt::pdf(session, id, shop, layout, db).await?;
+    let headers = [
+        (header::CONTENT_TYPE, "application/pdf".to_owned()),
+        (
+            header::CONTENT_DISPOSITION,
+            format!("inline; filename=\"zamowienie-{id}.pdf\""),
+        ),
+    ];
+    Ok((headers, output))
+}
+
+#[utoipa::path(
+    post,
+    path = "/orders/{id}/duplicate",
+    operation_id = "order_duplicate",
+    tag = "orders",
+    params(("id" = i32, Path)),
+    responses((status = 200, body = Created))
+)]
+async fn duplicate(
+    session: Session,
+    AppState {
+        db, shop, events, ..
+    }: AppState,
+    Path(id): Path<i32>,
+) -> Result<Json<Created>> {
+    trace!(" -- HANDLER POST /orders/{}/duplicate", id);
+    let id = controllers::order::duplicate(session, id, shop.timezone, db).await?;
+    events.publish(Event::order(EventKind::OrderCreated, id));
+    Ok(Json(Created { id }))
+}
+
+#[derive(Deserialize, Validate, ToSchema)]
+#[schema(as = OrderBulkPayload)]
+struct BulkPayload {
+    #[validate(length(min = 1, max = controllers::bulk::LIMIT))]
+    order_ids: Vec<i32>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/orders/bulk/delete",
+    operation_id = "order_delete_bulk",
+    tag = "orders",
+    request_body = BulkPayload,
+    responses(
+        (status = 200, body = BulkReport),
+        (status = 422, body = ValidationReport),
+    )
+)]
+async fn delete_bulk(
+    session: Session,
+    AppState { db, events, .. }: AppState,
+    ValidJson(payload): ValidJson<BulkPayload>,
+) -> Result<Json<BulkReport>> {
+    trace!(" -- HANDLER POST /orders/bulk/delete");
+    let output = controllers::bulk::delete_orders(session, payload.order_ids, db).await?;
+    for id in output.succeeded_ids() {
+        events.publish(Event::order(EventKind::OrderDeleted, id));
+    }
+    Ok(Json(output))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::StatusCode;
+    use jwt_simple::prelude::HS256Key;
+
+    use super::*;
+    use crate::{events::Events, metrics::Metrics, models::time::DEFAULT_TIMEZONE, Db};
+
+    #[sqlx::test]
+    async fn order_transition_conflict(pool: Db) -> anyhow::Result<()> {
+        let order_fc = OrderForCreate {
+            receiver: "tomek".to_owned(),
+            additional_info: None,
+            customer_id: None,
+        };
+        let id =
+            controllers::order::create(Session::BASIC(), order_fc, DEFAULT_TIMEZONE, pool.clone())
+                .await?;
+        let events = Events::default();
+        let state = AppState {
+            db: pool,
+            jwt_key: HS256Key::generate(),
+            shop: Default::default(),
+            auth: Default::default(),
+            metrics: Metrics::new(&events),
+            events,
+        };
+
+        // paid comes from payments only
+        let payload = TransitionPayload {
+            status: OrderStatus::Paid,
+        };
+        let response = transition(Session::BASIC(), state, Path(id), ValidJson(payload))
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+        Ok(())
+    }
+}
@@ -1,5 +1,6 @@
 use axum::{routing::get, Router};
 use tracing::trace;
+use utoipa::OpenApi;
 
 use crate::AppState;
 
@@ -7,6 +8,18 @@ pub fn routes() -> Router<AppState> {
     Router::new().route("/ping", get(ping))
 }
 
+#[derive(OpenApi)]
+#[openapi(paths(ping))]
+pub struct Api;
+
+#[utoipa::path(
+    get,
+    path = "/ping",
+    operation_id = "ping",
+    tag = "health",
+    security(()),
+    responses((status = 200, body = String, example = "pong"))
+)]
 async fn ping() -> String {
     trace!(" -- HANDLER /ping");
     "pong".to_owned()
@@ -0,0 +1,42 @@
+use axum::{extract::Query, routing::get, Json, Router};
+use serde::Deserialize;
+use tracing::trace;
+use utoipa::{IntoParams, OpenApi};
+
+use crate::{
+    controllers, models::product::ProductResponseBasic, session::Session, AppState, Result,
+};
+
+pub fn routes() -> Router<AppState> {
+    Router::new().route("/products", get(search))
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(search))]
+pub struct Api;
+
+// GET /products?q=farba
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct SearchParams {
+    q: Option<String>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/products",
+    operation_id = "product_search",
+    tag = "products",
+    params(SearchParams),
+    responses((status = 200, body = Vec<ProductResponseBasic>))
+)]
+async fn search(
+    session: Session,
+    AppState { db, .. }: AppState,
+    Query(params): Query<SearchParams>,
+) -> Result<Json<Vec<ProductResponseBasic>>> {
+    trace!(" -- HANDLER GET /products");
+    let output = controllers::product::search(session, params.q.unwrap_or_default(), db).await?;
+    Ok(Json(output))
+}
@@ -0,0 +1,145 @@
+use axum::{
+    extract::Path,
+    routing::{get, post},
+    Json, Router,
+};
+use serde::Deserialize;
+use tracing::trace;
+use utoipa::{OpenApi, ToSchema};
+use validator::Validate;
+
+use super::Created;
+use crate::{
+    controllers,
+    models::{
+        event::{Event, EventKind},
+        template::{OrderTemplate, OrderTemplateDetail, OrderTemplateForCreate},
+    },
+    session::Session,
+    validation::{trimmed, ValidJson, ValidationReport},
+    AppState, Result,
+};
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/templates", get(list).post(create))
+        .route("/templates/:id", get(read).delete(delete))
+        .route("/templates/:id/orders", post(instantiate))
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(list, create, read, delete, instantiate))]
+pub struct Api;
+
+// GET /templates
+// POST /templates (Full only)
+// GET /templates/:id
+// DELETE /templates/:id (Full only)
+// POST /templates/:id/orders
+
+#[derive(Deserialize, Validate, ToSchema)]
+#[schema(as = TemplateCreatePayload)]
+struct CreatePayload {
+    #[serde(deserialize_with = "trimmed")]
+    #[validate(length(min = 1, max = 100))]
+    name: String,
+    order_id: i32,
+}
+
+#[utoipa::path(
+    post,
+    path = "/templates",
+    operation_id = "template_create",
+    tag = "templates",
+    description = "Full only",
+    request_body = CreatePayload,
+    responses(
+        (status = 200, body = Created),
+        (status = 422, body = ValidationReport),
+    )
+)]
+async fn create(
+    session: Session,
+    AppState { db, .. }: AppState,
+    ValidJson(payload): ValidJson<CreatePayload>,
+) -> Result<Json<Created>> {
+    trace!(" -- HANDLER POST /templates");
+    let template_fc = OrderTemplateForCreate {
+        name: payload.name,
+        order_id: payload.order_id,
+    };
+    let id = controllers::template::create(session, template_fc, db).await?;
+    Ok(Json(Created { id }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/templates",
+    operation_id = "template_list",
+    tag = "templates",
+    responses((status = 200, body = Vec<OrderTemplate>))
+)]
+async fn list(session: Session, AppState { db, .. }: AppState) -> Result<Json<Vec<OrderTemplate>>> {
+    trace!(" -- HANDLER GET /templates");
+    let output = controllers::template::list(session, db).await?;
+    Ok(Json(output))
+}
+
+#[utoipa::path(
+    get,
+    path = "/templates/{id}",
+    operation_id = "template_read",
+    tag = "templates",
+    params(("id" = i32, Path)),
+    responses((status = 200, body = OrderTemplateDetail))
+)]
+async fn read(
+    session: Session,
+    AppState { db, .. }: AppState,
+    Path(id): Path<i32>,
+) -> Result<Json<OrderTemplateDetail>> {
+    trace!(" -- HANDLER GET /templates/{}", id);
+    let output = controllers::template::read(session, id, db).await?;
+    Ok(Json(output))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/templates/{id}",
+    operation_id = "template_delete",
+    tag = "templates",
+    description = "Full only",
+    params(("id" = i32, Path)),
+    responses((status = 200))
+)]
+async fn delete(
+    session: Session,
+    AppState { db, .. }: AppState,
+    Path(id): Path<i32>,
+) -> Result<()> {
+    trace!(" -- HANDLER DELETE /templates/{}", id);
+    controllers::template::delete(session, id, db).await?;
+    Ok(())
+}
+
+// returns the id of the new order
+#[utoipa::path(
+    post,
+    path = "/templates/{id}/orders",
+    operation_id = "template_instantiate",
+    tag = "templates",
+    params(("id" = i32, Path)),
+    responses((status = 200, description = "the new order", body = Created))
+)]
+async fn instantiate(
+    session: Session,
+    AppState {
+        db, shop, events, ..
+    }: AppState,
+    Path(id): Path<i32>,
+) -> Result<Json<Created>> {
+    trace!(" -- HANDLER POST /templates/{}/orders", id);
+    let id = controllers::template::instantiate(session, id, shop.timezone, db).await?;
+    events.publish(Event::order(EventKind::OrderCreated, id));
+    Ok(Json(Created { id }))
+}
@@ -21,11 +21,11 @@ impl Session {
     pub fn new(id: i32, privileges: Privileges) -> Self {
         Self { id, privileges }
     }
-    pub fn id(self) -> i32 {
+    pub fn id(&self) -> i32 {
         self.id
     }
-    pub fn privileges(self) -> Privileges {
-        self.privileges
+    pub fn privileges(&self) -> Privileges {
+        self.privileges.clone()
     }
 
     #[cfg(test)]
@@ -78,6 +78,7 @@ where
             .map_err(|_| Error::AuthBadToken)?;
         let session_id = token_claims.custom.id;
         let privileges = token_claims.custom.privileges;
+        tracing::Span::current().record("user_id", session_id);
         Ok(Session::new(session_id, privileges))
     }
 }
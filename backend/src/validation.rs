@@ -0,0 +1,412 @@
+// Checks of request payloads before they reach the controllers.
+// Rules are declared on the payloads with #[validate(..)], strings are trimmed while
+// deserializing. Failures answer 422 with Polish messages by field.
+
+use std::collections::BTreeMap;
+
+use axum::{
+    async_trait,
+    extract::{rejection::JsonRejection, FromRequest, Request},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize};
+use utoipa::ToSchema;
+use validator::{Validate, ValidationError, ValidationErrors, ValidationErrorsKind};
+
+use crate::{
+    models::{money::Money, quantity::Quantity, tax::Discount},
+    Error,
+};
+
+// Json that also passed validation
+pub struct ValidJson<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequest<S> for ValidJson<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = Response;
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let Json(value) = Json::<T>::from_request(req, state)
+            .await
+            .map_err(|rejection| Error::ValidationFailed(rejected(&rejection)).into_response())?;
+        value
+            .validate()
+            .map_err(|errors| Error::ValidationFailed(messages(&errors)).into_response())?;
+        Ok(ValidJson(value))
+    }
+}
+
+// body of 422 answers, from Error::ValidationFailed
+#[derive(Serialize, ToSchema)]
+pub struct ValidationReport {
+    pub message: &'static str,
+    pub fields: BTreeMap<String, Vec<String>>, // messages by field
+    pub request_id: Option<String>,
+}
+
+// #[serde(deserialize_with = "trimmed")]
+pub fn trimmed<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    let value = String::deserialize(deserializer)?;
+    Ok(value.trim().to_owned())
+}
+
+// #[serde(default, deserialize_with = "trimmed_option")]
+pub fn trimmed_option<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<String>, D::Error> {
+    let value = Option::<String>::deserialize(deserializer)?;
+    Ok(value.map(|value| value.trim().to_owned()))
+}
+
+// #[serde(default, deserialize_with = "nullable")], absent is None and null is Some(None)
+pub fn nullable<'de, T: Deserialize<'de>, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Option<T>>, D::Error> {
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+// #[validate(custom(function = "non_zero"))], payments and refunds move some money
+pub fn non_zero(money: &Money) -> Result<(), ValidationError> {
+    match money.amount == 0 {
+        true => Err(ValidationError::new("non_zero")),
+        false => Ok(()),
+    }
+}
+
+// #[validate(custom(function = "non_zero_quantity"))]
+pub fn non_zero_quantity(quantity: &Quantity) -> Result<(), ValidationError> {
+    match quantity.amount == 0 {
+        true => Err(ValidationError::new("non_zero_quantity")),
+        false => Ok(()),
+    }
+}
+
+// #[validate(custom(function = "valid_quantity"))], what items accept, see Quantity::from_str
+pub fn valid_quantity(quantity: &str) -> Result<(), ValidationError> {
+    match quantity.parse::<Quantity>() {
+        Ok(_) => Ok(()),
+        Err(_) => Err(ValidationError::new("quantity")),
+    }
+}
+
+// #[validate(custom(function = "valid_discount"))]
+pub fn valid_discount(discount: &Discount) -> Result<(), ValidationError> {
+    match discount.validate() {
+        Ok(_) => Ok(()),
+        Err(_) => Err(ValidationError::new("discount")),
+    }
+}
+
+// payloads that didn't deserialize, by the field serde stopped at. Bodies that aren't JSON at
+// all are reported under the empty field
+fn rejected(rejection: &JsonRejection) -> BTreeMap<String, Vec<String>> {
+    let JsonRejection::JsonDataError(error) = rejection else {
+        return report("", "json");
+    };
+    let mut source = std::error::Error::source(error);
+    while let Some(error) = source {
+        if let Some(error) = error.downcast_ref::<serde_path_to_error::Error<serde_json::Error>>() {
+            let path = match error.path().to_string() {
+                path if path == "." => String::new(),
+                path => path,
+            };
+            // serde names a missing field only in its message
+            let inner = error.inner().to_string();
+            let missing = inner
+                .strip_prefix("missing field `")
+                .and_then(|rest| rest.split('`').next());
+            return match (missing, path.as_str()) {
+                (Some(field), "") => report(field, "required"),
+                (Some(field), path) => report(&format!("{path}.{field}"), "required"),
+                (None, path) => report(path, "invalid"),
+            };
+        }
+        source = error.source();
+    }
+    report("", "invalid")
+}
+
+// the messages of a single failed rule, for errors the controllers find
+pub fn report(field: &str, code: &'static str) -> BTreeMap<String, Vec<String>> {
+    BTreeMap::from([(field.to_owned(), vec![message(&ValidationError::new(code))])])
+}
+
+// #[validate(custom(function = "non_negative"))]
+pub fn non_negative(money: &Money) -> Result<(), ValidationError> {
+    match money.amount < 0 {
+        true => Err(ValidationError::new("non_negative")),
+        false => Ok(()),
+    }
+}
+
+// field paths like name or [2].name for lists, with their messages
+pub fn messages(errors: &ValidationErrors) -> BTreeMap<String, Vec<String>> {
+    let mut messages = BTreeMap::new();
+    collect(errors, "", &mut messages);
+    messages
+}
+
+fn collect(errors: &ValidationErrors, prefix: &str, messages: &mut BTreeMap<String, Vec<String>>) {
+    for (field, kind) in errors.errors() {
+        // lists validated at the top have no field name
+        let path = match (prefix, field.as_ref()) {
+            (prefix, "_tmp_validator") => prefix.to_owned(),
+            ("", field) => field.to_owned(),
+            (prefix, field) => format!("{prefix}.{field}"),
+        };
+        match kind {
+            ValidationErrorsKind::Field(errors) => {
+                let entry: &mut Vec<String> = messages.entry(path).or_default();
+                entry.extend(errors.iter().map(message));
+            }
+            ValidationErrorsKind::Struct(errors) => collect(errors, &path, messages),
+            ValidationErrorsKind::List(list) => {
+                for (index, errors) in list {
+                    collect(errors, &format!("{path}[{index}]"), messages);
+                }
+            }
+        }
+    }
+}
+
+fn message(error: &ValidationError) -> String {
+    let param = |name: &str| error.params.get(name).and_then(|value| value.as_u64());
+    match error.code.as_ref() {
+        "length" => {
+            let value = error.params.get("value");
+            // lists of ids
+            if let Some(list) = value.and_then(|value| value.as_array()) {
+                return match (param("min"), param("max")) {
+                    (Some(_), _) if list.is_empty() => "Lista nie może być pusta".to_owned(),
+                    (_, Some(max)) => format!("Dozwolone najwyżej {max} {}", items(max)),
+                    _ => "Niepoprawna liczba pozycji".to_owned(),
+                };
+            }
+            let length = value
+                .and_then(|value| value.as_str())
+                .map(|value| value.chars().count() as u64)
+                .unwrap_or_default();
+            match (param("min"), param("max")) {
+                (Some(_), _) if length == 0 => "To pole nie może być puste".to_owned(),
+                (Some(min), _) if length < min => {
+                    format!("Wymagane co najmniej {min} {}", chars(min))
+                }
+                (_, Some(max)) => format!("Dozwolone najwyżej {max} {}", chars(max)),
+                _ => "Niepoprawna długość".to_owned(),
+            }
+        }
+        "range" => match (param("min"), param("max")) {
+            (Some(min), Some(max)) => format!("Wartość musi być od {min} do {max}"),
+            (Some(min), None) => format!("Wartość nie może być mniejsza niż {min}"),
+            (None, Some(max)) => format!("Wartość nie może być większa niż {max}"),
+            (None, None) => "Wartość jest poza zakresem".to_owned(),
+        },
+        "non_negative" => "Kwota nie może być ujemna".to_owned(),
+        "non_zero" => "Kwota nie może być zerowa".to_owned(),
+        "non_zero_quantity" => "Ilość nie może być zerowa".to_owned(),
+        "quantity" => "Niepoprawna ilość, na przykład 2, 1,5 kg albo 250ml".to_owned(),
+        "discount" => "Rabat musi być od 0 do 100% albo nieujemną kwotą".to_owned(),
+        "email" => "Niepoprawny adres e-mail".to_owned(),
+        "required" => "To pole jest wymagane".to_owned(),
+        "json" => "Treść żądania nie jest poprawnym JSON-em".to_owned(),
+        _ => match &error.message {
+            Some(message) => message.to_string(),
+            None => "Niepoprawna wartość".to_owned(),
+        },
+    }
+}
+
+// 1 znak, 2 znaki, 5 znaków, 22 znaki
+fn plural(count: u64, [one, few, many]: [&'static str; 3]) -> &'static str {
+    match (count % 10, count % 100) {
+        _ if count == 1 => one,
+        (2..=4, tens) if !(12..=14).contains(&tens) => few,
+        _ => many,
+    }
+}
+
+fn chars(count: u64) -> &'static str {
+    plural(count, ["znak", "znaki", "znaków"])
+}
+
+fn items(count: u64) -> &'static str {
+    plural(count, ["pozycja", "pozycje", "pozycji"])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Deserialize, Validate)]
+    struct Payload {
+        #[serde(deserialize_with = "trimmed")]
+        #[validate(length(min = 1, max = 5))]
+        name: String,
+        #[serde(default, deserialize_with = "trimmed_option")]
+        #[validate(length(max = 3))]
+        info: Option<String>,
+        #[validate(custom(function = "non_negative"))]
+        value: Money,
+        #[validate(range(min = 1, max = 10))]
+        count: i32,
+    }
+
+    fn validate(json: &str) -> BTreeMap<String, Vec<String>> {
+        let payload: Payload = serde_json::from_str(json).unwrap();
+        match payload.validate() {
+            Ok(()) => BTreeMap::new(),
+            Err(errors) => messages(&errors),
+        }
+    }
+
+    #[test]
+    fn validation_messages() {
+        let ok = r#"{"name":" farba ","info":null,"value":{"amount":0},"count":1}"#;
+        assert!(validate(ok).is_empty());
+        let payload: Payload = serde_json::from_str(ok).unwrap();
+        assert_eq!(payload.name, "farba");
+
+        let bad = r#"{"name":"   ","info":" abcd ","value":{"amount":-1},"count":11}"#;
+        let messages = validate(bad);
+        assert_eq!(messages["name"], ["To pole nie może być puste"]);
+        assert_eq!(messages["info"], ["Dozwolone najwyżej 3 znaki"]);
+        assert_eq!(messages["value"], ["Kwota nie może być ujemna"]);
+        assert_eq!(messages["count"], ["Wartość musi być od 1 do 10"]);
+
+        let long = r#"{"name":"zielona","value":{"amount":1},"count":1}"#;
+        assert_eq!(validate(long)["name"], ["Dozwolone najwyżej 5 znaków"]);
+    }
+
+    #[test]
+    fn validation_quantity_and_discount() {
+        #[derive(Deserialize, Validate)]
+        struct Item {
+            #[validate(custom(function = "valid_quantity"))]
+            quantity: String,
+            #[validate(custom(function = "valid_discount"))]
+            discount: Option<Discount>,
+        }
+        let validate = |json| match serde_json::from_str::<Item>(json).unwrap().validate() {
+            Ok(()) => BTreeMap::new(),
+            Err(errors) => messages(&errors),
+        };
+        let ok = r#"{"quantity":"1,5 kg","discount":{"kind":"percent","value":1000}}"#;
+        assert!(validate(ok).is_empty());
+        let bad = r#"{"quantity":"dużo","discount":{"kind":"percent","value":10001}}"#;
+        let messages = validate(bad);
+        assert_eq!(
+            messages["quantity"],
+            [message(&ValidationError::new("quantity"))]
+        );
+        assert_eq!(
+            messages["discount"],
+            [message(&ValidationError::new("discount"))]
+        );
+
+        // the same when only a controller finds out
+        let response = Error::ItemInvalidQuantity.into_response();
+        assert_eq!(
+            response.status(),
+            axum::http::StatusCode::UNPROCESSABLE_ENTITY
+        );
+        let response = Error::DiscountInvalid.into_response();
+        assert_eq!(
+            response.status(),
+            axum::http::StatusCode::UNPROCESSABLE_ENTITY
+        );
+    }
+
+    #[test]
+    fn validation_nullable() {
+        #[derive(Deserialize)]
+        struct Patch {
+            #[serde(default, deserialize_with = "nullable")]
+            count: Option<Option<i32>>,
+        }
+        let parse = |json| serde_json::from_str::<Patch>(json).unwrap().count;
+        assert_eq!(parse("{}"), None);
+        assert_eq!(parse(r#"{"count":null}"#), Some(None));
+        assert_eq!(parse(r#"{"count":2}"#), Some(Some(2)));
+    }
+
+    #[test]
+    fn validation_list_paths() {
+        let payloads: Vec<Payload> = serde_json::from_str(
+            r#"[{"name":"a","value":{"amount":1},"count":1},{"name":"","value":{"amount":1},"count":1}]"#,
+        )
+        .unwrap();
+        let messages = messages(&payloads.validate().unwrap_err());
+        assert_eq!(messages.keys().collect::<Vec<_>>(), ["[1].name"]);
+    }
+
+    #[test]
+    fn validation_ids_and_non_zero() {
+        #[derive(Deserialize, Validate)]
+        struct Bulk {
+            #[validate(length(min = 1, max = 2))]
+            ids: Vec<i32>,
+            #[validate(custom(function = "non_zero"))]
+            amount: Money,
+        }
+        let validate = |json| match serde_json::from_str::<Bulk>(json).unwrap().validate() {
+            Ok(()) => BTreeMap::new(),
+            Err(errors) => messages(&errors),
+        };
+        assert!(validate(r#"{"ids":[1,2],"amount":{"amount":-100}}"#).is_empty());
+        let messages = validate(r#"{"ids":[],"amount":{"amount":0}}"#);
+        assert_eq!(messages["ids"], ["Lista nie może być pusta"]);
+        assert_eq!(messages["amount"], ["Kwota nie może być zerowa"]);
+        let many = validate(r#"{"ids":[1,2,3],"amount":{"amount":1}}"#);
+        assert_eq!(many["ids"], ["Dozwolone najwyżej 2 pozycje"]);
+    }
+
+    #[tokio::test]
+    async fn validation_rejections() {
+        async fn fields(json: &str) -> serde_json::Value {
+            let request = Request::builder()
+                .header("content-type", "application/json")
+                .body(axum::body::Body::from(json.to_owned()))
+                .unwrap();
+            let Err(response) = ValidJson::<Vec<Payload>>::from_request(request, &()).await else {
+                panic!("{json} passed");
+            };
+            assert_eq!(
+                response.status(),
+                axum::http::StatusCode::UNPROCESSABLE_ENTITY
+            );
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            serde_json::from_slice::<serde_json::Value>(&body).unwrap()["fields"].clone()
+        }
+        let missing = r#"[{"name":"a","value":{"amount":1},"count":1},{"name":"a","count":1}]"#;
+        assert_eq!(
+            fields(missing).await,
+            serde_json::json!({"[1].value": ["To pole jest wymagane"]})
+        );
+        let wrong_type = r#"[{"name":"a","value":{"amount":1},"count":"dwa"}]"#;
+        assert_eq!(
+            fields(wrong_type).await,
+            serde_json::json!({"[0].count": ["Niepoprawna wartość"]})
+        );
+        assert_eq!(
+            fields("[{").await,
+            serde_json::json!({"": ["Treść żądania nie jest poprawnym JSON-em"]})
+        );
+    }
+
+    #[test]
+    fn validation_plural() {
+        let forms: Vec<&str> = [1, 2, 4, 5, 12, 22, 100].map(chars).to_vec();
+        assert_eq!(
+            forms,
+            ["znak", "znaki", "znaki", "znaków", "znaków", "znaki", "znaków"]
+        );
+        let forms: Vec<&str> = [1, 3, 13, 500].map(items).to_vec();
+        assert_eq!(forms, ["pozycja", "pozycje", "pozycji", "pozycji"]);
+    }
+}
@@ -0,0 +1,26 @@
+use anyhow::Result;
+use common::get_api_path;
+
+mod common;
+
+#[tokio::test]
+async fn health() -> Result<()> {
+    let api_path = get_api_path()?;
+    let live = reqwest::get(format!("{api_path}/health/live")).await?;
+    assert_eq!(live.status(), 200);
+
+    let ready = reqwest::get(format!("{api_path}/health/ready")).await?;
+    assert_eq!(ready.status(), 200);
+
+    // counted by route, so /ping shows up after being called
+    reqwest::get(format!("{api_path}/ping")).await?;
+    let metrics_path = api_path.trim_end_matches("/api");
+    let metrics = reqwest::get(format!("{metrics_path}/metrics"))
+        .await?
+        .text()
+        .await?;
+    assert!(metrics.contains(r#"http_requests_total{method="GET",route="/api/ping",status="200"}"#));
+    assert!(metrics.contains("db_pool_connections"));
+
+    Ok(())
+}
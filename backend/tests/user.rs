@@ -1,5 +1,9 @@
 use std::str::FromStr;
 
+use backend::{
+    cli::{create_user, set_password, UserExists},
+    models::user::Privileges,
+};
 use reqwest::{
     header::{self, SET_COOKIE},
     StatusCode,
@@ -15,10 +19,26 @@ struct LoginPayload {
     password: String,
 }
 
-// requires running backend with postgresql and mock_data migration on
+const USER: &str = "integration-test";
+const PASSWORD: &str = "integration-test-password";
+
+// the user the tests log in as, in the database of the running backend
+async fn setup_user() -> anyhow::Result<()> {
+    let db = sqlx::PgPool::connect(&std::env::var("DATABASE_URL")?).await?;
+    match create_user(&db, USER, PASSWORD, Privileges::Full).await {
+        Ok(_) => {}
+        Err(err) if err.is::<UserExists>() => set_password(&db, USER, PASSWORD).await?,
+        Err(err) => return Err(err),
+    }
+    db.close().await;
+    Ok(())
+}
+
+// requires running backend with postgresql
 #[tokio::test]
 async fn login() -> anyhow::Result<()> {
     let api_path = common::get_api_path()?;
+    setup_user().await?;
 
     let client = reqwest::Client::new();
 
@@ -38,7 +58,7 @@ async fn login() -> anyhow::Result<()> {
 
     // bad password
     let payload = LoginPayload {
-        login: "boss".to_owned(),
+        login: USER.to_owned(),
         password: "123_ale_4".to_owned(),
     };
     let res = client
@@ -51,8 +71,8 @@ async fn login() -> anyhow::Result<()> {
 
     // ok
     let payload = LoginPayload {
-        login: "boss".to_owned(),
-        password: "123".to_owned(),
+        login: USER.to_owned(),
+        password: PASSWORD.to_owned(),
     };
     let res = client
         .post(&format!("{api_path}/login"))
@@ -77,6 +97,7 @@ async fn login() -> anyhow::Result<()> {
 #[tokio::test]
 async fn token() -> anyhow::Result<()> {
     let api_path = common::get_api_path()?;
+    setup_user().await?;
     let client = reqwest::ClientBuilder::new().cookie_store(true).build()?;
 
     // token without cookie
@@ -93,8 +114,8 @@ async fn token() -> anyhow::Result<()> {
 
     // ok
     let payload = LoginPayload {
-        login: "boss".to_owned(),
-        password: "123".to_owned(),
+        login: USER.to_owned(),
+        password: PASSWORD.to_owned(),
     };
     let res = client
         .post(&format!("{api_path}/login"))
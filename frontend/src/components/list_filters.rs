@@ -16,6 +16,7 @@ pub fn ListFilter(
             .date_naive()
             .checked_add_days(chrono::Days::new(1)),
     );
+    let number = create_rw_signal(String::new());
     let show_filters = create_rw_signal(false);
     let show_today = move |_| {
         let today = chrono::Local::now().naive_local().date().to_string();
@@ -34,11 +35,16 @@ pub fn ListFilter(
         res.refetch();
     };
     let filter = move |_| {
-        params.set(format!(
+        let mut query = format!(
             "?date_start={}&date_end={}",
             date_picker_start.get_untracked().unwrap(),
             date_picker_end.get_untracked().unwrap()
-        ));
+        );
+        let number = number.get_untracked();
+        if !number.trim().is_empty() {
+            query.push_str(&format!("&number={}", encode_query(number.trim())));
+        }
+        params.set(query);
         res.refetch();
         show_filters.set(false);
     };
@@ -61,6 +67,10 @@ pub fn ListFilter(
                 {"Do (włącznie)"}
                 <DatePicker value=date_picker_end/>
                 </Space>
+                <Space>
+                {"Numer"}
+                <Input value=number placeholder="ZAM/2026/00123"/>
+                </Space>
                 <Button on_click=filter block=true>"Filtruj"</Button>
             </Space>
         </Modal>
@@ -72,6 +82,15 @@ pub fn ListFilter(
         </ButtonGroup>
     }
 }
+// order numbers contain slashes
+fn encode_query(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' => (b as char).to_string(),
+            _ => format!("%{b:02X}"),
+        })
+        .collect()
+}
 fn this_month_in_polish() -> &'static str {
     let month = chrono::Local::now().naive_local().date().month0() + 1; // 1-12
     match month {
@@ -8,6 +8,7 @@ use crate::model::{ItemResponseBasic, OrderResponseBasic};
 
 #[component]
 pub fn OrderCard(order: OrderResponseBasic) -> impl IntoView {
+    let time_created = order.time_created.with_timezone(&chrono::Local);
     view! {
         <Style>"
             .inner > div{
@@ -32,8 +33,8 @@ pub fn OrderCard(order: OrderResponseBasic) -> impl IntoView {
             <div class="padding">
                 <br/>
                 <Space justify=SpaceJustify::SpaceBetween>
-                    {order.time_created.format("%H:%M").to_string()}
-                    {order.time_created.format("%d.%m.%Y").to_string()}
+                    {time_created.format("%H:%M").to_string()}
+                    {time_created.format("%d.%m.%Y").to_string()}
                 </Space>
                 <Divider />
                 <Space justify=SpaceJustify::SpaceBetween>{"Dla: ".to_owned()}{order.receiver}</Space>
@@ -51,7 +52,7 @@ pub fn OrderCard(order: OrderResponseBasic) -> impl IntoView {
                    <tr class={if item.checked {"checked"} else {""}}>
                        <td>{item.quantity}</td>
                        <td>{item.name}</td>
-                       <td>{format!("{}.{:02} zł",item.value/100,item.value%100)}</td>
+                       <td>{item.value.to_string()}</td>
                    </tr>
                }).collect::<Vec<_>>()}
            </Table>
@@ -5,8 +5,7 @@ use thaw::*;
 
 use crate::components::list_filters::ListFilter;
 use crate::list::fetch_orders;
-use crate::model::OrderResponseBasic;
-use crate::order::get_order_sum_value;
+use crate::model::{Money, OrderResponseBasic};
 #[component]
 pub fn DashboardView() -> impl IntoView {
     let params = create_rw_signal(String::from(""));
@@ -36,10 +35,10 @@ pub fn DashboardView() -> impl IntoView {
                 {move||match res.get(){
                     None => view!{<Space justify=SpaceJustify::Center><Spinner /></Space>}.into_view(),
                     Some(s) => view!{{
-                        let r = count_income(s);
-                        let c = r/100;
-                        let rem = r%100;
-                        format!("{c}.{rem:02} zł")
+                        match count_income(s) {
+                            Some(income) => income.to_string(),
+                            None => "Błąd: przekroczono zakres".to_owned(),
+                        }
                     }}.into_view(),
                 }}
                 </Space>
@@ -57,11 +56,11 @@ pub fn DashboardView() -> impl IntoView {
     }
 }
 
-fn count_income(vec: Vec<OrderResponseBasic>) -> i32 {
-    let mut income = 0;
+// None if the sum overflows
+fn count_income(vec: Vec<OrderResponseBasic>) -> Option<Money> {
+    let mut income = Money::pln(0);
     for order in vec {
-        let order_value = get_order_sum_value(order.items);
-        income += order_value;
+        income = income.checked_add(order.totals.gross)?;
     }
-    income
+    Some(income)
 }
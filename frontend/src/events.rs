@@ -0,0 +1,30 @@
+use leptos::*;
+use wasm_bindgen::{closure::Closure, JsCast};
+use web_sys::{EventSource, EventSourceInit, MessageEvent};
+
+use crate::{model::Event, API_PATH};
+
+// calls on_event for every change the backend announces, until the component is dropped.
+// the browser reconnects by itself when the connection breaks
+pub fn subscribe(on_event: impl Fn(Event) + 'static) {
+    let init = EventSourceInit::new();
+    init.set_with_credentials(true);
+    let Ok(source) =
+        EventSource::new_with_event_source_init_dict(&format!("{}/events", API_PATH), &init)
+    else {
+        return;
+    };
+    let on_message = Closure::<dyn Fn(MessageEvent)>::new(move |message: MessageEvent| {
+        let Some(data) = message.data().as_string() else {
+            return;
+        };
+        if let Ok(event) = serde_json::from_str(&data) {
+            on_event(event);
+        }
+    });
+    source.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+    on_cleanup(move || {
+        source.close();
+        drop(on_message);
+    });
+}
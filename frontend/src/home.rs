@@ -10,7 +10,7 @@ use serde_json::json;
 use thaw::*;
 use wasm_bindgen::JsValue;
 
-use crate::{Context, API_PATH};
+use crate::{model::OrderTemplate, Context, API_PATH};
 
 #[component]
 pub fn HomeView() -> impl IntoView {
@@ -39,6 +39,19 @@ pub fn HomeView() -> impl IntoView {
             }
         });
     };
+    // saved orders of repeat customers
+    let templates = create_resource(|| {}, |_| async { fetch_templates().await.unwrap_or_default() });
+    let from_template = move |template_id: i32| {
+        spawn_local(async move {
+            match fetch_instantiate(template_id).await {
+                Ok(id) => {
+                    let nav = use_navigate();
+                    nav(&format!("/orders/{}", id), Default::default())
+                }
+                Err(e) => messages.create(e.to_string(), MessageVariant::Error, Default::default()),
+            }
+        });
+    };
     let window_size = window()
         .inner_width()
         .unwrap_or(JsValue::from_f64(400.0))
@@ -101,6 +114,13 @@ pub fn HomeView() -> impl IntoView {
                         }}.into_view()}
                 }.into_view()}}
         </Space>
+        <Space justify=SpaceJustify::Center>
+            {move||templates.get().unwrap_or_default().into_iter().map(|template|view!{
+                <Button on_click=move|_|from_template(template.id) variant=ButtonVariant::Outlined>
+                    {format!("Z szablonu: {}", template.name)}
+                </Button>
+            }).collect::<Vec<_>>()}
+        </Space>
     }
 }
 
@@ -156,3 +176,37 @@ async fn fetch_create() -> Result<i32> {
     let json: Output = res.json().await?;
     Ok(json.id)
 }
+
+async fn fetch_templates() -> Result<Vec<OrderTemplate>> {
+    let client = reqwest::Client::new();
+    let res = client
+        .get(format!("{}/templates", API_PATH))
+        .fetch_credentials_include()
+        .send()
+        .await?;
+    if res.status() != StatusCode::OK {
+        let e = res.text().await?;
+        anyhow::bail!(e.to_string());
+    }
+    Ok(res.json().await?)
+}
+
+// returns the id of the new order
+async fn fetch_instantiate(template_id: i32) -> Result<i32> {
+    let client = reqwest::Client::new();
+    let res = client
+        .post(format!("{}/templates/{template_id}/orders", API_PATH))
+        .fetch_credentials_include()
+        .send()
+        .await?;
+    if res.status() != StatusCode::OK {
+        let e = res.text().await?;
+        anyhow::bail!(e.to_string());
+    }
+    #[derive(Deserialize)]
+    struct Output {
+        id: i32,
+    }
+    let json: Output = res.json().await?;
+    Ok(json.id)
+}
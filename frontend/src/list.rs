@@ -23,6 +23,8 @@ pub fn ListView() -> impl IntoView {
             }
         },
     );
+    // any change can move orders in or out of the filtered list
+    crate::events::subscribe(move |_| res.refetch());
     let back = move |_| {
         let nav = use_navigate();
         nav("/", Default::default());
@@ -57,12 +59,12 @@ pub fn ListView() -> impl IntoView {
                 Some(s) => { s.iter().cloned().map(|order: OrderResponseBasic|{view!{
                     <Space vertical=true>
                         <Button
-                            class={if is_order_checked(&order) {"checked"}else{""}}
+                            class={if order.status.is_done() {"checked"}else{""}}
                              on_click=move|_|go_to_order(order.id)
                              block=true
                              variant=ButtonVariant::Outlined>
 
-                             {order.receiver.to_string()}
+                             {format!("{} {} ({})", order.number, order.receiver, order.status.label())}
                         </Button>
                         <div style="height:0px"></div>
                     </Space>
@@ -93,11 +95,3 @@ pub async fn fetch_orders(params: String) -> Result<Vec<OrderResponseBasic>> {
     let vec: Vec<OrderResponseBasic> = res.json().await?;
     Ok(vec)
 }
-
-fn is_order_checked(order: &OrderResponseBasic) -> bool {
-    // if all items inside are checked
-    if order.items.is_empty() {
-        return false;
-    }
-    order.items.iter().all(|x| x.checked)
-}
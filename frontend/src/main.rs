@@ -10,6 +10,7 @@ use thaw::*;
 
 pub mod components;
 mod dashboard;
+mod events;
 mod home;
 mod list;
 mod login;
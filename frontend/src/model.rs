@@ -3,19 +3,222 @@ use serde::{Deserialize, Serialize};
 #[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Serialize, Debug, Deserialize)]
 pub struct OrderResponseBasic {
     pub id: i32,
-    pub time_created: chrono::NaiveDateTime,
+    pub number: String,
+    pub time_created: chrono::DateTime<chrono::Utc>,
     pub receiver: String,
     pub additional_info: Option<String>,
+    pub status: OrderStatus,
+    pub picked_up: bool, // paid orders can be paid before or after that
+    pub customer_id: Option<i32>,
+    pub totals: OrderTotals,
     pub items: Vec<ItemResponseBasic>,
+    pub version: i32, // sent back with changes, see CONFLICT_MESSAGE
+}
+
+// computed by the server, the item values are gross prices
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Serialize, Deserialize, Debug)]
+pub struct OrderTotals {
+    pub before_discount: Money,
+    pub discount: Money,
+    pub net: Money,
+    pub vat: Money,
+    pub gross: Money,
+}
+
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Serialize, Deserialize, Debug)]
+pub enum OrderStatus {
+    Draft,
+    Submitted,
+    InPreparation,
+    Ready,
+    PickedUp,
+    Paid,
+    Cancelled,
+}
+
+impl OrderStatus {
+    pub fn label(self) -> &'static str {
+        match self {
+            OrderStatus::Draft => "Szkic",
+            OrderStatus::Submitted => "Przyjęte",
+            OrderStatus::InPreparation => "W przygotowaniu",
+            OrderStatus::Ready => "Gotowe",
+            OrderStatus::PickedUp => "Odebrane",
+            OrderStatus::Paid => "Opłacone",
+            OrderStatus::Cancelled => "Anulowane",
+        }
+    }
+    // statuses a worker can move the order to, payments are handled in the admin panel
+    // and cancelling is done by deleting the order. Orders paid in advance are still picked up
+    pub fn next(self, picked_up: bool) -> Vec<OrderStatus> {
+        match self {
+            OrderStatus::Draft => vec![OrderStatus::Submitted],
+            OrderStatus::Submitted => vec![OrderStatus::Draft, OrderStatus::InPreparation],
+            OrderStatus::InPreparation => vec![OrderStatus::Ready],
+            OrderStatus::Ready => vec![OrderStatus::PickedUp],
+            OrderStatus::Paid if !picked_up => vec![OrderStatus::PickedUp],
+            _ => vec![],
+        }
+    }
+    // nothing left to prepare
+    pub fn is_done(self) -> bool {
+        matches!(
+            self,
+            OrderStatus::Ready | OrderStatus::PickedUp | OrderStatus::Paid
+        )
+    }
 }
 #[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Serialize, Deserialize, Debug)]
 pub struct ItemResponseBasic {
     pub id: i32,
     pub order_id: i32,
-    pub time_created: chrono::NaiveDateTime,
+    pub time_created: chrono::DateTime<chrono::Utc>,
     pub quantity: String, // for example 1l or 5kg
-    pub name: String,     // for example czerwona farba
-    pub value: i32,       // for example 100_00 = 100PLN
+    pub quantity_parsed: Option<Quantity>,
+    pub product_id: Option<i32>,
+    pub name: String, // for example czerwona farba
+    pub value: Money, // for example 100_00 = 100PLN
     pub additional_info: Option<String>,
     pub checked: bool,
+    pub position: i32, // place in the order, from 1
+    pub version: i32,
+}
+
+#[derive(
+    PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Serialize, Deserialize, Debug, Default,
+)]
+pub enum Currency {
+    #[default]
+    PLN,
+}
+
+impl Currency {
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Currency::PLN => "zł",
+        }
+    }
+}
+
+// amount in minor units, for example 100_00 = 100PLN. Mirrors backend/src/models/money.rs, the
+// formatting both share is in the shared crate
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Serialize, Deserialize, Debug)]
+pub struct Money {
+    pub amount: i64,
+    #[serde(default)]
+    pub currency: Currency,
+}
+
+impl Money {
+    pub fn pln(amount: i64) -> Self {
+        Self {
+            amount,
+            currency: Currency::PLN,
+        }
+    }
+    // None on overflow or when currencies differ
+    pub fn checked_add(self, rhs: Money) -> Option<Money> {
+        if self.currency != rhs.currency {
+            return None;
+        }
+        let amount = self.amount.checked_add(rhs.amount)?;
+        Some(Money { amount, ..self })
+    }
+    pub fn checked_mul(self, factor: i64) -> Option<Money> {
+        let amount = self.amount.checked_mul(factor)?;
+        Some(Money { amount, ..self })
+    }
+    // price of `quantity` units, rounded half away from zero to whole grosze
+    pub fn checked_mul_quantity(self, quantity: Quantity) -> Option<Money> {
+        let thousandths = self.amount.checked_mul(quantity.amount)?;
+        let rounded = thousandths.checked_add(500 * thousandths.signum())? / 1000;
+        Some(Money {
+            amount: rounded,
+            ..self
+        })
+    }
+    // accepts "100", "100.5" and "100,50"
+    pub fn parse_pln(s: &str) -> Option<Money> {
+        let s = s.trim().replace(',', ".");
+        let (whole, fraction) = s.split_once('.').unwrap_or((&s, ""));
+        if fraction.len() > 2 || !fraction.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        let negative = whole.starts_with('-');
+        let whole: i64 = whole.trim_start_matches('-').parse().ok()?;
+        let fraction: i64 = format!("{fraction:0<2}").parse().ok()?;
+        let amount = whole.checked_mul(100)?.checked_add(fraction)?;
+        Some(Money::pln(if negative { -amount } else { amount }))
+    }
+}
+
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Serialize, Deserialize, Debug)]
+pub struct ProductResponseBasic {
+    pub id: i32,
+    pub name: String,
+    pub default_unit: Unit,
+    pub unit_price: Money,
+    pub category: Option<String>,
+}
+
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Serialize, Deserialize, Debug)]
+pub struct CustomerResponseBasic {
+    pub id: i32,
+    pub name: String,
+    pub phone: Option<String>,
+    pub nip: Option<String>,
+}
+
+// saved order to start new ones from
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Serialize, Deserialize, Debug)]
+pub struct OrderTemplate {
+    pub id: i32,
+    pub name: String,
+    pub receiver: String,
+}
+
+// change sent by the backend on /events, only ids
+#[derive(PartialEq, Eq, Clone, Copy, Serialize, Deserialize, Debug)]
+pub struct Event {
+    pub kind: EventKind,
+    pub order_id: Option<i32>,
+    pub item_id: Option<i32>,
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Serialize, Deserialize, Debug)]
+pub enum EventKind {
+    OrderCreated,
+    OrderUpdated,
+    OrderDeleted,
+    OrderPaid,
+    ItemCreated,
+    ItemUpdated,
+    ItemDeleted,
+    Resync,
+}
+
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum Unit {
+    Szt,
+    Kg,
+    G,
+    L,
+    Ml,
+    M,
+    M2,
+}
+
+// amount in thousandths of the unit, for example 1_500 with Unit::Kg = 1.5kg
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Serialize, Deserialize, Debug)]
+pub struct Quantity {
+    pub amount: i64,
+    pub unit: Unit,
+}
+
+// pl-PL: "12 345,67 zł", the backend shows amounts the same way
+impl std::fmt::Display for Money {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&shared::money::format(self.amount, self.currency.symbol()))
+    }
 }
@@ -1,4 +1,4 @@
-use anyhow::{bail, Context as _};
+use anyhow::bail;
 use icondata as i;
 use leptos::*;
 use leptos_meta::Style;
@@ -8,7 +8,10 @@ use serde_json::json;
 use thaw::*;
 
 use crate::{
-    model::{ItemResponseBasic, OrderResponseBasic},
+    model::{
+        CustomerResponseBasic, EventKind, Money, OrderResponseBasic, OrderStatus,
+        ProductResponseBasic,
+    },
     Context, API_PATH,
 };
 
@@ -22,7 +25,14 @@ pub fn OrderView() -> impl IntoView {
     let params = use_params::<OrderParams>();
     let id =
         move || params.with(|params| params.as_ref().map(|params| params.id).unwrap_or_default());
-    let res = create_resource(|| {}, move |_| fetch_order_safe(id().unwrap_or(0)));
+    // follows the id, so going to a duplicated order loads it
+    let res = create_resource(id, move |id| fetch_order_safe(id.unwrap_or(0)));
+    // changes of this order made by others
+    crate::events::subscribe(move |event| {
+        if event.kind == EventKind::Resync || event.order_id == id() {
+            res.refetch();
+        }
+    });
 
     // fetch and create editable input for receiver
     let receiver = move || {
@@ -32,7 +42,36 @@ pub fn OrderView() -> impl IntoView {
         "".to_string()
     };
     let receiver_val = create_rw_signal("".to_string());
-    create_effect(move |_| receiver_val.set(receiver()));
+    // customer picked from the receiver autocomplete
+    let customer: RwSignal<Option<CustomerResponseBasic>> = create_rw_signal(None);
+    let customers = create_resource(
+        move || receiver_val.get(),
+        |query| async move { fetch_customers(query).await.unwrap_or_default() },
+    );
+    let customer_options = move || {
+        customers
+            .get()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|customer| AutoCompleteOption {
+                label: match &customer.phone {
+                    Some(phone) => format!("{} ({phone})", customer.name),
+                    None => customer.name.clone(),
+                },
+                value: customer.id.to_string(),
+            })
+            .collect::<Vec<_>>()
+    };
+    let select_customer = Callback::new(move |id: String| {
+        let picked = untrack(move || customers.get())
+            .unwrap_or_default()
+            .into_iter()
+            .find(|customer| customer.id.to_string() == id);
+        if let Some(picked) = picked {
+            receiver_val.set(picked.name.clone());
+            customer.set(Some(picked));
+        }
+    });
 
     // for additional info
     let additional = move || {
@@ -42,57 +81,213 @@ pub fn OrderView() -> impl IntoView {
         "".to_string()
     };
     let additional_val = create_rw_signal("".to_string());
-    create_effect(move |_| additional_val.set(additional()));
-    // vec of checkbox bools which reacts to api responses
-    let checkeds = move || {
+    // version the changes of the order are based on
+    let version = move || {
         if let Some(Some(s)) = res.get() {
-            return s.items.iter().map(|item| item.checked).collect();
+            return s.version;
         }
-        vec![]
+        0
     };
-    let checked_values: RwSignal<Vec<bool>> = create_rw_signal(vec![]);
-    // binds api responses to vec of signals
-    create_effect(move |_| {
-        checked_values.set(checkeds().to_owned());
-    });
-    let update_check = move |order_id: i32, index: usize| {
-        let mut values = checked_values.get();
-        if let Some(v) = values.get_mut(index) {
-            let new_value: bool = !*v;
-            spawn_local(async move {
-                fetch_update_safe(order_id, receiver_val, additional_val).await;
-                fetch_item_check_safe(order_id, index as i32, new_value).await;
-                res.refetch();
-            });
+    let version_val = create_rw_signal(0);
+    // a refetch keeps unsaved edits of the inputs and the version they are based on, so saving
+    // them still reports a conflict when the order was changed meanwhile
+    create_effect(move |previous: Option<(Option<i32>, String, String)>| {
+        let current = (id(), receiver(), additional());
+        let unsaved = untrack(move || (receiver_val.get(), additional_val.get()));
+        let edited =
+            previous.is_some_and(|(previous_id, previous_receiver, previous_additional)| {
+                previous_id == current.0 && unsaved != (previous_receiver, previous_additional)
+            }) && unsaved != (current.1.clone(), current.2.clone());
+        if !edited {
+            receiver_val.set(current.1.clone());
+            additional_val.set(current.2.clone());
+            version_val.set(version());
         }
+        current
+    });
+    // items are addressed by id, their place in the list can change between fetches
+    let update_check = move |order_id: i32, item_id: i32, item_version: i32, checked: bool| {
+        spawn_local(async move {
+            fetch_update_safe(
+                order_id,
+                version_val,
+                receiver_val,
+                additional_val,
+                customer,
+            )
+            .await;
+            fetch_item_check_safe(order_id, item_id, item_version, checked).await;
+            res.refetch();
+        });
+    };
+    // swaps the item with its neighbour, offset is -1 for up and 1 for down
+    let move_item = move |order_id: i32, item_ids: Vec<i32>, index: usize, offset: isize| {
+        let Some(other) = index
+            .checked_add_signed(offset)
+            .filter(|other| *other < item_ids.len())
+        else {
+            return;
+        };
+        let mut item_ids = item_ids;
+        item_ids.swap(index, other);
+        spawn_local(async move {
+            fetch_update_safe(
+                order_id,
+                version_val,
+                receiver_val,
+                additional_val,
+                customer,
+            )
+            .await;
+            if let Err(e) = fetch_items_reorder(order_id, item_ids).await {
+                use_message().create(e.to_string(), MessageVariant::Error, Default::default());
+            }
+            res.refetch();
+        });
+    };
+
+    let check_all = move |order_id: i32, checked: bool| {
+        spawn_local(async move {
+            fetch_update_safe(
+                order_id,
+                version_val,
+                receiver_val,
+                additional_val,
+                customer,
+            )
+            .await;
+            if let Err(e) = fetch_items_check_all(order_id, checked).await {
+                use_message().create(e.to_string(), MessageVariant::Error, Default::default());
+            }
+            res.refetch();
+        });
     };
 
-    let delete_item = move |order_id: i32, item_id: i32| {
+    let delete_item = move |order_id: i32, item_id: i32, item_version: i32| {
         spawn_local(async move {
-            fetch_item_delete_safe(order_id, item_id).await;
+            fetch_item_delete_safe(order_id, item_id, item_version).await;
             res.refetch();
         });
     };
 
     let new_item_quantity = create_rw_signal("".to_string());
     let new_item_name = create_rw_signal("".to_string());
-    let new_item_value = create_rw_signal(0i32);
+    let new_item_value = create_rw_signal("".to_string());
+    // catalog product picked from the name autocomplete
+    let new_item_product: RwSignal<Option<ProductResponseBasic>> = create_rw_signal(None);
+    let products = create_resource(
+        move || new_item_name.get(),
+        |query| async move { fetch_products(query).await.unwrap_or_default() },
+    );
+    let product_options = move || {
+        products
+            .get()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|product| AutoCompleteOption {
+                label: format!("{} ({})", product.name, product.unit_price),
+                value: product.id.to_string(),
+            })
+            .collect::<Vec<_>>()
+    };
+    let select_product = Callback::new(move |id: String| {
+        let product = untrack(move || products.get())
+            .unwrap_or_default()
+            .into_iter()
+            .find(|product| product.id.to_string() == id);
+        if let Some(product) = product {
+            new_item_name.set(product.name.clone());
+            new_item_value.set(product.unit_price.to_string());
+            new_item_product.set(Some(product));
+        }
+    });
     let add_item = move |order_id: i32| {
         spawn_local(async move {
-            let val = new_item_value.get_untracked();
-            fetch_update_safe(order_id, receiver_val, additional_val).await;
-            fetch_new_item_safe(order_id, new_item_quantity, new_item_name, val as i32).await;
+            let val = Money::parse_pln(&new_item_value.get_untracked().replace("zł", ""))
+                .unwrap_or(Money::pln(0));
+            // the product is only referenced if its name wasn't edited afterwards
+            let product_id = new_item_product
+                .get_untracked()
+                .filter(|product| product.name == new_item_name.get_untracked())
+                .map(|product| product.id);
+            fetch_update_safe(
+                order_id,
+                version_val,
+                receiver_val,
+                additional_val,
+                customer,
+            )
+            .await;
+            fetch_new_item_safe(order_id, new_item_quantity, new_item_name, val, product_id).await;
             res.refetch();
             new_item_quantity.set("".to_owned());
             new_item_name.set("".to_owned());
-            new_item_value.set(0);
+            new_item_value.set("".to_owned());
+            new_item_product.set(None);
+        });
+    };
+    let change_status = move |order_id: i32, status: OrderStatus| {
+        spawn_local(async move {
+            fetch_update_safe(
+                order_id,
+                version_val,
+                receiver_val,
+                additional_val,
+                customer,
+            )
+            .await;
+            if let Err(e) = fetch_order_status(order_id, status).await {
+                use_message().create(e.to_string(), MessageVariant::Error, Default::default());
+            }
+            res.refetch();
         });
     };
-    let delete_order = move |order_id:i32|{
-        spawn_local(async move{
-            fetch_order_delete_safe(order_id).await;
+    let duplicate_order = move |order_id: i32| {
+        spawn_local(async move {
+            fetch_update_safe(
+                order_id,
+                version_val,
+                receiver_val,
+                additional_val,
+                customer,
+            )
+            .await;
+            match fetch_order_duplicate(order_id).await {
+                Ok(id) => {
+                    let nav = use_navigate();
+                    nav(&format!("/orders/{id}"), Default::default());
+                }
+                Err(e) => {
+                    use_message().create(e.to_string(), MessageVariant::Error, Default::default())
+                }
+            }
+        });
+    };
+    let ctx = expect_context::<Context>();
+    let full = move || ctx.privileges.get() == "Full";
+    let save_template = move |order_id: i32| {
+        spawn_local(async move {
+            fetch_update_safe(
+                order_id,
+                version_val,
+                receiver_val,
+                additional_val,
+                customer,
+            )
+            .await;
+            let message = match fetch_template_create(order_id, receiver_val.get_untracked()).await
+            {
+                Ok(()) => ("Zapisano szablon".to_owned(), MessageVariant::Success),
+                Err(e) => (e.to_string(), MessageVariant::Error),
+            };
+            use_message().create(message.0, message.1, Default::default());
+        });
+    };
+    let delete_order = move |order_id: i32| {
+        spawn_local(async move {
+            fetch_order_delete_safe(order_id, version_val.get_untracked()).await;
             let nav = use_navigate();
-            nav("/orders",Default::default());  
+            nav("/orders", Default::default());
         });
     };
 
@@ -102,7 +297,6 @@ pub fn OrderView() -> impl IntoView {
                 view! {<Space justify=SpaceJustify::Center><Spinner /></Space>}.into_view()
             }
             Some(Some(order)) => {
-                let order_b = order.clone();
                 view! {
                 <Style>"
                 .checkbox{
@@ -126,20 +320,41 @@ pub fn OrderView() -> impl IntoView {
                     color: #c2c2c2 !important;
                 }
             "</Style>
-                <Card title={"Zamówienie nr. ".to_owned()+&order.id.to_string()}>
+                <Card title={"Zamówienie nr. ".to_owned()+&order.number}>
                 <Space vertical=true>
                     <Space align=SpaceAlign::Center justify=SpaceJustify::SpaceBetween>
-                    <Text>"Odbiorca: "</Text> <Input value=receiver_val/>
+                    <Text>"Odbiorca: "</Text>
+                    <AutoComplete
+                        value=receiver_val
+                        options=Signal::derive(customer_options)
+                        on_select=select_customer
+                        allow_free_input=true />
                     </Space>
                     <Space align=SpaceAlign::Center  justify=SpaceJustify::SpaceBetween>
                     <Text>"Dopis: "</Text> <Input value=additional_val/>
                     </Space>
+                    <Space align=SpaceAlign::Center>
+                    <Text>"Status: "{order.status.label()}</Text>
+                    {order.status.next(order.picked_up).into_iter().map(|status|view!{
+                        <Button on_click=move|_|change_status(order.id,status) variant=ButtonVariant::Outlined>
+                            {status.label()}
+                        </Button>
+                    }).collect::<Vec<_>>()}
+                    </Space>
                 </Space>
                 <br/>
-                <p>"Przedmioty: "</p>
+                <Space align=SpaceAlign::Center>
+                    <p>"Przedmioty: "</p>
+                    <Button on_click=move|_|check_all(order.id,true) variant=ButtonVariant::Text>"Zaznacz wszystkie"</Button>
+                    <Button on_click=move|_|check_all(order.id,false) variant=ButtonVariant::Text>"Odznacz wszystkie"</Button>
+                </Space>
                 <Space vertical=true>
                 // rows
-                {move||{order.items.clone().iter().cloned().enumerate().map(|(index,item)|view!{
+                {move||{
+                let item_ids: Vec<i32> = order.items.iter().map(|item| item.id).collect();
+                order.items.clone().iter().cloned().enumerate().map(|(index,item)|{
+                let (up_ids, down_ids) = (item_ids.clone(), item_ids.clone());
+                view!{
                     <div style="margin-top:5px"></div>
                     <div class:striped=move||item.checked>
                         <Space align=SpaceAlign::Center class="inputs">
@@ -148,7 +363,7 @@ pub fn OrderView() -> impl IntoView {
                                 type="checkbox"
                                 class="checkbox"
                                 checked=move||{item.checked}
-                                on:click=move|_|update_check(order.id,index)
+                                on:click=move|_|update_check(order.id,item.id,item.version,!item.checked)
                             ></input>
 
                             // quantity
@@ -165,18 +380,26 @@ pub fn OrderView() -> impl IntoView {
                             <div class="thaw-input thaw-input--disabled" style="--thaw-background-color-disabled: #fafafc;">
                                 <input
                                 disabled
-                                value=move||format!("{:.2} zł",item.value as f32/100f32)
+                                value=move||item.value.to_string()
                                 class="thaw-input__input-el"
                                 placeholder="bejca" />
                             </div>
 
+                            // move
+                            <Button on_click=move|_|move_item(order.id,up_ids.clone(),index,-1) variant=ButtonVariant::Text>
+                                <Icon width="15px" icon=i::AiUpOutlined/>
+                            </Button>
+                            <Button on_click=move|_|move_item(order.id,down_ids.clone(),index,1) variant=ButtonVariant::Text>
+                                <Icon width="15px" icon=i::AiDownOutlined/>
+                            </Button>
+
                             // delete
-                            <Button on_click=move|_|delete_item(order.id,item.id) variant=ButtonVariant::Outlined>
+                            <Button on_click=move|_|delete_item(order.id,item.id,item.version) variant=ButtonVariant::Outlined>
                                 <Icon width="15px" icon=i::RiDeleteBin5SystemLine/>
                             </Button>
                         </Space>
                     </div>
-                }).collect::<Vec<_>>()}}
+                }}).collect::<Vec<_>>()}}
                 //row for add
                 <div>
                     <Space align=SpaceAlign::Center class="inputs">
@@ -185,17 +408,13 @@ pub fn OrderView() -> impl IntoView {
                         // <div style="width:20px; height:20px;content:'';"></div>
                         <Icon icon=i::AiRightOutlined width="20px" />
                         <Input value=new_item_quantity placeholder="1kg" />
-                        <Input value=new_item_name placeholder="lakier" />
-                        <div class="thaw-input thaw-input" style="">
-                            <input 
-                                on:input=move|ev|{
-                                    let value = event_target_value(&ev).parse::<f32>().unwrap_or(0.0f32);
-                                    let int_value = (value * 100f32) as i32;
-                                    new_item_value.set(int_value);
-                                }
-                                class="thaw-input__input-el"
-                                placeholder="100.00zł" />
-                        </div>
+                        <AutoComplete
+                            value=new_item_name
+                            options=Signal::derive(product_options)
+                            on_select=select_product
+                            allow_free_input=true
+                            placeholder="lakier" />
+                        <Input value=new_item_value placeholder="100,00zł" />
 
                         // add
                         <Button on:click=move|_|add_item(order.id) color=ButtonColor::Success>
@@ -206,19 +425,28 @@ pub fn OrderView() -> impl IntoView {
                 </Space>
                 <br/>
 
-                <Space>
-                    "Suma: "
-                    {move||format!("{:.2} zł",get_order_sum_value(order_b.items.clone()) as f32 / 100.0f32)}
+                <Space vertical=true>
+                    {(order.totals.discount.amount != 0).then(||view!{
+                        <Text>"Rabat: "{order.totals.discount.to_string()}</Text>
+                    })}
+                    <Text>"Suma: "{order.totals.gross.to_string()}</Text>
+                    <Text>"Netto: "{order.totals.net.to_string()}" VAT: "{order.totals.vat.to_string()}</Text>
                 </Space>
                 <br/>
-                <Button on_click=move|_|delete_order(order.id) color=ButtonColor::Error>"Usuń"</Button>
+                <Space>
+                    <Button on_click=move|_|delete_order(order.id) color=ButtonColor::Error>"Usuń"</Button>
+                    <Button on_click=move|_|duplicate_order(order.id) variant=ButtonVariant::Outlined>"Powtórz"</Button>
+                    {move||full().then(||view!{
+                        <Button on_click=move|_|save_template(order.id) variant=ButtonVariant::Outlined>"Zapisz jako szablon"</Button>
+                    })}
+                </Space>
                 // divider and back button
                 <Divider/>
                 <Space justify=SpaceJustify::Center>
 
                 <Button on:click=move|_|{
                     spawn_local(async move{
-                        fetch_update_safe(order.id,receiver_val,additional_val).await;
+                        fetch_update_safe(order.id,version_val,receiver_val,additional_val,customer).await;
                         let nav = use_navigate();
                         nav("/orders",Default::default());
                     });
@@ -236,13 +464,21 @@ pub fn OrderView() -> impl IntoView {
     }
 }
 
-async fn fetch_item_delete(order_id: i32, item_id: i32) -> anyhow::Result<()> {
+// answer to changes of an order someone else changed since it was fetched
+const CONFLICT_MESSAGE: &str =
+    "Zamówienie zostało zmienione przez kogoś innego, sprawdź je i spróbuj ponownie";
+
+async fn fetch_item_delete(order_id: i32, item_id: i32, version: i32) -> anyhow::Result<()> {
     let client = reqwest::Client::new();
     let res = client
         .delete(format!("{}/orders/{order_id}/items/{item_id}", API_PATH))
+        .header("If-Match", format!("\"{version}\""))
         .fetch_credentials_include()
         .send()
         .await?;
+    if res.status() == StatusCode::CONFLICT {
+        bail!(CONFLICT_MESSAGE);
+    }
     if res.status() != StatusCode::OK {
         let e = res.text().await?;
         bail!(e.to_string());
@@ -250,17 +486,20 @@ async fn fetch_item_delete(order_id: i32, item_id: i32) -> anyhow::Result<()> {
     Ok(())
 }
 
-async fn fetch_item_delete_safe(order_id: i32, item_id: i32) {
-    let _ = fetch_item_delete(order_id, item_id).await;
+async fn fetch_item_delete_safe(order_id: i32, item_id: i32, version: i32) {
+    if let Err(e) = fetch_item_delete(order_id, item_id, version).await {
+        use_message().create(e.to_string(), MessageVariant::Error, Default::default());
+    }
 }
 
 async fn fetch_new_item_safe(
     order_id: i32,
     quan: RwSignal<String>,
     name: RwSignal<String>,
-    val: i32,
+    val: Money,
+    product_id: Option<i32>,
 ) {
-    if let Err(e) = fetch_new_item(order_id, quan, name, val).await {
+    if let Err(e) = fetch_new_item(order_id, quan, name, val, product_id).await {
         // use_message().create(
         //     e.to_string(),
         //     thaw::MessageVariant::Error,
@@ -273,7 +512,8 @@ async fn fetch_new_item(
     order_id: i32,
     quan: RwSignal<String>,
     name: RwSignal<String>,
-    val: i32,
+    val: Money,
+    product_id: Option<i32>,
 ) -> anyhow::Result<()> {
     let client = reqwest::Client::new();
     let res = client
@@ -282,6 +522,7 @@ async fn fetch_new_item(
             "quantity": quan.get_untracked(),
             "name": name.get_untracked(),
             "value": val,
+            "product_id": product_id,
         }))
         .fetch_credentials_include()
         .send()
@@ -293,6 +534,38 @@ async fn fetch_new_item(
     Ok(())
 }
 
+async fn fetch_products(query: String) -> anyhow::Result<Vec<ProductResponseBasic>> {
+    let client = reqwest::Client::new();
+    let res = client
+        .get(format!("{}/products", API_PATH))
+        .query(&[("q", query)])
+        .fetch_credentials_include()
+        .send()
+        .await?;
+    if res.status() != StatusCode::OK {
+        let e = res.text().await?;
+        bail!(e.to_string());
+    }
+    let json = res.json().await?;
+    Ok(json)
+}
+
+async fn fetch_customers(query: String) -> anyhow::Result<Vec<CustomerResponseBasic>> {
+    let client = reqwest::Client::new();
+    let res = client
+        .get(format!("{}/customers", API_PATH))
+        .query(&[("q", query)])
+        .fetch_credentials_include()
+        .send()
+        .await?;
+    if res.status() != StatusCode::OK {
+        let e = res.text().await?;
+        bail!(e.to_string());
+    }
+    let json = res.json().await?;
+    Ok(json)
+}
+
 async fn fetch_order_safe(id: i32) -> Option<OrderResponseBasic> {
     match fetch_order(id).await {
         Ok(s) => Some(s),
@@ -324,8 +597,12 @@ async fn fetch_order(id: i32) -> anyhow::Result<OrderResponseBasic> {
     Ok(json)
 }
 
-async fn fetch_item_check_safe(order_id: i32, item_id: i32, value: bool) {
-    if let Err(e) = fetch_item_check(order_id, item_id, value).await {
+async fn fetch_item_check_safe(order_id: i32, item_id: i32, version: i32, value: bool) {
+    if let Err(e) = fetch_item_check(order_id, item_id, version, value).await {
+        if e.to_string() == CONFLICT_MESSAGE {
+            use_message().create(e.to_string(), MessageVariant::Error, Default::default());
+            return;
+        }
         // use_message().create(
         //     e.to_string(),
         //     thaw::MessageVariant::Error,
@@ -337,38 +614,37 @@ async fn fetch_item_check_safe(order_id: i32, item_id: i32, value: bool) {
 }
 async fn fetch_update_safe(
     order_id: i32,
+    version: RwSignal<i32>,
     receiver: RwSignal<String>,
     additional_info: RwSignal<String>,
+    customer: RwSignal<Option<CustomerResponseBasic>>,
 ) {
-    if let Err(_) = fetch_update(order_id, receiver, additional_info).await {}
+    if let Err(e) = fetch_update(order_id, version, receiver, additional_info, customer).await {
+        if e.to_string() == CONFLICT_MESSAGE {
+            use_message().create(e.to_string(), MessageVariant::Error, Default::default());
+        }
+    }
 }
 
-// item_index in order
-async fn fetch_item_check(order_id: i32, item_index: i32, value: bool) -> anyhow::Result<()> {
+async fn fetch_item_check(
+    order_id: i32,
+    item_id: i32,
+    version: i32,
+    value: bool,
+) -> anyhow::Result<()> {
     let client = reqwest::Client::new();
-    let res = client
-        .get(format!("{}/orders/{order_id}", API_PATH))
-        .fetch_credentials_include()
-        .send()
-        .await?;
-    if res.status() != StatusCode::OK {
-        let e = res.text().await?;
-        bail!(e.to_string());
-    }
-    let json: OrderResponseBasic = res.json().await?;
-    let item_id = json
-        .items
-        .get(item_index as usize)
-        .context("failed to index item")?
-        .id;
     let res = client
         .patch(format!("{}/orders/{order_id}/items/{item_id}", API_PATH))
         .fetch_credentials_include()
         .json(&json!({
-            "checked": value
+            "checked": value,
+            "version": version,
         }))
         .send()
         .await?;
+    if res.status() == StatusCode::CONFLICT {
+        bail!(CONFLICT_MESSAGE);
+    }
     if res.status() != StatusCode::OK {
         let e = res.text().await?;
         bail!(e.to_string());
@@ -378,9 +654,16 @@ async fn fetch_item_check(order_id: i32, item_index: i32, value: bool) -> anyhow
 
 async fn fetch_update(
     order_id: i32,
+    version: RwSignal<i32>,
     receiver: RwSignal<String>,
     additional_info: RwSignal<String>,
+    customer: RwSignal<Option<CustomerResponseBasic>>,
 ) -> anyhow::Result<()> {
+    // the customer is only linked if the receiver wasn't edited after picking it
+    let customer_id = customer
+        .get_untracked()
+        .filter(|customer| customer.name == receiver.get_untracked())
+        .map(|customer| customer.id);
     let client = reqwest::Client::new();
     // request for updating order
     let res = client
@@ -389,9 +672,14 @@ async fn fetch_update(
         .json(&json!({
             "receiver": receiver.get_untracked(),
             "additional_info": additional_info.get_untracked(),
+            "customer_id": customer_id,
+            "version": version.get_untracked(),
         }))
         .send()
         .await?;
+    if res.status() == StatusCode::CONFLICT {
+        bail!(CONFLICT_MESSAGE);
+    }
     if res.status() != StatusCode::OK {
         let e = res.text().await?;
         bail!(e.to_string());
@@ -399,34 +687,108 @@ async fn fetch_update(
     Ok(())
 }
 
-fn get_number_from_string(s: String) -> i32 {
-    let numb = s
-        .chars()
-        .filter(|e| e.is_ascii_digit())
-        .collect::<String>()
-        .parse();
-    numb.unwrap_or(0)
+async fn fetch_order_status(order_id: i32, status: OrderStatus) -> anyhow::Result<()> {
+    let client = reqwest::Client::new();
+    let res = client
+        .post(format!("{}/orders/{order_id}/status", API_PATH))
+        .fetch_credentials_include()
+        .json(&json!({ "status": status }))
+        .send()
+        .await?;
+    if res.status() != StatusCode::OK {
+        let e = res.text().await?;
+        bail!(e.to_string());
+    }
+    Ok(())
+}
+
+async fn fetch_order_delete_safe(order_id: i32, version: i32) {
+    if let Err(e) = fetch_order_delete(order_id, version).await {
+        use_message().create(e.to_string(), MessageVariant::Error, Default::default());
+    }
+}
+async fn fetch_order_delete(order_id: i32, version: i32) -> anyhow::Result<()> {
+    let client = reqwest::Client::new();
+    let res = client
+        .delete(format!("{}/orders/{order_id}", API_PATH))
+        .header("If-Match", format!("\"{version}\""))
+        .fetch_credentials_include()
+        .send()
+        .await?;
+    if res.status() == StatusCode::CONFLICT {
+        bail!(CONFLICT_MESSAGE);
+    }
+
+    if res.status() != StatusCode::OK {
+        let e = res.text().await?;
+        bail!(e.to_string());
+    }
+    Ok(())
 }
-pub fn get_order_sum_value(items: Vec<ItemResponseBasic>) -> i32 {
-    items
-        .iter()
-        .map(|item| get_number_from_string(item.quantity.to_string()) * item.value)
-        .sum()
+
+// returns the id of the new order
+async fn fetch_order_duplicate(order_id: i32) -> anyhow::Result<i32> {
+    let client = reqwest::Client::new();
+    let res = client
+        .post(format!("{}/orders/{order_id}/duplicate", API_PATH))
+        .fetch_credentials_include()
+        .send()
+        .await?;
+    if res.status() != StatusCode::OK {
+        let e = res.text().await?;
+        bail!(e.to_string());
+    }
+    #[derive(serde::Deserialize)]
+    struct Output {
+        id: i32,
+    }
+    let json: Output = res.json().await?;
+    Ok(json.id)
 }
 
-async fn fetch_order_delete_safe(order_id: i32){
-    let _ = fetch_order_delete(order_id).await;
+// templates are named after the receiver
+async fn fetch_template_create(order_id: i32, name: String) -> anyhow::Result<()> {
+    let client = reqwest::Client::new();
+    let res = client
+        .post(format!("{}/templates", API_PATH))
+        .fetch_credentials_include()
+        .json(&json!({ "name": name, "order_id": order_id }))
+        .send()
+        .await?;
+    if res.status() != StatusCode::OK {
+        let e = res.text().await?;
+        bail!(e.to_string());
+    }
+    Ok(())
 }
-async fn fetch_order_delete(order_id: i32) -> anyhow::Result<()>{
+
+async fn fetch_items_check_all(order_id: i32, checked: bool) -> anyhow::Result<()> {
     let client = reqwest::Client::new();
-    let res = client.delete(format!("{}/orders/{order_id}",API_PATH)).
-        fetch_credentials_include()
+    let res = client
+        .post(format!("{}/orders/{order_id}/items/check", API_PATH))
+        .fetch_credentials_include()
+        .json(&json!({ "checked": checked }))
         .send()
         .await?;
+    if res.status() != StatusCode::OK {
+        let e = res.text().await?;
+        bail!(e.to_string());
+    }
+    Ok(())
+}
 
+// item_ids lists every item of the order in the new order
+async fn fetch_items_reorder(order_id: i32, item_ids: Vec<i32>) -> anyhow::Result<()> {
+    let client = reqwest::Client::new();
+    let res = client
+        .post(format!("{}/orders/{order_id}/items/reorder", API_PATH))
+        .fetch_credentials_include()
+        .json(&json!({ "item_ids": item_ids }))
+        .send()
+        .await?;
     if res.status() != StatusCode::OK {
         let e = res.text().await?;
         bail!(e.to_string());
     }
-    Ok(())    
+    Ok(())
 }
@@ -0,0 +1,3 @@
+// Code the backend and the frontend both need to agree on, plain Rust for the server and wasm
+
+pub mod money;
@@ -0,0 +1,44 @@
+// Formatting of money amounts, Money itself is declared on each side
+
+// pl-PL: "12 345,67 zł", digits are grouped only from 5 digits up ("1234,56 zł").
+// amount is in minor units, for example 100_00 = 100PLN
+pub fn format(amount: i64, symbol: &str) -> String {
+    let sign = if amount < 0 { "-" } else { "" };
+    let abs = amount.unsigned_abs();
+    let whole = (abs / 100).to_string();
+    let rem = abs % 100;
+
+    let mut grouped = String::new();
+    for (i, digit) in whole.chars().enumerate() {
+        if whole.len() > 4 && i > 0 && (whole.len() - i).is_multiple_of(3) {
+            grouped.push('\u{a0}');
+        }
+        grouped.push(digit);
+    }
+    format!("{sign}{grouped},{rem:02}\u{a0}{symbol}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn money_format() {
+        assert_eq!(format(0, "zł"), "0,00\u{a0}zł");
+        assert_eq!(format(5, "zł"), "0,05\u{a0}zł");
+        assert_eq!(format(10000, "zł"), "100,00\u{a0}zł");
+        assert_eq!(format(123456, "zł"), "1234,56\u{a0}zł");
+        assert_eq!(format(1234567, "zł"), "12\u{a0}345,67\u{a0}zł");
+        assert_eq!(format(123456789, "zł"), "1\u{a0}234\u{a0}567,89\u{a0}zł");
+    }
+
+    #[test]
+    fn money_format_negative() {
+        assert_eq!(format(-5, "zł"), "-0,05\u{a0}zł");
+        assert_eq!(format(-15000, "zł"), "-150,00\u{a0}zł");
+        assert_eq!(
+            format(i64::MIN, "zł"),
+            "-92\u{a0}233\u{a0}720\u{a0}368\u{a0}547\u{a0}758,08\u{a0}zł"
+        );
+    }
+}